@@ -0,0 +1,318 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::core::geo::polygon::*;
+
+use std::f64::consts::PI;
+
+// Points closer together than this are taken to be the same point, which is what lets sides that
+// were cut at the same crossing be joined back up
+pub const BOOLEAN_SNAP_DISTANCE: Scalar = 1e-9;
+
+// How far either side of a piece of outline the regions are looked at, to tell whether it's on
+// the boundary of the result
+const SAMPLE_OFFSET: Scalar = 1e-7;
+
+type Side = (Vector2, Vector2);
+
+fn is_same_point(a: Vector2, b: Vector2) -> bool {
+    (a - b).length_squared() <= BOOLEAN_SNAP_DISTANCE * BOOLEAN_SNAP_DISTANCE
+}
+
+fn sides(polygon: &Polygon) -> Vec<Side> {
+    let count = polygon.num_sides();
+    (0..count)
+        .map(|i| (polygon.get(i), polygon.get((i + 1) % count)))
+        .filter(|(a, b)| !is_same_point(*a, *b))
+        .collect()
+}
+
+// How many times the sides wind counterclockwise around the point
+fn winding_number(point: Vector2, sides: &Vec<Side>) -> i32 {
+    let mut winding = 0;
+    for (a, b) in sides {
+        if a.y <= point.y {
+            if b.y > point.y && cross(*b - *a, point - *a) > 0.0 {
+                winding += 1;
+            }
+        } else if b.y <= point.y && cross(*b - *a, point - *a) < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+// Points where two sides meet. Points close to the end of either side are moved onto it, so a
+// vertex lying on another side cuts it at exactly that vertex
+fn side_crossings((a, b): Side, (c, d): Side) -> Vec<Vector2> {
+    if a.x.max(b.x) + BOOLEAN_SNAP_DISTANCE < c.x.min(d.x) || c.x.max(d.x) + BOOLEAN_SNAP_DISTANCE < a.x.min(b.x) ||
+        a.y.max(b.y) + BOOLEAN_SNAP_DISTANCE < c.y.min(d.y) || c.y.max(d.y) + BOOLEAN_SNAP_DISTANCE < a.y.min(b.y) {
+        return vec!();
+    }
+
+    let r = b - a;
+    let s = d - c;
+    let denominator = cross(r, s);
+
+    if denominator.abs() <= 1e-12 * r.length() * s.length() {
+        // Parallel, so they only meet if they lie along the same line, at the ends of the overlap
+        if cross(r, c - a).abs() > BOOLEAN_SNAP_DISTANCE * r.length() {
+            return vec!();
+        }
+        let mut out = vec!();
+        for point in &[c, d] {
+            let along = (*point - a).dot(r) / r.length_squared();
+            if 0.0 <= along && along <= 1.0 {
+                out.push(*point);
+            }
+        }
+        for point in &[a, b] {
+            let along = (*point - c).dot(s) / s.length_squared();
+            if 0.0 <= along && along <= 1.0 {
+                out.push(*point);
+            }
+        }
+        return out;
+    }
+
+    let t = cross(c - a, s) / denominator;
+    let u = cross(c - a, r) / denominator;
+    let (t_margin, u_margin) = (BOOLEAN_SNAP_DISTANCE / r.length(), BOOLEAN_SNAP_DISTANCE / s.length());
+    if t < -t_margin || t > 1.0 + t_margin || u < -u_margin || u > 1.0 + u_margin {
+        return vec!();
+    }
+
+    let point = a + t * r;
+    match [a, b, c, d].iter().find(|end| is_same_point(point, **end)) {
+        Some(end) => vec![*end],
+        None => vec![point],
+    }
+}
+
+// Cut every side at every point where it meets another, so that no two pieces cross
+fn split_sides(sides: &Vec<Side>) -> Vec<Side> {
+    let mut cuts: Vec<Vec<Vector2>> = vec![vec!(); sides.len()];
+    for i in 0..sides.len() {
+        for j in (i + 1)..sides.len() {
+            for point in side_crossings(sides[i], sides[j]) {
+                cuts[i].push(point);
+                cuts[j].push(point);
+            }
+        }
+    }
+
+    let mut pieces = vec!();
+    for (i, (a, b)) in sides.iter().enumerate() {
+        let direction = *b - *a;
+        let mut points = cuts[i].clone();
+        points.push(*a);
+        points.push(*b);
+        points.sort_by(|p, q| (*p - *a).dot(direction).partial_cmp(&(*q - *a).dot(direction)).unwrap());
+        points.dedup_by(|p, q| is_same_point(*p, *q));
+        for k in 1..points.len() {
+            pieces.push((points[k - 1], points[k]));
+        }
+    }
+    pieces
+}
+
+// Follow the pieces round into closed outlines. Where more than one piece carries on from a
+// point, the one turning furthest left is taken, which keeps outlines that only touch at a corner
+// apart. Pieces that don't lead back round are dropped
+fn join_pieces(pieces: &Vec<Side>) -> Vec<Polygon> {
+    let mut used = vec![false; pieces.len()];
+    let mut outlines = vec!();
+
+    for first in 0..pieces.len() {
+        if used[first] {
+            continue;
+        }
+
+        let mut vertices = vec!();
+        let mut current = first;
+        used[first] = true;
+        let closed = loop {
+            let (start, end) = pieces[current];
+            vertices.push(start);
+
+            let incoming = end - start;
+            let mut best = None;
+            let mut best_turn = NEG_INFINITY;
+            for (i, (next_start, next_end)) in pieces.iter().enumerate() {
+                if (used[i] && i != first) || !is_same_point(*next_start, end) {
+                    continue;
+                }
+                let outgoing = *next_end - *next_start;
+                let mut turn = cross(incoming, outgoing).atan2(incoming.dot(outgoing));
+                // Turning straight back is as far right as it can go, not left
+                if turn >= PI {
+                    turn = -PI;
+                }
+                if turn > best_turn {
+                    best_turn = turn;
+                    best = Some(i);
+                }
+            }
+
+            match best {
+                Some(next) if next == first => break true,
+                Some(next) => {
+                    used[next] = true;
+                    current = next;
+                }
+                None => break false,
+            }
+        };
+
+        if closed {
+            let outline = Polygon(vertices).simplify(0.0);
+            if outline.num_sides() >= 3 && outline.area() > BOOLEAN_SNAP_DISTANCE {
+                outlines.push(outline);
+            }
+        }
+    }
+    outlines
+}
+
+// Outline the region picked out by `inside`, which is given how many times each polygon winds
+// around a point. Every side is cut where it meets the others, and each piece is kept if the
+// region is on one side of it and not the other, turned round so the region is on its left
+fn outline_region<F: Fn(&Vec<i32>) -> bool>(polygons: &Vec<Polygon>, inside: F) -> Vec<Polygon> {
+    let polygon_sides: Vec<Vec<Side>> = polygons.iter().map(sides).collect();
+    let all_sides: Vec<Side> = polygon_sides.concat();
+    let windings_at = |point: Vector2| -> Vec<i32> {
+        polygon_sides.iter().map(|sides| winding_number(point, sides)).collect()
+    };
+
+    let mut kept: Vec<Side> = vec!();
+    for (a, b) in split_sides(&all_sides) {
+        let direction = (b - a).normalize();
+        let left = Vector2 { x: -direction.y, y: direction.x };
+        let middle = 0.5 * (a + b);
+        let left_inside = inside(&windings_at(middle + SAMPLE_OFFSET * left));
+        let right_inside = inside(&windings_at(middle - SAMPLE_OFFSET * left));
+
+        let piece = match (left_inside, right_inside) {
+            (true, false) => (a, b),
+            (false, true) => (b, a),
+            _ => continue,
+        };
+
+        // Sides the polygons share are only wanted once
+        if !kept.iter().any(|(c, d)| is_same_point(*c, piece.0) && is_same_point(*d, piece.1)) {
+            kept.push(piece);
+        }
+    }
+
+    join_pieces(&kept)
+}
+
+fn counterclockwise(polygon: &Polygon) -> Polygon {
+    let mut polygon = polygon.clone();
+    polygon.make_counterclockwise();
+    polygon
+}
+
+// The area covered by any of the polygons, such as buildings drawn over each other. Results are
+// counterclockwise outlines, with clockwise holes where the polygons enclose space between them
+pub fn union_all(polygons: &Vec<Polygon>) -> Vec<Polygon> {
+    let polygons: Vec<Polygon> = polygons.iter().map(counterclockwise).collect();
+    outline_region(&polygons, |windings| windings.iter().any(|winding| *winding > 0))
+}
+
+// The area covered by both polygons
+pub fn intersection(a: &Polygon, b: &Polygon) -> Vec<Polygon> {
+    outline_region(&vec![counterclockwise(a), counterclockwise(b)], |windings| windings[0] > 0 && windings[1] > 0)
+}
+
+// Split a polygon that crosses over itself into simple ones covering the area it winds
+// counterclockwise around. Loops that wind the other way, like the ones left by offsetting a
+// polygon inwards by more than it is thick, are dropped
+pub fn remove_self_intersections(polygon: &Polygon) -> Vec<Polygon> {
+    outline_region(&vec![polygon.clone()], |windings| windings[0] > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::geo::polygon::tests::*;
+    use rand::Rng;
+
+    // Area of the outlines less the holes in them
+    fn net_area(region: &Vec<Polygon>) -> Scalar {
+        region.iter().map(|polygon| polygon.signed_area()).sum()
+    }
+
+    fn region_contains_point(region: &Vec<Polygon>, point: Vector2) -> bool {
+        let winding: i32 = region.iter()
+            .filter(|polygon| polygon.contains_point(point))
+            .map(|polygon| if polygon.is_counterclockwise() { 1 } else { -1 })
+            .sum();
+        winding > 0
+    }
+
+    // Two polygons that overlap more often than not
+    fn overlapping_polygons<R: Rng>(rng: &mut R) -> (Polygon, Polygon) {
+        let offset = vector2(rng.gen_range(-8.0, 8.0), rng.gen_range(-8.0, 8.0));
+        (random_star_polygon(rng, vector2(0.0, 0.0), 10.0), random_star_polygon(rng, offset, 10.0))
+    }
+
+    #[test]
+    fn union_and_intersection_agree_with_inclusion_exclusion() {
+        let mut rng = test_rng(2);
+        for _ in 0..RANDOM_POLYGON_CASES {
+            let (a, b) = overlapping_polygons(&mut rng);
+
+            let union = net_area(&union_all(&vec![a.clone(), b.clone()]));
+            let overlap = net_area(&intersection(&a, &b));
+            assert!(overlap >= 0.0 && overlap <= a.area().min(b.area()) + 1e-6);
+            assert!((union + overlap - a.area() - b.area()).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn union_and_intersection_cover_the_right_points() {
+        let mut rng = test_rng(3);
+        for _ in 0..RANDOM_POLYGON_CASES {
+            let (a, b) = overlapping_polygons(&mut rng);
+            let union = union_all(&vec![a.clone(), b.clone()]);
+            let overlap = intersection(&a, &b);
+
+            for _ in 0..50 {
+                let point = vector2(rng.gen_range(-20.0, 20.0), rng.gen_range(-20.0, 20.0));
+                let (in_a, in_b) = (a.contains_point(point), b.contains_point(point));
+                assert_eq!(region_contains_point(&union, point), in_a || in_b);
+                assert_eq!(region_contains_point(&overlap, point), in_a && in_b);
+            }
+        }
+    }
+
+    #[test]
+    fn polygons_that_only_touch_have_no_intersection() {
+        let a = Polygon(vec![vector2(0.0, 0.0), vector2(1.0, 0.0), vector2(1.0, 1.0), vector2(0.0, 1.0)]);
+        let b = Polygon(vec![vector2(1.0, 0.0), vector2(2.0, 0.0), vector2(2.0, 1.0), vector2(1.0, 1.0)]);
+        assert!(intersection(&a, &b).is_empty());
+
+        let union = union_all(&vec![a, b]);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].num_sides(), 4);
+        assert!((union[0].signed_area() - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn removing_self_intersections_preserves_area() {
+        let mut rng = test_rng(4);
+        for _ in 0..RANDOM_POLYGON_CASES {
+            let mut polygon = random_star_polygon(&mut rng, vector2(0.0, 0.0), 10.0);
+            polygon.make_counterclockwise();
+            let pieces = remove_self_intersections(&polygon);
+            assert_eq!(pieces.len(), 1);
+            assert!((pieces[0].area() - polygon.area()).abs() < 1e-9 * polygon.area());
+        }
+
+        // A bow tie comes apart into its two triangles, and only the counterclockwise one is kept
+        let bow_tie = Polygon(vec![vector2(0.0, 0.0), vector2(2.0, 0.0), vector2(0.0, 2.0), vector2(2.0, 2.0)]);
+        let pieces = remove_self_intersections(&bow_tie);
+        assert_eq!(pieces.len(), 1);
+        assert!((pieces[0].signed_area() - 1.0).abs() < 1e-12);
+    }
+}
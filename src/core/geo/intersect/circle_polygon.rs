@@ -0,0 +1,140 @@
+use crate::core::scalar::*;
+use crate::core::geo::circle::*;
+use crate::core::geo::polygon::*;
+use crate::core::geo::segment2::*;
+
+// How a circle overlaps a polygon, and which way to move it to separate them
+#[derive(Copy, Clone, Debug)]
+pub struct Contact {
+    // Unit direction to push the circle out of the polygon
+    pub normal: Vector2,
+    // How far the circle has to move along the normal to stop overlapping
+    pub depth: Scalar,
+    // Point on the polygon's outline closest to the centre of the circle
+    pub point: Vector2,
+    // Whether that point is a corner of the polygon, in which case the normal points from the
+    // corner to the centre, rather than straight out of a side
+    pub is_vertex: bool,
+}
+
+// Whether the point is inside the polygon, counting crossings of a ray to the right of it. Unlike
+// Polygon::contains_point this doesn't miscount when the ray goes through a vertex
+fn is_inside(polygon: &Polygon, point: Vector2) -> bool {
+    let sides = polygon.num_sides();
+    let mut inside = false;
+    for i in 0..sides {
+        let (a, b) = (polygon.get(i), polygon.get((i + 1) % sides));
+        if (a.y > point.y) != (b.y > point.y) &&
+            point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// Find how the circle and polygon overlap, if they do. Works whether the centre of the circle is
+// outside the polygon or has already got inside it, and for concave polygons
+pub fn circle_polygon_contact(circle: &Circle, polygon: &Polygon) -> Option<Contact> {
+    let sides = polygon.num_sides();
+    if sides < 3 {
+        return None;
+    }
+
+    // Closest point on the outline, and the side it's on
+    let mut closest = polygon.get(0);
+    let mut closest_side = 0;
+    let mut closest_distance_squared = INFINITY;
+    let mut is_vertex = true;
+    for i in 0..sides {
+        let side = Segment2 { p1: polygon.get(i), p2: polygon.get((i + 1) % sides) };
+        let point = side.nearest_point_on_segment_to_point(circle.center);
+        let distance_squared = (point - circle.center).length_squared();
+        if distance_squared < closest_distance_squared {
+            closest = point;
+            closest_side = i;
+            closest_distance_squared = distance_squared;
+            is_vertex = point == side.p1 || point == side.p2;
+        }
+    }
+
+    let inside = is_inside(polygon, circle.center);
+    if !inside && closest_distance_squared >= circle.radius * circle.radius {
+        return None;
+    }
+
+    let distance = closest_distance_squared.sqrt();
+    let normal = if distance > 0.0 {
+        let to_centre = (circle.center - closest) / distance;
+        if inside { -to_centre } else { to_centre }
+    } else {
+        // Centre right on the outline, so go straight out of the side it's on
+        let side = polygon.get((closest_side + 1) % sides) - polygon.get(closest_side);
+        let winding = if polygon.signed_area() < 0.0 { -1.0 } else { 1.0 };
+        winding * side.right().normalize()
+    };
+
+    Some(Contact {
+        normal,
+        depth: if inside { circle.radius + distance } else { circle.radius - distance },
+        point: closest,
+        is_vertex,
+    })
+}
+
+// How far along the way from start to end, between 0 and 1, a circle of the radius first touches
+// the polygon. Circles already touching it at the start are left to circle_polygon_contact, so
+// this only catches ones moving fast enough to go into or through the polygon in one step
+pub fn circle_polygon_time_of_impact(start: Vector2, end: Vector2, radius: Scalar, polygon: &Polygon) -> Option<Scalar> {
+    let movement = end - start;
+    let movement_squared = movement.length_squared();
+    if movement_squared == 0.0 {
+        return None;
+    }
+
+    let sides = polygon.num_sides();
+    let mut earliest: Option<Scalar> = None;
+    let mut consider = |time: Scalar| {
+        if 0.0 <= time && time <= 1.0 && earliest.map_or(true, |earliest| time < earliest) {
+            earliest = Some(time);
+        }
+    };
+
+    for i in 0..sides {
+        let (a, b) = (polygon.get(i), polygon.get((i + 1) % sides));
+        let side = b - a;
+        let length = side.length();
+        if length == 0.0 {
+            continue;
+        }
+
+        // The flat face of the side, pushed out by the radius towards the start
+        let normal = side.right() / length;
+        let start_distance = normal.dot(start - a);
+        if start_distance.abs() <= radius {
+            // Already touching the side's line, so only its ends can be run into
+        } else {
+            let approach = normal.dot(movement);
+            let target = radius * start_distance.signum();
+            if approach != 0.0 {
+                let time = (target - start_distance) / approach;
+                let along = side.dot(start + time * movement - a) / length;
+                if 0.0 <= along && along <= length {
+                    consider(time);
+                }
+            }
+        }
+
+        // The rounded end at the corner
+        let from_corner = start - a;
+        let c = from_corner.length_squared() - radius * radius;
+        if c > 0.0 {
+            let b_half = from_corner.dot(movement);
+            let discriminant = b_half * b_half - movement_squared * c;
+            if discriminant >= 0.0 {
+                consider((-b_half - discriminant.sqrt()) / movement_squared);
+            }
+        }
+    }
+
+    earliest
+}
@@ -1,2 +1,3 @@
 pub mod rectangle_point;
 pub mod segment_circle;
+pub mod circle_polygon;
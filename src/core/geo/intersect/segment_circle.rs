@@ -2,7 +2,7 @@ use crate::core::scalar::*;
 use crate::core::geo::circle::*;
 use crate::core::geo::segment2::*;
 
-pub struct IntersectionTimes(Scalar, Scalar);
+pub struct IntersectionTimes(pub Scalar, pub Scalar);
 
 // Returns the "times" of the intersection with the circle along the line segment
 // Adapted from:
@@ -2,3 +2,5 @@ pub mod circle;
 pub mod intersect;
 pub mod segment2;
 pub mod polygon;
+pub mod offset;
+pub mod boolean;
@@ -0,0 +1,195 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::core::geo::polygon::*;
+use crate::core::geo::boolean::remove_self_intersections;
+
+// Corners sharper than this, as a multiple of the offset distance, are cut off rather than
+// coming to a long spike
+pub const MITER_LIMIT: Scalar = 2.0;
+
+// Most that a rounded corner turns between two of its points, in radians
+pub const ROUND_JOIN_STEP: Scalar = 0.3;
+
+// How the sides are joined where offsetting pulls them apart at a corner
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Join {
+    // Carry the sides on until they meet, or bevel the corner past the miter limit
+    Miter,
+    // Go round the corner on an arc about the original vertex
+    Round,
+}
+
+// Move every side of the polygon out by the distance, or in when it's negative, keeping the
+// sides parallel to the originals. Works with either winding and with concave corners, which
+// are pulled in along the bisector so they stay the distance from both sides. Offsetting by
+// more than the polygon is thick can leave it crossing itself, which offset_region sorts out
+pub fn offset_polygon(polygon: &Polygon, distance: Scalar, join: Join) -> Polygon {
+    offset_outline(polygon, distance, join, false)
+}
+
+// With through_corners, corners where the offset sides overlap and are too short to be cut back to
+// where they meet go back via the original vertex instead. That isn't a clean outline, but the
+// area it winds counterclockwise around is still the offset area, where cutting the sides back
+// would have turned them round
+fn offset_outline(polygon: &Polygon, distance: Scalar, join: Join, through_corners: bool) -> Polygon {
+    // Repeated vertices would give sides with no direction
+    let mut vertices: Vec<Vector2> = vec!();
+    for vertex in &polygon.0 {
+        if vertices.last() != Some(vertex) {
+            vertices.push(*vertex);
+        }
+    }
+    while vertices.len() > 1 && vertices.first() == vertices.last() {
+        vertices.pop();
+    }
+
+    let count = vertices.len();
+    if count < 3 {
+        return Polygon(vertices);
+    }
+
+    // The outside of every side comes from the winding, rather than which side its neighbours
+    // are on
+    let winding = Polygon(vertices.clone()).signed_area().signum();
+
+    let outward_normal = |from: Vector2, to: Vector2| {
+        let direction = (to - from).normalize();
+        winding * Vector2 { x: direction.y, y: -direction.x }
+    };
+
+    let mut out = vec!();
+    for i in 0..count {
+        let previous = vertices[(i + count - 1) % count];
+        let vertex = vertices[i];
+        let next = vertices[(i + 1) % count];
+
+        let n1 = outward_normal(previous, vertex);
+        let n2 = outward_normal(vertex, next);
+
+        // The corner bends away from the side being offset towards, so the sides pull apart there
+        let opening = winding * distance * cross(vertex - previous, next - vertex) > 0.0;
+        let alignment = 1.0 + n1.dot(n2);
+        // Where the sides overlap, how far back along them they meet
+        let cut_back = (distance * cross(n1, n2)).abs() / alignment;
+        let shortest_side = (vertex - previous).length().min((next - vertex).length());
+
+        if opening && join == Join::Round {
+            let turn = cross(n1, n2).atan2(n1.dot(n2));
+            let steps = (turn.abs() / ROUND_JOIN_STEP).ceil().max(1.0) as usize;
+            for step in 0..=steps {
+                out.push(vertex + distance * n1.rotate_by(turn * step as Scalar / steps as Scalar));
+            }
+        } else if opening && alignment < 2.0 / (MITER_LIMIT * MITER_LIMIT) {
+            // Too sharp for a miter, so bevel it with a point off the end of each side
+            out.push(vertex + distance * n1);
+            out.push(vertex + distance * n2);
+        } else if !opening && through_corners && (alignment <= 0.0 || 2.0 * cut_back > shortest_side) {
+            // Where the sides meet is more than half way back along one of them, so cutting them
+            // back to there could turn a side round
+            out.push(vertex + distance * n1);
+            out.push(vertex);
+            out.push(vertex + distance * n2);
+        } else if alignment > 0.0 {
+            // Where the two offset sides meet
+            out.push(vertex + (distance / alignment) * (n1 + n2));
+        } else {
+            // The polygon doubles straight back on itself here. Going out to the end of each side
+            // crosses over, and the loop that makes is cut away by offset_region
+            out.push(vertex + distance * n1);
+            out.push(vertex + distance * n2);
+        }
+    }
+
+    Polygon(out)
+}
+
+// The area within the distance of the polygon, or for a negative distance the part of it that
+// far from the outside, as counterclockwise outlines with clockwise holes. Shrinking can split the
+// polygon in pieces or make it vanish altogether, and growing can close off notches into holes
+pub fn offset_region(polygon: &Polygon, distance: Scalar, join: Join) -> Vec<Polygon> {
+    let mut polygon = polygon.clone();
+    polygon.make_counterclockwise();
+    remove_self_intersections(&offset_outline(&polygon, distance, join, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::geo::polygon::tests::*;
+    use crate::core::geo::segment2::Segment2;
+    use rand::Rng;
+
+    fn distance_to_outline(polygon: &Polygon, point: Vector2) -> Scalar {
+        let sides = polygon.num_sides();
+        (0..sides)
+            .map(|i| Segment2 { p1: polygon.get(i), p2: polygon.get((i + 1) % sides) }.distance_from_segment_to_point_squared(point))
+            .fold(INFINITY, |a, b| a.min(b))
+            .sqrt()
+    }
+
+    #[test]
+    fn offsetting_out_and_back_in_round_trips() {
+        let mut rng = test_rng(5);
+        for _ in 0..RANDOM_POLYGON_CASES {
+            let polygon = random_convex_polygon(&mut rng, vector2(0.0, 0.0), 10.0);
+            let distance = rng.gen_range(0.1, 5.0);
+
+            let grown = offset_region(&polygon, distance, Join::Miter);
+            assert_eq!(grown.len(), 1);
+            let restored = offset_region(&grown[0], -distance, Join::Miter);
+            assert_eq!(restored.len(), 1);
+
+            assert!((restored[0].area() - polygon.area()).abs() < 1e-9 * polygon.area());
+            for vertex in &polygon.0 {
+                assert!(restored[0].0.iter().any(|restored_vertex| (*restored_vertex - *vertex).length() < 1e-9));
+            }
+        }
+    }
+
+    #[test]
+    fn rounded_offset_keeps_its_distance() {
+        let mut rng = test_rng(6);
+        for _ in 0..RANDOM_POLYGON_CASES {
+            let polygon = random_convex_polygon(&mut rng, vector2(0.0, 0.0), 10.0);
+            let distance = rng.gen_range(0.1, 5.0);
+
+            let grown = offset_region(&polygon, distance, Join::Round);
+            assert_eq!(grown.len(), 1);
+            for vertex in &grown[0].0 {
+                assert!((distance_to_outline(&polygon, *vertex) - distance).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn offsetting_concave_polygons_grows_and_shrinks_them() {
+        let mut rng = test_rng(7);
+        for _ in 0..RANDOM_POLYGON_CASES {
+            let polygon = random_star_polygon(&mut rng, vector2(0.0, 0.0), 10.0);
+            let distance = rng.gen_range(0.1, 2.0);
+
+            let grown = offset_region(&polygon, distance, Join::Round);
+            for vertex in &polygon.0 {
+                assert!(grown.iter().any(|outline| outline.is_counterclockwise() && outline.contains_point(*vertex)));
+            }
+            // Where a rounded corner is cut off, it's cut along one of the chords that stand in
+            // for the arc, which can be a little nearer than the distance
+            let nearest = distance * (0.5 * ROUND_JOIN_STEP).cos();
+            for outline in &grown {
+                for vertex in &outline.0 {
+                    assert!(distance_to_outline(&polygon, *vertex) > nearest - 1e-9);
+                }
+            }
+
+            // Whatever is left after shrinking is inside the polygon, and no nearer its sides than
+            // the distance
+            for outline in offset_region(&polygon, -distance, Join::Miter) {
+                assert!(outline.area() < polygon.area());
+                for vertex in &outline.0 {
+                    assert!(polygon.contains_point(*vertex));
+                    assert!(distance_to_outline(&polygon, *vertex) > distance - 1e-9);
+                }
+            }
+        }
+    }
+}
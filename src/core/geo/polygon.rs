@@ -1,5 +1,8 @@
 use crate::core::vector::*;
 use crate::core::scalar::*;
+use crate::core::geo::segment2::Segment2;
+
+use std::f64::consts::PI;
 
 // Could probably refactor this to be a Vec<Edge>
 // Polygon is an ordered vec of vertices (represented by Vector2)
@@ -15,38 +18,122 @@ impl Polygon {
         self.0[i]
     }
 
-    // Find the normals of all edges of the polygon
+    // Find the outward normals of all edges of the polygon. Which way is out comes from the
+    // winding, so it's right at concave corners too
     pub fn normals(&self) -> Vec<Vector2> {
         let mut out = Vec::new();
+        let winding = if self.signed_area() < 0.0 { -1.0 } else { 1.0 };
 
         for i in 0..self.num_sides() {
             let ab = self.0[(i + 1) % self.num_sides()] - self.0[i];
-            let ac = self.0[if i < 1 { self.num_sides()-1 } else { i-1 }] - self.0[i];
-            let n = Vector2 { x: ab.y, y: -ab.x }.normalize();
-            let d = n.dot(ac);
-
-            if d > 0.0 {
-                out.push(-n);
-            }
-            else {
-                out.push(n);
-            }
+            out.push(winding * Vector2 { x: ab.y, y: -ab.x }.normalize());
         }
 
         out
     }
 
+    // Area enclosed by the polygon, positive when the vertices go counterclockwise
+    pub fn signed_area(&self) -> Scalar {
+        let sides = self.num_sides();
+        0.5 * (0..sides).map(|i| cross(self.0[i], self.0[(i + 1) % sides])).sum::<Scalar>()
+    }
+
+    pub fn area(&self) -> Scalar {
+        self.signed_area().abs()
+    }
+
+    pub fn is_counterclockwise(&self) -> bool {
+        self.signed_area() > 0.0
+    }
+
+    // Put the vertices in counterclockwise order, which is how the boolean operations and the
+    // offsetting hand polygons back
+    pub fn make_counterclockwise(&mut self) {
+        if self.signed_area() < 0.0 {
+            self.0.reverse();
+        }
+    }
+
+    // Drop the vertices that stray less than the tolerance from the outline without them, which
+    // with no tolerance is the vertices in the middle of straight sides. Keeps the polygon as it
+    // is if there'd be fewer than three left
+    pub fn simplify(&self, tolerance: Scalar) -> Polygon {
+        let sides = self.num_sides();
+        if sides <= 3 {
+            return self.clone();
+        }
+
+        // Split the outline into two chains between vertices on opposite sides of it, which are
+        // always kept, and simplify each chain
+        let far = (1..sides)
+            .max_by(|&i, &j| (self.0[i] - self.0[0]).length_squared()
+                .partial_cmp(&(self.0[j] - self.0[0]).length_squared()).unwrap())
+            .unwrap();
+        let mut keep = vec![false; sides + 1];
+        let mut points = self.0.clone();
+        points.push(self.0[0]);
+        keep[0] = true;
+        keep[far] = true;
+        simplify_chain(&points, 0, far, tolerance, &mut keep);
+        simplify_chain(&points, far, sides, tolerance, &mut keep);
+
+        let vertices: Vec<Vector2> = (0..sides).filter(|i| keep[*i]).map(|i| self.0[i]).collect();
+        if vertices.len() < 3 {
+            return self.clone();
+        }
+        Polygon(vertices)
+    }
+
+    // Split the polygon into counterclockwise triangles covering it, by ear clipping. Works on
+    // concave polygons in either winding, and vertices in the middle of a straight side or
+    // repeated are passed over rather than giving triangles with no area
     pub fn triangles(&self) -> Vec<Polygon> {
         if self.num_sides() < 3 { panic!("Polygons must have at least 3 sides!") }
-        if self.num_sides() == 3 { return vec![self.clone()] }
 
-        let triangle = Polygon(vec![self.get(0), self.get(1), self.get(2)]);
+        let mut remaining: Vec<usize> = (0..self.num_sides()).collect();
+        if self.signed_area() < 0.0 {
+            remaining.reverse();
+        }
+
+        let mut triangles = Vec::with_capacity(self.num_sides() - 2);
+        let mut i = 0;
+        // Vertices looked at since the last one was clipped, so a polygon with no ears left,
+        // which only happens when it crosses itself, can't go round forever
+        let mut since_clipped = 0;
+
+        while remaining.len() > 3 {
+            let count = remaining.len();
+            let (previous, vertex, next) = (
+                self.0[remaining[(i + count - 1) % count]],
+                self.0[remaining[i % count]],
+                self.0[remaining[(i + 1) % count]]);
+            let turn = cross(vertex - previous, next - vertex);
 
-        let mut vertices = self.0.clone();
-        vertices.remove(1);
+            // Flat corners are cut off for nothing, and reflex ones can't be ears
+            let is_flat = turn.abs() <= TRIANGULATION_EPSILON * (vertex - previous).length() * (next - vertex).length();
+            let is_ear = !is_flat && turn > 0.0 && !remaining.iter().any(|&j| {
+                let point = self.0[j];
+                point != previous && point != vertex && point != next &&
+                    is_in_triangle(point, previous, vertex, next)
+            });
 
-        let mut triangles = Polygon(vertices).triangles();
-        triangles.push(triangle);
+            if is_flat || is_ear || since_clipped > count {
+                if !is_flat {
+                    triangles.push(Polygon(vec![previous, vertex, next]));
+                }
+                remaining.remove(i % count);
+                since_clipped = 0;
+            } else {
+                i += 1;
+                since_clipped += 1;
+            }
+            i %= remaining.len();
+        }
+
+        let last = Polygon(remaining.iter().map(|&j| self.0[j]).collect());
+        if last.area() > 0.0 {
+            triangles.push(last);
+        }
         triangles
     }
 
@@ -144,7 +231,418 @@ impl Polygon {
         out
     }
 
+    // Where the segment from start to end first crosses a side, as how far along the segment that
+    // is between 0 and 1, and which side it crosses
+    pub fn first_intersect(&self, start: Vector2, end: Vector2) -> Option<(Scalar, usize)> {
+        let movement = end - start;
+        let mut first: Option<(Scalar, usize)> = None;
+
+        for i in 0..self.num_sides() {
+            let p1 = self.get(i);
+            let side = self.get((i + 1) % self.num_sides()) - p1;
+
+            let denominator = movement.x * side.y - movement.y * side.x;
+            if denominator == 0.0 {
+                continue;
+            }
+
+            let to_side = p1 - start;
+            let time = (to_side.x * side.y - to_side.y * side.x) / denominator;
+            let along = (to_side.x * movement.y - to_side.y * movement.x) / denominator;
+            if 0.0 <= time && time <= 1.0 && 0.0 <= along && along <= 1.0 &&
+                first.map_or(true, |(first_time, _)| time < first_time) {
+                first = Some((time, i));
+            }
+        }
+
+        first
+    }
+
     pub fn contains_point(&self, pos: Vector2) -> bool {
         self.num_intersects(pos, Vector2 { x: pos.x, y: MAX }) % 2 == 1
     }
 }
+
+// Vertices closer to the outline than this, relative to the length of the side they'd be merged
+// into, count as on it, so rounding doesn't keep vertices in the middle of straight sides
+const SIMPLIFY_EPSILON: Scalar = 1e-12;
+
+// Ramer-Douglas-Peucker over the points between first and last, marking the ones to keep
+fn simplify_chain(points: &Vec<Vector2>, first: usize, last: usize, tolerance: Scalar, keep: &mut Vec<bool>) {
+    if last <= first + 1 {
+        return;
+    }
+
+    let chord = Segment2 { p1: points[first], p2: points[last] };
+    let mut furthest = first + 1;
+    let mut furthest_distance_squared = NEG_INFINITY;
+    for i in (first + 1)..last {
+        let distance_squared = chord.distance_from_segment_to_point_squared(points[i]);
+        if distance_squared > furthest_distance_squared {
+            furthest = i;
+            furthest_distance_squared = distance_squared;
+        }
+    }
+
+    let allowance = tolerance + SIMPLIFY_EPSILON * (points[last] - points[first]).length();
+    if furthest_distance_squared > allowance * allowance {
+        keep[furthest] = true;
+        simplify_chain(points, first, furthest, tolerance, keep);
+        simplify_chain(points, furthest, last, tolerance, keep);
+    }
+}
+
+// Corners closer to straight than this, relative to the length of their sides, count as flat
+// when triangulating
+const TRIANGULATION_EPSILON: Scalar = 1e-12;
+
+// Whether the point is inside the counterclockwise triangle abc or on its edge
+fn is_in_triangle(point: Vector2, a: Vector2, b: Vector2, c: Vector2) -> bool {
+    cross(b - a, point - a) >= 0.0 && cross(c - b, point - b) >= 0.0 && cross(a - c, point - c) >= 0.0
+}
+
+// Part of the view from a point that ends at the same thing all the way across
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VisibleSector {
+    pub start_angle: Scalar,
+    pub end_angle: Scalar,
+    // Index of the obstacle and the side of it that the view ends at, or None where nothing is in
+    // the way and the view carries on to the radius
+    pub blocker: Option<(usize, Vector2, Vector2)>,
+}
+
+// Region that can be seen from the viewpoint within the radius, as sectors going round from -PI
+// to PI. Each ends either at a straight side or at the circle, so the region is exact, arcs and all
+#[derive(Clone, Debug)]
+pub struct VisibilityRegion {
+    pub viewpoint: Vector2,
+    pub radius: Scalar,
+    pub sectors: Vec<VisibleSector>,
+}
+
+impl VisibilityRegion {
+    pub fn sector_at(&self, angle: Scalar) -> &VisibleSector {
+        let angle = normalize_angle(angle);
+        let index = match self.sectors.binary_search_by(|sector| sector.end_angle.partial_cmp(&angle).unwrap()) {
+            Ok(index) | Err(index) => index.min(self.sectors.len() - 1),
+        };
+        &self.sectors[index]
+    }
+
+    // How far the view reaches in the direction
+    pub fn distance_at(&self, angle: Scalar) -> Scalar {
+        match self.sector_at(angle).blocker {
+            Some((_, p1, p2)) => {
+                let denominator = cross(Vector2::from_angle(angle), p2 - p1);
+                if denominator == 0.0 {
+                    return 0.0;
+                }
+                (cross(p1 - self.viewpoint, p2 - p1) / denominator).max(0.0).min(self.radius)
+            }
+            None => self.radius,
+        }
+    }
+
+    pub fn contains_point(&self, point: Vector2) -> bool {
+        let offset = point - self.viewpoint;
+        let distance_squared = offset.length_squared();
+        if distance_squared == 0.0 {
+            return true;
+        }
+        let reach = self.distance_at(offset.angle());
+        distance_squared <= reach * reach
+    }
+
+    // The obstacle that first gets in the way of seeing in the direction, if any
+    pub fn blocker_at(&self, angle: Scalar) -> Option<usize> {
+        self.sector_at(angle).blocker.map(|(index, _, _)| index)
+    }
+}
+
+// Find the region visible from the viewpoint within the radius, with the obstacles blocking line
+// of sight. The nearest side in any direction can only change at the angle of a vertex, where a
+// side crosses the circle or where two sides cross, so the view is swept round between those
+// angles, finding what ends it halfway across each gap
+pub fn visibility_region(viewpoint: Vector2, radius: Scalar, obstacles: &Vec<Polygon>) -> VisibilityRegion {
+    // Only sides that come within the radius need to be considered
+    let mut sides = Vec::new();
+    for (index, obstacle) in obstacles.iter().enumerate() {
+        let (min, max) = obstacle.bounding_box();
+        if viewpoint.x < min.x - radius || viewpoint.x > max.x + radius ||
+            viewpoint.y < min.y - radius || viewpoint.y > max.y + radius {
+            continue;
+        }
+        for i in 0..obstacle.num_sides() {
+            let (p1, p2) = (obstacle.get(i), obstacle.get((i + 1) % obstacle.num_sides()));
+            if (Segment2 { p1, p2 }).distance_from_segment_to_point_squared(viewpoint) <= radius * radius {
+                sides.push((index, p1, p2));
+            }
+        }
+    }
+
+    // Vertices and crossings within the radius, and where sides cross the circle, which rounding
+    // could put just outside it
+    let mut inside = vec!();
+    let mut on_circle = vec!();
+    for (i, (_, p1, p2)) in sides.iter().enumerate() {
+        inside.push(*p1);
+        on_circle.extend(segment_circle_intersects(*p1, *p2, viewpoint, radius));
+        for (_, p3, p4) in &sides[(i + 1)..] {
+            inside.extend(side_crossing(*p1, *p2, *p3, *p4));
+        }
+    }
+
+    let mut events = vec![-PI, PI];
+    for point in inside.iter().filter(|point| (**point - viewpoint).length_squared() <= radius * radius).chain(on_circle.iter()) {
+        if *point != viewpoint {
+            events.push(normalize_angle((*point - viewpoint).angle()));
+        }
+    }
+    events.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    events.dedup();
+
+    let mut sectors: Vec<VisibleSector> = Vec::with_capacity(events.len());
+    for k in 1..events.len() {
+        let (start_angle, end_angle) = (events[k - 1], events[k]);
+        let blocker = nearest_side(viewpoint, Vector2::from_angle(0.5 * (start_angle + end_angle)), radius, &sides);
+
+        // Neighbouring sectors ending at the same side are one sector
+        match sectors.last_mut() {
+            Some(last) if last.blocker == blocker => last.end_angle = end_angle,
+            _ => sectors.push(VisibleSector { start_angle, end_angle, blocker }),
+        }
+    }
+
+    VisibilityRegion { viewpoint, radius, sectors }
+}
+
+// The side the ray hits first within max_distance, if any
+fn nearest_side(start: Vector2, direction: Vector2, max_distance: Scalar, sides: &Vec<(usize, Vector2, Vector2)>) -> Option<(usize, Vector2, Vector2)> {
+    let mut nearest = max_distance;
+    let mut nearest_side = None;
+
+    for (index, p1, p2) in sides {
+        let side = *p2 - *p1;
+        let denominator = cross(direction, side);
+
+        // Rays running along a side are blocked by the neighbouring sides instead
+        if denominator == 0.0 {
+            continue;
+        }
+
+        let to_p1 = *p1 - start;
+        let t = cross(to_p1, side) / denominator;
+        let u = cross(to_p1, direction) / denominator;
+
+        if t >= 0.0 && t < nearest && u >= 0.0 && u <= 1.0 {
+            nearest = t;
+            nearest_side = Some((*index, *p1, *p2));
+        }
+    }
+
+    nearest_side
+}
+
+// Where the segments from p1 to p2 and from p3 to p4 cross, if they do
+fn side_crossing(p1: Vector2, p2: Vector2, p3: Vector2, p4: Vector2) -> Option<Vector2> {
+    let (r, s) = (p2 - p1, p4 - p3);
+    let denominator = cross(r, s);
+    if denominator == 0.0 {
+        return None;
+    }
+
+    let t = cross(p3 - p1, s) / denominator;
+    let u = cross(p3 - p1, r) / denominator;
+    if t >= 0.0 && t <= 1.0 && u >= 0.0 && u <= 1.0 {
+        Some(p1 + t * r)
+    } else {
+        None
+    }
+}
+
+// Find the points where the segment from p1 to p2 crosses the circle
+fn segment_circle_intersects(p1: Vector2, p2: Vector2, center: Vector2, radius: Scalar) -> Vec<Vector2> {
+    let d = p2 - p1;
+    let f = p1 - center;
+
+    let a = d.length_squared();
+    let b = 2.0 * f.dot(d);
+    let c = f.length_squared() - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+
+    let mut out = Vec::new();
+    if a == 0.0 || discriminant < 0.0 {
+        return out;
+    }
+
+    let root = discriminant.sqrt();
+    for t in &[(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)] {
+        if *t >= 0.0 && *t <= 1.0 {
+            out.push(p1 + *t * d);
+        }
+    }
+    out
+}
+
+// Wrap an angle into the range [-PI, PI)
+fn normalize_angle(angle: Scalar) -> Scalar {
+    let mut out = angle;
+    while out < -PI { out += 2.0 * PI }
+    while out >= PI { out -= 2.0 * PI }
+    out
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    pub const RANDOM_POLYGON_CASES: usize = 200;
+
+    pub fn test_rng(seed: u64) -> XorShiftRng {
+        XorShiftRng::seed_from_u64(seed)
+    }
+
+    // Polygon around the centre that every vertex can see, which is concave more often than not
+    pub fn random_star_polygon<R: Rng>(rng: &mut R, center: Vector2, max_radius: Scalar) -> Polygon {
+        // With at least four vertices jittered this little, no gap between them gets to half a turn
+        let count = rng.gen_range(4, 12);
+        let step = 2.0 * PI / count as Scalar;
+        let mut polygon = Polygon((0..count)
+            .map(|i| {
+                let angle = (i as Scalar + rng.gen_range(-0.4, 0.4)) * step;
+                center + rng.gen_range(0.2, 1.0) * max_radius * Vector2::from_angle(angle)
+            })
+            .collect());
+        if rng.gen_bool(0.5) {
+            polygon.0.reverse();
+        }
+        polygon
+    }
+
+    // Convex polygon with no corner sharp enough for offsetting to bevel it
+    pub fn random_convex_polygon<R: Rng>(rng: &mut R, center: Vector2, radius: Scalar) -> Polygon {
+        let count = rng.gen_range(5, 10);
+        let step = 2.0 * PI / count as Scalar;
+        Polygon((0..count)
+            .map(|i| center + radius * Vector2::from_angle((i as Scalar + rng.gen_range(-0.2, 0.2)) * step))
+            .collect())
+    }
+
+    #[test]
+    fn signed_area_follows_winding() {
+        let mut square = Polygon(vec![vector2(0.0, 0.0), vector2(2.0, 0.0), vector2(2.0, 3.0), vector2(0.0, 3.0)]);
+        assert_eq!(square.signed_area(), 6.0);
+        assert!(square.is_counterclockwise());
+
+        square.0.reverse();
+        assert_eq!(square.signed_area(), -6.0);
+        assert_eq!(square.area(), 6.0);
+        square.make_counterclockwise();
+        assert_eq!(square.signed_area(), 6.0);
+    }
+
+    #[test]
+    fn triangulating_preserves_area() {
+        let mut rng = test_rng(0);
+        for _ in 0..RANDOM_POLYGON_CASES {
+            let polygon = random_star_polygon(&mut rng, vector2(0.0, 0.0), 10.0);
+            let total: Scalar = polygon.triangles().iter().map(|triangle| triangle.area()).sum();
+            assert!((total - polygon.area()).abs() < 1e-9 * polygon.area().max(1.0));
+        }
+    }
+
+    #[test]
+    fn simplifying_without_tolerance_only_drops_vertices_on_straight_sides() {
+        let mut rng = test_rng(1);
+        for _ in 0..RANDOM_POLYGON_CASES {
+            let polygon = random_star_polygon(&mut rng, vector2(0.0, 0.0), 10.0);
+            let count = polygon.num_sides();
+            let mut padded = vec!();
+            for i in 0..count {
+                padded.push(polygon.get(i));
+                padded.push(0.5 * (polygon.get(i) + polygon.get((i + 1) % count)));
+            }
+
+            let simplified = Polygon(padded).simplify(0.0);
+            assert_eq!(simplified.num_sides(), count);
+            assert!((simplified.area() - polygon.area()).abs() < 1e-9 * polygon.area().max(1.0));
+        }
+    }
+
+    // How far a ray gets before hitting any side of any obstacle, trying every one
+    fn brute_force_ray_cast(start: Vector2, angle: Scalar, radius: Scalar, obstacles: &Vec<Polygon>) -> Scalar {
+        let direction = Vector2::from_angle(angle);
+        let mut nearest = radius;
+        for obstacle in obstacles {
+            for i in 0..obstacle.num_sides() {
+                let (p1, p2) = (obstacle.get(i), obstacle.get((i + 1) % obstacle.num_sides()));
+                let denominator = cross(direction, p2 - p1);
+                if denominator != 0.0 {
+                    let t = cross(p1 - start, p2 - p1) / denominator;
+                    let u = cross(p1 - start, direction) / denominator;
+                    if t >= 0.0 && u >= 0.0 && u <= 1.0 {
+                        nearest = nearest.min(t);
+                    }
+                }
+            }
+        }
+        nearest
+    }
+
+    #[test]
+    fn visibility_matches_brute_force_ray_casting() {
+        let mut rng = test_rng(9);
+        for _ in 0..RANDOM_POLYGON_CASES {
+            // Obstacles can overlap, and the view can reach past some of them
+            let obstacles: Vec<Polygon> = (0..rng.gen_range(1, 6))
+                .map(|_| {
+                    let center = vector2(rng.gen_range(-15.0, 15.0), rng.gen_range(-15.0, 15.0));
+                    random_star_polygon(&mut rng, center, 5.0)
+                })
+                .collect();
+            let viewpoint = vector2(rng.gen_range(-15.0, 15.0), rng.gen_range(-15.0, 15.0));
+            if obstacles.iter().any(|obstacle| obstacle.contains_point(viewpoint)) {
+                continue;
+            }
+            let radius = rng.gen_range(2.0, 20.0);
+            let view = visibility_region(viewpoint, radius, &obstacles);
+
+            for _ in 0..100 {
+                let angle = rng.gen_range(-PI, PI);
+                let expected = brute_force_ray_cast(viewpoint, angle, radius, &obstacles);
+                assert!((view.distance_at(angle) - expected).abs() < 1e-9);
+
+                // Points clear of the edge of the view are in it exactly when they're nearer
+                let distance = rng.gen_range(0.0, 1.2 * radius);
+                if (distance - expected).abs() > 1e-9 {
+                    let point = viewpoint + distance * Vector2::from_angle(angle);
+                    assert_eq!(view.contains_point(point), distance < expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn visibility_along_and_between_touching_sides() {
+        // Two squares sharing a side, which lines up with the viewpoint
+        let obstacles = vec![
+            Polygon(vec![vector2(2.0, 0.0), vector2(4.0, 0.0), vector2(4.0, 2.0), vector2(2.0, 2.0)]),
+            Polygon(vec![vector2(2.0, -2.0), vector2(4.0, -2.0), vector2(4.0, 0.0), vector2(2.0, 0.0)]),
+        ];
+        let view = visibility_region(vector2(0.0, 0.0), 10.0, &obstacles);
+        for angle in &[-1e-6, 0.0, 1e-6] {
+            assert!((view.distance_at(*angle) - 2.0).abs() < 1e-6);
+        }
+        assert!(!view.contains_point(vector2(5.0, 0.0)));
+        assert_eq!(view.distance_at(PI / 2.0), 10.0);
+
+        // Looking along the outside of a side, which grazes it rather than being blocked
+        let obstacles = vec![Polygon(vec![vector2(2.0, 0.0), vector2(4.0, 0.0), vector2(4.0, 2.0), vector2(2.0, 2.0)])];
+        let view = visibility_region(vector2(0.0, 0.0), 10.0, &obstacles);
+        assert_eq!(view.distance_at(-1e-6), 10.0);
+        assert!((view.distance_at(1e-6) - 2.0).abs() < 1e-6);
+        assert!(view.contains_point(vector2(8.0, -1e-3)));
+    }
+}
@@ -3,7 +3,7 @@ use crate::core::vector::Vector;
 
 use std::ops::*;
 
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
 pub struct Vector2 {
     pub x: Scalar,
     pub y: Scalar,
@@ -33,6 +33,11 @@ pub fn vector2(x: Scalar, y: Scalar) -> Vector2 {
     Vector2{ x: x, y: y }
 }
 
+// Cross product of two vectors in the plane, positive when b is counterclockwise of a
+pub fn cross(a: Vector2, b: Vector2) -> Scalar {
+    a.x * b.y - a.y * b.x
+}
+
 impl Neg for Vector2 {
     type Output = Vector2;
     fn neg(self) -> Self {
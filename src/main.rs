@@ -57,6 +57,12 @@ fn init() -> Result<((Sdl, SDL2Facade, EventPump),
 
 
 fn main() {
+    // Headless benchmark of the cop pathfinding, doesn't need a window
+    if std::env::args().any(|arg| arg == "--benchmark-pathfinding") {
+        simulation::ai::benchmark::benchmark_pathfinding();
+        return;
+    }
+
     // init
     let (window_tuple,
         textures,
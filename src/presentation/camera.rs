@@ -11,6 +11,9 @@ pub struct Camera {
     velocity: Vector2,
     pub zoom: Vector2,
     initial_mouse_pos: Vector2,
+    // World bounds, set from the map
+    min_bound: Vector2,
+    max_bound: Vector2,
 }
 
 impl Camera {
@@ -20,9 +23,16 @@ impl Camera {
             velocity: Vector2::zero(),
             zoom: Vector2 { x: 0.09 as f64, y: 0.09 as f64 },  // initial zoom level
             initial_mouse_pos: Vector2 { x: 0.0 as f64, y: 0.0 as f64 },    // initial mouse wheel button position
+            min_bound: vector2(Self::LEFT_BOUND, Self::BOTTOM_BOUND),
+            max_bound: vector2(Self::RIGHT_BOUND, Self::TOP_BOUND),
         }
     }
 
+    pub fn set_bounds(&mut self, min_bound: Vector2, max_bound: Vector2) {
+        self.min_bound = min_bound;
+        self.max_bound = max_bound;
+    }
+
     pub fn get_world_position(&self) -> Vector2 {
         // I wish our camera's position was stored in world coordinates
         // but with 1.5 days until our course ends its not worth changing now
@@ -37,7 +47,7 @@ impl Camera {
     const LOWER_BOUND: f64 = 0.015;
     const UPPER_BOUND: f64 = 0.15;
 
-    // World bounds
+    // Default world bounds
     const LEFT_BOUND: Scalar = -25.0;
     const RIGHT_BOUND: Scalar = 115.0;
     const TOP_BOUND: Scalar = 115.0;
@@ -85,8 +95,8 @@ impl Camera {
 
         // Allow mouse cursor to move camera when against screen edges in fullscreen
         if window.window().fullscreen_state() == True {
-            let left_corner_bound = vector2(Self::LEFT_BOUND * self.zoom.x, Self::BOTTOM_BOUND * self.zoom.y);
-            let right_corner_bound = vector2(Self::RIGHT_BOUND * self.zoom.x, Self::TOP_BOUND * self.zoom.y);
+            let left_corner_bound = vector2(self.min_bound.x * self.zoom.x, self.min_bound.y * self.zoom.y);
+            let right_corner_bound = vector2(self.max_bound.x * self.zoom.x, self.max_bound.y * self.zoom.y);
 
             let mouse_pos = &mut vector2(ms.x() as f64, ms.y() as f64);
             translate_to_camera_coord(mouse_pos, window.window().size());
@@ -174,8 +184,8 @@ impl Camera {
 
     fn key_pressed(&mut self, ks: &sdl2::keyboard::KeyboardState, s: keyboard::Scancode) -> Scalar {
 
-        let left_corner_bound = vector2(Self::LEFT_BOUND * self.zoom.x, Self::BOTTOM_BOUND * self.zoom.y);
-        let right_corner_bound = vector2(Self::RIGHT_BOUND * self.zoom.x, Self::TOP_BOUND * self.zoom.y);
+        let left_corner_bound = vector2(self.min_bound.x * self.zoom.x, self.min_bound.y * self.zoom.y);
+        let right_corner_bound = vector2(self.max_bound.x * self.zoom.x, self.max_bound.y * self.zoom.y);
 
         // Returns 1.0 if camera_frame is in world bounds
         if ks.is_scancode_pressed(s) {
@@ -200,8 +210,8 @@ impl Camera {
     fn mouse_within_bounds(&mut self, mouse_pos: Vector2) -> bool {
 
         // Don't want zoom to affect bounds
-        let left_corner_bound = vector2(Self::LEFT_BOUND, Self::BOTTOM_BOUND);
-        let right_corner_bound = vector2(Self::RIGHT_BOUND, Self::TOP_BOUND);
+        let left_corner_bound = self.min_bound;
+        let right_corner_bound = self.max_bound;
 
         if (mouse_pos.x > left_corner_bound.x && mouse_pos.y > left_corner_bound.y) && (mouse_pos.x < right_corner_bound.x && mouse_pos.y < right_corner_bound.y) {
             return true;
@@ -15,10 +15,20 @@ use crate::presentation::ui::gui::Component;
 use crate::presentation::ui::gui::GuiType;
 use crate::simulation::barricade::*;
 use crate::simulation::control::*;
+use crate::simulation::fog_of_war::*;
+use crate::simulation::ammo_crate::*;
+use crate::simulation::upgrades::*;
+use crate::simulation::survival::Survival;
+use crate::simulation::objectives::*;
+use crate::simulation::campaign::*;
+use crate::simulation::map::SpawnKind;
+use crate::simulation::editor::*;
 use crate::simulation::game_state::GameState;
 use crate::simulation::state::*;
 use crate::simulation::update::EntityCounts;
 
+use std::f64::consts::PI;
+
 // Enum ordered by draw order
 #[derive(Copy, Clone, Debug, Enum, PartialEq)]
 pub enum SpriteType {
@@ -284,6 +294,11 @@ fn push_gui_vertices(buffer: &mut Vec<ColorVertex>, ui: &Gui) {
         GuiType::Button { .. } => { color = [0.6, 0.7, 0.8, 0.0] }
         GuiType::Window => { color = [0.0, 0.0, 0.0, 0.7] }
         GuiType::Menu { .. } => { color = [0.6, 0.7, 0.8, 0.0] }
+        GuiType::RecruitButton { highlight, .. } |
+        GuiType::UpgradePanel { highlight, .. } |
+        GuiType::UpgradeButton { highlight, .. } => {
+            color = if highlight { [0.25, 0.3, 0.4, 0.9] } else { [0.1, 0.1, 0.15, 0.8] }
+        }
         _ => (),
     };
 
@@ -622,6 +637,177 @@ fn push_path_vertices(buffer: &mut Vec<ColorVertex>, point1: Vector2, point2: Ve
     buffer.push(vertex2);
 }
 
+fn push_vision_cone_vertices(buffer: &mut Vec<ColorVertex>, entity: &Entity, field_of_view: Scalar, radius: Scalar, color: [f32; 4]) {
+    let segments = 16;
+    let start_angle = entity.facing_angle - 0.5 * field_of_view;
+    let step = field_of_view / segments as Scalar;
+
+    for i in 0..segments {
+        let p1 = entity.position + radius * Vector2::from_angle(start_angle + step * i as Scalar);
+        let p2 = entity.position + radius * Vector2::from_angle(start_angle + step * (i + 1) as Scalar);
+
+        buffer.push(ColorVertex {
+            position: entity.position.as_f32_array(),
+            tex_coords: [0.0, 0.0],
+            color,
+        });
+        buffer.push(ColorVertex {
+            position: p1.as_f32_array(),
+            tex_coords: [0.0, 1.0],
+            color,
+        });
+        buffer.push(ColorVertex {
+            position: p2.as_f32_array(),
+            tex_coords: [1.0, 1.0],
+            color,
+        });
+    }
+}
+
+fn push_circle_vertices(buffer: &mut Vec<ColorVertex>, center: Vector2, radius: Scalar, color: [f32; 4]) {
+    let segments = 32;
+    let step = 2.0 * PI / segments as Scalar;
+
+    for i in 0..segments {
+        let p1 = center + radius * Vector2::from_angle(step * i as Scalar);
+        let p2 = center + radius * Vector2::from_angle(step * (i + 1) as Scalar);
+
+        buffer.push(ColorVertex {
+            position: center.as_f32_array(),
+            tex_coords: [0.5, 0.5],
+            color,
+        });
+        buffer.push(ColorVertex {
+            position: p1.as_f32_array(),
+            tex_coords: [0.0, 1.0],
+            color,
+        });
+        buffer.push(ColorVertex {
+            position: p2.as_f32_array(),
+            tex_coords: [1.0, 1.0],
+            color,
+        });
+    }
+}
+
+// Cover the whole screen, in GUI coordinates
+fn push_night_overlay_vertices(buffer: &mut Vec<ColorVertex>, color: [f32; 4]) {
+    let vertex0 = ColorVertex {
+        position: [-1.0, 1.0],
+        tex_coords: [0.0, 1.0],
+        color,
+    };
+    let vertex1 = ColorVertex {
+        position: [1.0, 1.0],
+        tex_coords: [1.0, 1.0],
+        color,
+    };
+    let vertex2 = ColorVertex {
+        position: [-1.0, -1.0],
+        tex_coords: [0.0, 0.0],
+        color,
+    };
+    let vertex3 = ColorVertex {
+        position: [1.0, -1.0],
+        tex_coords: [1.0, 0.0],
+        color,
+    };
+
+    buffer.push(vertex0);
+    buffer.push(vertex1);
+    buffer.push(vertex2);
+    buffer.push(vertex1);
+    buffer.push(vertex3);
+    buffer.push(vertex2);
+}
+
+fn push_fog_of_war_vertices(buffer: &mut Vec<ColorVertex>, fog_of_war: &FogOfWar) {
+    for row in 0..fog_of_war.rows {
+        for column in 0..fog_of_war.columns {
+            let color = match fog_of_war.get(column, row) {
+                Visibility::Unexplored => [0.05, 0.05, 0.05, 1.0],
+                Visibility::Explored => [0.0, 0.0, 0.0, 0.5],
+                Visibility::Visible => continue,
+            };
+            let (bot_left, top_right) = fog_of_war.cell_bounds(column, row);
+
+            let vertex0 = ColorVertex {
+                position: [bot_left.x as f32, top_right.y as f32],
+                tex_coords: [0.0, 1.0],
+                color,
+            };
+            let vertex1 = ColorVertex {
+                position: top_right.as_f32_array(),
+                tex_coords: [1.0, 1.0],
+                color,
+            };
+            let vertex2 = ColorVertex {
+                position: bot_left.as_f32_array(),
+                tex_coords: [0.0, 0.0],
+                color,
+            };
+            let vertex3 = ColorVertex {
+                position: [top_right.x as f32, bot_left.y as f32],
+                tex_coords: [1.0, 0.0],
+                color,
+            };
+
+            buffer.push(vertex0);
+            buffer.push(vertex1);
+            buffer.push(vertex2);
+            buffer.push(vertex1);
+            buffer.push(vertex3);
+            buffer.push(vertex2);
+        }
+    }
+}
+
+// Each building type has its own texture
+fn building_sprite(building_type: u32) -> SpriteType {
+    match building_type {
+        1 => SpriteType::BuildingFour,
+        2 => SpriteType::BuildingSeven,
+        3 => SpriteType::BuildingThree,
+        4 => SpriteType::BuildingTwo,
+        5 => SpriteType::BuildingFive,
+        6 => SpriteType::BuildingSix,
+        _ => SpriteType::BuildingOne,
+    }
+}
+
+fn draw_buildings(
+    frame: &mut glium::Frame,
+    window: &glium_sdl2::SDL2Facade,
+    programs: &Programs,
+    textures: &Textures,
+    params: &glium::DrawParameters,
+    camera_frame: [[f32; 4]; 4],
+    buildings: Vec<(&Polygon, u32)>)
+{
+    let color = [0.1, 0.1, 0.1, 1.0];
+    let mut vertex_buffers_building = enum_map! {_ => vec!()};
+    for (building, building_type) in buildings {
+        push_building_vertices(&mut vertex_buffers_building[building_sprite(building_type)], building, color);
+    }
+
+    for (sprite_type, vertex_buffer) in &vertex_buffers_building {
+        if vertex_buffer.is_empty() {
+            continue;
+        }
+        let uniforms = uniform! {
+            matrix: camera_frame,
+            tex: &textures.sprite_textures[sprite_type],
+        };
+        draw_color_sprites(
+            frame,
+            window,
+            vertex_buffer,
+            &programs.sprite_program,
+            params,
+            &uniforms);
+    }
+}
+
 fn draw_sprites<U>(
     frame: &mut glium::Frame,
     window: &glium_sdl2::SDL2Facade,
@@ -709,123 +895,28 @@ fn draw_outside_background(
         params).unwrap();
 }
 
-fn draw_left_fence(
-    frame: &mut glium::Frame,
-    window: &glium_sdl2::SDL2Facade,
-    textures: &Textures,
-    programs: &Programs,
-    camera_frame: [[f32; 4]; 4],
-    params: &glium::DrawParameters)
-{
-    let top_left = VertexPosition {
-        position: [-24.5, 116.0],
-    };
-    let top_right = VertexPosition {
-        position: [-26.0, 116.0],
-    };
-    let bot_left = VertexPosition {
-        position: [-24.5, -26.0],
-    };
-    let bot_right = VertexPosition {
-        position: [-26.0, -26.0],
-    };
-
-    // tl    tr
-    //  +----+
-    //  |  / |
-    //  | /  |
-    //  +----+
-    // bl    br
-
-    let vertices = vec!(
-        top_left,
-        top_right,
-        bot_left,
-        top_right,
-        bot_right,
-        bot_left,
-    );
-
-    let uniforms = uniform! {
-        matrix: camera_frame,
-        tex: &textures.left_fence_texture,
-    };
-    frame.draw(
-        &glium::VertexBuffer::new(window, &vertices).unwrap(),
-        &glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
-        &programs.background_program,
-        &uniforms,
-        params).unwrap();
-}
-
-fn draw_right_fence(
-    frame: &mut glium::Frame,
-    window: &glium_sdl2::SDL2Facade,
-    textures: &Textures,
-    programs: &Programs,
-    camera_frame: [[f32; 4]; 4],
-    params: &glium::DrawParameters)
-{
-    let top_left = VertexPosition {
-        position: [114.5, 116.0],
-    };
-    let top_right = VertexPosition {
-        position: [116.0, 116.0],
-    };
-    let bot_left = VertexPosition {
-        position: [114.5, -26.0],
-    };
-    let bot_right = VertexPosition {
-        position: [116.0, -26.0],
-    };
-
-    // tl    tr
-    //  +----+
-    //  |  / |
-    //  | /  |
-    //  +----+
-    // bl    br
-
-    let vertices = vec!(
-        top_left,
-        top_right,
-        bot_left,
-        top_right,
-        bot_right,
-        bot_left,
-    );
-
-    let uniforms = uniform! {
-        matrix: camera_frame,
-        tex: &textures.left_fence_texture,
-    };
-    frame.draw(
-        &glium::VertexBuffer::new(window, &vertices).unwrap(),
-        &glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
-        &programs.background_program,
-        &uniforms,
-        params).unwrap();
-}
-
-fn draw_top_fence(
+// Draw a stretch of the world boundary as a fence, running along its longer side
+fn draw_fence(
     frame: &mut glium::Frame,
     window: &glium_sdl2::SDL2Facade,
     textures: &Textures,
     programs: &Programs,
     camera_frame: [[f32; 4]; 4],
-    params: &glium::DrawParameters)
+    params: &glium::DrawParameters,
+    boundary: &Polygon)
 {
+    let (min, max) = boundary.bounding_box();
     let top_left = VertexPosition {
-        position: [-26.0, 116.0],
+        position: [min.x as f32, max.y as f32],
     };
     let top_right = VertexPosition {
-        position: [116.0, 116.0],
+        position: [max.x as f32, max.y as f32],
     };
     let bot_left = VertexPosition {
-        position: [-26.0, 114.5],
+        position: [min.x as f32, min.y as f32],
     };
     let bot_right = VertexPosition {
-        position: [116.0, 114.5],
+        position: [max.x as f32, min.y as f32],
     };
 
     // tl    tr
@@ -844,58 +935,14 @@ fn draw_top_fence(
         bot_left,
     );
 
-    let uniforms = uniform! {
-        matrix: camera_frame,
-        tex: &textures.top_fence_texture,
-    };
-    frame.draw(
-        &glium::VertexBuffer::new(window, &vertices).unwrap(),
-        &glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
-        &programs.background_program,
-        &uniforms,
-        params).unwrap();
-}
-
-fn draw_lower_fence(
-    frame: &mut glium::Frame,
-    window: &glium_sdl2::SDL2Facade,
-    textures: &Textures,
-    programs: &Programs,
-    camera_frame: [[f32; 4]; 4],
-    params: &glium::DrawParameters)
-{
-    let top_left = VertexPosition {
-        position: [-26.0, -24.5],
-    };
-    let top_right = VertexPosition {
-        position: [116.0, -24.5],
-    };
-    let bot_left = VertexPosition {
-        position: [-26.0, -26.0],
-    };
-    let bot_right = VertexPosition {
-        position: [116.0, -26.0],
+    let texture = if max.y - min.y > max.x - min.x {
+        &textures.left_fence_texture
+    } else {
+        &textures.top_fence_texture
     };
-
-    // tl    tr
-    //  +----+
-    //  |  / |
-    //  | /  |
-    //  +----+
-    // bl    br
-
-    let vertices = vec!(
-        top_left,
-        top_right,
-        bot_left,
-        top_right,
-        bot_right,
-        bot_left,
-    );
-
     let uniforms = uniform! {
         matrix: camera_frame,
-        tex: &textures.top_fence_texture,
+        tex: texture,
     };
     frame.draw(
         &glium::VertexBuffer::new(window, &vertices).unwrap(),
@@ -1151,26 +1198,25 @@ pub fn display(
     let mut vertex_buffers_green_hp = vec!();
     let mut vertex_buffers_gui = enum_map! {_ => vec!()};
 
-    let mut vertex_buffers_building = vec!();
-    let mut vertex_buffers_building_two = vec!();
-    let mut vertex_buffers_building_three = vec!();
-    let mut vertex_buffers_building_four = vec!();
-    let mut vertex_buffers_building_five = vec!();
-    let mut vertex_buffers_building_six = vec!();
-    let mut vertex_buffers_building_seven = vec!();
-
     let mut vertex_buffers_barricade = vec!();
     let mut vertex_buffers_path = vec!();
     let mut text_buffers = vec!();
 
     let mut _menu_buttons: Vec<(Vector2, Vector2, Vector2, Vector2)> = vec![];
+    let mut button_labels: Vec<(Gui, String, [f32; 4])> = vec![];
 
     // Compute the vertices in world coordinates of all projectiles
     for p in &state.projectiles {
         let sprite_type = match p.kind {
-            ProjectileKind::Bullet => SpriteType::BulletInAir,
+            // A melee swing has nothing in the air to show
+            ProjectileKind::Bullet { weapon, .. } if !weapon.uses_ammo() => continue,
+            ProjectileKind::Bullet { .. } => SpriteType::BulletInAir,
             ProjectileKind::Casing => SpriteType::BulletCasing,
             ProjectileKind::Fist { owner_index, left_hand } => {
+                // Don't give away the position of units the cops can't see
+                if !is_visible_to_player(state, owner_index) {
+                    continue;
+                }
                 match &state.entities[owner_index].dead_or_alive {
                     DeadOrAlive::Alive { zombie_or_human, .. } => match zombie_or_human {
                         ZombieOrHuman::Zombie { .. } => {
@@ -1208,7 +1254,10 @@ pub fn display(
     }
 
     // Compute the vertices in world coordinates of all entities
-    for entity in &state.entities {
+    for (i, entity) in state.entities.iter().enumerate() {
+        if !is_visible_to_player(state, i) {
+            continue;
+        }
         let sprite = Sprite {
             position: entity.position,
             facing: entity.get_facing_normal(),
@@ -1291,10 +1340,78 @@ pub fn display(
     }
 
 
+    // Compute vertices for vision cones
+    let mut vertex_buffers_vision_cone = vec!();
+    if control.show_vision_cones {
+        for (i, entity) in state.entities.iter().enumerate() {
+            if !is_visible_to_player(state, i) {
+                continue;
+            }
+            match &entity.dead_or_alive {
+                DeadOrAlive::Alive { zombie_or_human: ZombieOrHuman::Zombie { .. }, .. } => {
+                    push_vision_cone_vertices(&mut vertex_buffers_vision_cone, entity, state.zombie_field_of_view,
+                                              ZOMBIE_SIGHT_RADIUS, [0.8, 0.1, 0.1, 0.05]);
+                }
+                // Cops only narrow their view down to the cone while they're aiming
+                DeadOrAlive::Alive {
+                    zombie_or_human: ZombieOrHuman::Human {
+                        human: Human::Cop { cop_type, state_stack, .. },
+                        ..
+                    },
+                    ..
+                } => {
+                    if let Some(CopState::Aiming { .. }) = state_stack.last() {
+                        push_vision_cone_vertices(&mut vertex_buffers_vision_cone, entity, cop_type.field_of_view(),
+                                                  state.day_night.sight_radius(COP_SIGHT_RADIUS), [0.2, 0.4, 1.0, 0.15]);
+                    }
+                }
+                _ => ()
+            }
+        }
+    }
+
+    // Compute vertices for objective markers
+    let mut vertex_buffers_objectives = vec!();
+    for objective in &state.objectives {
+        if objective.status != ObjectiveStatus::InProgress {
+            continue;
+        }
+        match &objective.objective_type {
+            ObjectiveType::Evacuate { zone_center, zone_radius, .. } => {
+                push_circle_vertices(&mut vertex_buffers_objectives, *zone_center, *zone_radius, [0.1, 0.8, 0.2, 0.25]);
+            }
+            ObjectiveType::HoldArea { center, radius, .. } => {
+                push_circle_vertices(&mut vertex_buffers_objectives, *center, *radius, [0.2, 0.4, 1.0, 0.25]);
+            }
+            ObjectiveType::ProtectVip { vip_index } => {
+                if is_visible_to_player(state, *vip_index) {
+                    push_circle_vertices(&mut vertex_buffers_objectives, state.entities[*vip_index].position,
+                                         2.0 * ENTITY_RADIUS, [1.0, 0.85, 0.1, 0.5]);
+                }
+            }
+            _ => ()
+        }
+    }
+
     // Compute vertices for selection highlights
     let mut selection_count = 0;
+    let mut selection_rounds = 0;
+    let mut selection_magazines = 0;
     {
         for i in &state.selection {
+            match &state.entities[*i].dead_or_alive {
+                DeadOrAlive::Alive {
+                    zombie_or_human: ZombieOrHuman::Human {
+                        human: Human::Cop { rounds_in_magazine, reserve_magazines, .. },
+                        ..
+                    },
+                    ..
+                } => {
+                    selection_rounds += rounds_in_magazine;
+                    selection_magazines += reserve_magazines;
+                }
+                _ => ()
+            }
 
             // TODO: Ian M - I think there's a bug where cops that become infected maintain their selection highlight
 
@@ -1344,6 +1461,28 @@ pub fn display(
             GuiType::MoneyUI => {
                 push_gui_vertices(&mut vertex_buffers_gui[SpriteType::MoneyWorldIcon], component);
             }
+            GuiType::RecruitButton { cop_type, .. } => {
+                push_gui_vertices(&mut vertex_buffers_gui[SpriteType::Button], component);
+                let (label, color) = recruit_label(*cop_type, state);
+                button_labels.push((component.clone(), label, color));
+            }
+            GuiType::UpgradePanel { _window_gui, _buttons_gui, .. } => {
+                push_gui_vertices(&mut vertex_buffers_gui[SpriteType::Button], component);
+                button_labels.push((component.clone(), "Upgrades".to_string(), [1.0, 1.0, 1.0, 1.0]));
+                if ui.active_window == ActiveWindow::Upgrades {
+                    push_gui_vertices(&mut vertex_buffers_gui[SpriteType::MenuWindow], _window_gui);
+                    for button in _buttons_gui {
+                        match button.id {
+                            GuiType::UpgradeButton { upgrade_type, .. } => {
+                                push_gui_vertices(&mut vertex_buffers_gui[SpriteType::Button], button);
+                                let (label, color) = upgrade_label(upgrade_type, state);
+                                button_labels.push(((**button).clone(), label, color));
+                            }
+                            _ => ()
+                        }
+                    }
+                }
+            }
             GuiType::SelectingModeIcon => {
                 if control.building_mode {
                     push_gui_vertices(&mut vertex_buffers_gui[SpriteType::BuildingModeIcon], component);
@@ -1427,36 +1566,29 @@ pub fn display(
 
     //  push_gui_vertices(&mut vertex_buffers_gui[SpriteType::SelectionHighlight], component);
 
-    // Compute vertices for buildings
-    for i in 0..state.buildings.len() {
-        let color = [0.1, 0.1, 0.1, 1.0];
-        match &state.building_type.get(&i) {
-            None => (),
-            Some(building_t) => {
-                if **building_t == 3 {
-                    push_building_vertices(&mut vertex_buffers_building_three, &state.buildings[i], color);
-                } else if **building_t == 4 {
-                    push_building_vertices(&mut vertex_buffers_building_two, &state.buildings[i], color);
-                } else if **building_t == 1 {
-                    push_building_vertices(&mut vertex_buffers_building_four, &state.buildings[i], color);
-                } else if **building_t == 2{
-                    push_building_vertices(&mut vertex_buffers_building_seven, &state.buildings[i], color);
-                } else if **building_t == 5 {
-                    push_building_vertices(&mut vertex_buffers_building_five, &state.buildings[i], color);
-                } else if **building_t == 6 {
-                    push_building_vertices(&mut vertex_buffers_building_six, &state.buildings[i], color);
-                } else {
-                    push_building_vertices(&mut vertex_buffers_building, &state.buildings[i], color);
-                }
-            }
-        }
-    }
-
     for barricade in &state.barricades {
         let color = [0.1, 0.1, 0.1, 1.0];
         draw_barricades(&mut vertex_buffers_barricade, barricade, color);
     }
 
+    // Compute vertices for ammo crates, greyed out once they are empty
+    for ammo_crate in &state.ammo_crates {
+        let color = if ammo_crate.magazines > 0 { [0.45, 0.35, 0.1, 1.0] } else { [0.3, 0.3, 0.3, 1.0] };
+        let half_size = 0.5 * AMMO_CRATE_SIZE;
+        let crate_poly = Polygon(vec![
+            ammo_crate.position + vector2(-half_size, -half_size),
+            ammo_crate.position + vector2(half_size, -half_size),
+            ammo_crate.position + vector2(half_size, half_size),
+            ammo_crate.position + vector2(-half_size, half_size),
+        ]);
+        push_building_vertices(&mut vertex_buffers_barricade, &crate_poly, color);
+    }
+
+    for station in &state.police_stations {
+        let half_size = vector2(0.5 * POLICE_STATION_SIZE, 0.5 * POLICE_STATION_SIZE);
+        push_building_vertices(&mut vertex_buffers_barricade, &rectangle(*station - half_size, *station + half_size), [0.1, 0.2, 0.6, 1.0]);
+    }
+
     // Compute vertices for cop paths
     for entity in &state.entities {
         match &entity.dead_or_alive {
@@ -1470,6 +1602,7 @@ pub fn display(
                 let path = match state_stack.last() {
                     Some(CopState::Moving { path, .. }) => path,
                     Some(CopState::AttackingZombie { path, .. }) => path,
+                    Some(CopState::Resupplying { path, .. }) => path,
                     _ => &None
                 };
                 match path {
@@ -1508,108 +1641,49 @@ pub fn display(
     }
 
     // Render regular buildings
-    {
-        let uniforms = uniform! {
-            matrix: camera_frame,
-            tex: &textures.sprite_textures[SpriteType::BuildingOne],
-        };
-        draw_color_sprites(
-            frame,
-            window,
-            &vertex_buffers_building,
-            &programs.sprite_program,
-            params,
-            &uniforms);
-    }
-
-    {
-        let uniforms = uniform! {
-            matrix: camera_frame,
-            tex: &textures.sprite_textures[SpriteType::BuildingThree],
-        };
-        draw_color_sprites(
-            frame,
-            window,
-            &vertex_buffers_building_three,
-            &programs.sprite_program,
-            params,
-            &uniforms);
+    let buildings = (0..state.buildings.len())
+        .filter_map(|i| state.building_type.get(&i).map(|building_type| (&state.buildings[i], *building_type)))
+        .collect();
+    draw_buildings(frame, window, programs, textures, params, camera_frame, buildings);
+
+    // Draw Fence border textures over the world boundary, which is every building without a type
+    for (i, boundary) in state.buildings.iter().enumerate() {
+        if !state.building_type.contains_key(&i) {
+            draw_fence(frame, window, textures, programs, camera_frame, params, boundary);
+        }
     }
 
+    // Render barricades
     {
         let uniforms = uniform! {
-            matrix: camera_frame,
-            tex: &textures.sprite_textures[SpriteType::BuildingTwo],
+            matrix: camera_frame
         };
         draw_color_sprites(
             frame,
             window,
-            &vertex_buffers_building_two,
-            &programs.sprite_program,
+            &vertex_buffers_barricade,
+            &programs.shape_program,
             params,
-            &uniforms);
+            &uniforms,
+        )
     }
 
+    // Render vision cones beneath the entities
     {
         let uniforms = uniform! {
-            matrix: camera_frame,
-            tex: &textures.sprite_textures[SpriteType::BuildingFour],
-        };
-        draw_color_sprites(
-            frame,
-            window,
-            &vertex_buffers_building_four,
-            &programs.sprite_program,
-            params,
-            &uniforms);
-    }
-    {
-        let uniforms = uniform! {
-            matrix: camera_frame,
-            tex: &textures.sprite_textures[SpriteType::BuildingFive],
-        };
-        draw_color_sprites(
-            frame,
-            window,
-            &vertex_buffers_building_five,
-            &programs.sprite_program,
-            params,
-            &uniforms);
-    }
-    {
-        let uniforms = uniform! {
-            matrix: camera_frame,
-            tex: &textures.sprite_textures[SpriteType::BuildingSix],
-        };
-        draw_color_sprites(
-            frame,
-            window,
-            &vertex_buffers_building_six,
-            &programs.sprite_program,
-            params,
-            &uniforms);
-    }
-    {
-        let uniforms = uniform! {
-            matrix: camera_frame,
-            tex: &textures.sprite_textures[SpriteType::BuildingSeven],
+            matrix: camera_frame
         };
         draw_color_sprites(
             frame,
             window,
-            &vertex_buffers_building_seven,
-            &programs.sprite_program,
+            &vertex_buffers_vision_cone,
+            &programs.shape_program,
             params,
-            &uniforms);
+            &uniforms,
+        )
     }
 
-    // Draw Fence border textures
-    draw_left_fence(frame, window, textures, programs, camera_frame, params);
-    draw_right_fence(frame, window, textures, programs, camera_frame, params);
-    draw_top_fence(frame, window, textures, programs, camera_frame, params);
-    draw_lower_fence(frame, window, textures, programs, camera_frame, params);
-
-    // Render barricades
+    // Render objective zones and the VIP marker beneath the entities
     {
         let uniforms = uniform! {
             matrix: camera_frame
@@ -1617,7 +1691,7 @@ pub fn display(
         draw_color_sprites(
             frame,
             window,
-            &vertex_buffers_barricade,
+            &vertex_buffers_objectives,
             &programs.shape_program,
             params,
             &uniforms,
@@ -1668,6 +1742,78 @@ pub fn display(
         params,
         &uniforms);
 
+    // Darken the world at night, with cops' flashlights cutting through it
+    let darkness = state.day_night.darkness() as f32;
+    if darkness > 0.0 {
+        let mut vertex_buffers_night = vec!();
+        push_night_overlay_vertices(&mut vertex_buffers_night, [0.0, 0.0, 0.1, 0.6 * darkness]);
+        let uniforms = uniform! {
+            matrix: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0f32],
+            ]
+        };
+        draw_color_sprites(
+            frame,
+            window,
+            &vertex_buffers_night,
+            &programs.gui_program,
+            params,
+            &uniforms);
+
+        let mut vertex_buffers_flashlight = vec!();
+        let flashlight_radius = state.day_night.sight_radius(COP_SIGHT_RADIUS);
+        for (i, entity) in state.entities.iter().enumerate() {
+            if !is_visible_to_player(state, i) {
+                continue;
+            }
+            match &entity.dead_or_alive {
+                DeadOrAlive::Alive {
+                    zombie_or_human: ZombieOrHuman::Human {
+                        human: Human::Cop { cop_type, .. },
+                        ..
+                    },
+                    ..
+                } => {
+                    push_vision_cone_vertices(&mut vertex_buffers_flashlight, entity, cop_type.field_of_view(),
+                                              flashlight_radius, [1.0, 0.95, 0.7, 0.15 * darkness]);
+                }
+                _ => ()
+            }
+        }
+        let uniforms = uniform! {
+            matrix: camera_frame
+        };
+        draw_color_sprites(
+            frame,
+            window,
+            &vertex_buffers_flashlight,
+            &programs.shape_program,
+            params,
+            &uniforms);
+    }
+
+    // Render fog of war over everything in the world
+    match &state.fog_of_war {
+        Some(fog_of_war) => {
+            let mut vertex_buffers_fog = vec!();
+            push_fog_of_war_vertices(&mut vertex_buffers_fog, fog_of_war);
+            let uniforms = uniform! {
+                matrix: camera_frame
+            };
+            draw_color_sprites(
+                frame,
+                window,
+                &vertex_buffers_fog,
+                &programs.shape_program,
+                params,
+                &uniforms);
+        }
+        None => ()
+    }
+
     // Render GUI
     let mat_gui = [
         [1.0, 0.0, 0.0, 0.0],
@@ -1712,7 +1858,7 @@ pub fn display(
                 params,
                 &uniforms);
         } else if _gui_type == SpriteType::MenuWindow {
-            if ui.active_window == ActiveWindow::Menu {
+            if ui.active_window == ActiveWindow::Menu || ui.active_window == ActiveWindow::Upgrades {
                 let uniforms = uniform! {
                         matrix: mat_gui,
                     };
@@ -1764,6 +1910,7 @@ pub fn display(
                 };
             // Draw the text showing the number of cops next to the UI cop icon
             draw_cop_num(window, selection_count, frame, &font.medres());
+            draw_ammo_num(window, selection_rounds, selection_magazines, frame, &font.lowres());
             draw_color_sprites(
                 frame,
                 window,
@@ -2065,6 +2212,20 @@ pub fn display(
         }
     }
 
+    // Render shop and upgrade button text
+    for (button, label, color) in &button_labels {
+        draw_button_label(window, button, label, *color, frame, &font.lowres());
+    }
+
+    // Render the wave counter in survival mode
+    match &state.survival {
+        Some(survival) => draw_survival_info(window, survival, frame, &font.lowres()),
+        None => ()
+    }
+
+    // Render objective progress
+    draw_objectives(window, &state.objectives, frame, &font.lowres());
+
     // Render Menu Button Text
     let mat = Mat4::init_id_matrix();
     for i in 0..text_buffers.len() {
@@ -2140,6 +2301,121 @@ fn draw_cop_num(window: &glium_sdl2::SDL2Facade, cop_num: i32, frame: &mut glium
     }
 }
 
+// Draw the rounds and spare magazines the selected cops have left under the cop number
+fn draw_ammo_num(window: &glium_sdl2::SDL2Facade, rounds: i64, magazines: i64, frame: &mut glium::Frame, font: &FontTexture) {
+    let system = glium_text::TextSystem::new(window);
+    let ammo_display = format!("{} rds + {} mags", rounds, magazines);
+    let str_slice: &str = &ammo_display[..];
+    let text = glium_text::TextDisplay::new(&system, font, str_slice);
+    // Warn the player in red when the selected cops are about to run dry
+    let color = if magazines > 0 { [0.0, 0.0, 0.05, 1.0f32] } else { [0.7, 0.0, 0.0, 1.0f32] };
+    let font_scale_down = 60.0;
+    let (w, h) = frame.get_dimensions();
+    let matrix = [
+        [1.0 / font_scale_down, 0.0, 0.0, 0.0],
+        [0.0, 1.0 * (w as f32) / (h as f32) / font_scale_down, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [-0.88, -0.96, 0.0, 1.0f32],
+    ];
+
+    glium_text::draw(&text, &system, frame, matrix, color);
+}
+
+
+// Draw the wave in progress and the countdown to the next one in survival mode
+fn draw_survival_info(window: &glium_sdl2::SDL2Facade, survival: &Survival, frame: &mut glium::Frame, font: &FontTexture) {
+    let system = glium_text::TextSystem::new(window);
+    let survival_display = format!("Wave {} - next wave in {}s",
+                                   survival.wave, survival.time_until_next_wave.ceil() as u32);
+    let str_slice: &str = &survival_display[..];
+    let text = glium_text::TextDisplay::new(&system, font, str_slice);
+    let color = [0.9, 0.1, 0.1, 1.0f32];
+    let font_scale_down = 50.0;
+    let (w, h) = frame.get_dimensions();
+    let matrix = [
+        [1.0 / font_scale_down, 0.0, 0.0, 0.0],
+        [0.0, 1.0 * (w as f32) / (h as f32) / font_scale_down, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [-0.3, 0.78, 0.0, 1.0f32],
+    ];
+
+    glium_text::draw(&text, &system, frame, matrix, color);
+}
+
+fn draw_objectives(window: &glium_sdl2::SDL2Facade, objectives: &Vec<Objective>, frame: &mut glium::Frame, font: &FontTexture) {
+    let system = glium_text::TextSystem::new(window);
+    let font_scale_down = 60.0;
+    let (w, h) = frame.get_dimensions();
+
+    for (i, objective) in objectives.iter().enumerate() {
+        let objective_display = objective.description();
+        let str_slice: &str = &objective_display[..];
+        let text = glium_text::TextDisplay::new(&system, font, str_slice);
+        let color = match objective.status {
+            ObjectiveStatus::InProgress => [1.0, 1.0, 1.0, 1.0f32],
+            ObjectiveStatus::Passed => [0.2, 0.9, 0.2, 1.0f32],
+            ObjectiveStatus::Failed => [0.9, 0.1, 0.1, 1.0f32],
+        };
+        let matrix = [
+            [1.0 / font_scale_down, 0.0, 0.0, 0.0],
+            [0.0, 1.0 * (w as f32) / (h as f32) / font_scale_down, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [-0.98, 0.7 - 0.06 * i as f32, 0.0, 1.0f32],
+        ];
+
+        glium_text::draw(&text, &system, frame, matrix, color);
+    }
+}
+
+// Label a reinforcement button with its price, or why it can't be bought right now
+fn recruit_label(cop_type: CopType, state: &State) -> (String, [f32; 4]) {
+    let name = match cop_type {
+        CopType::Normal => "Cop",
+        CopType::Soldier => "Soldier",
+    };
+    let reinforcements = &state.reinforcements;
+    let label = if reinforcements.recruited >= reinforcements.cap {
+        format!("{} (max)", name)
+    } else if reinforcements.cooldown(cop_type) > 0.0 {
+        format!("{} {}s", name, reinforcements.cooldown(cop_type).ceil())
+    } else {
+        format!("{} ${}", name, cop_type.recruit_cost())
+    };
+    (label, if reinforcements.can_recruit(cop_type, state.money) { [1.0, 1.0, 1.0, 1.0] } else { [0.5, 0.5, 0.5, 1.0] })
+}
+
+// Label an upgrade button with its level and the price of the next level
+fn upgrade_label(upgrade_type: UpgradeType, state: &State) -> (String, [f32; 4]) {
+    let upgrades = &state.upgrades;
+    let level = upgrades.level(upgrade_type);
+    let label = if !upgrades.is_unlocked(upgrade_type) {
+        match upgrade_type.prerequisite() {
+            Some((prerequisite, required_level)) =>
+                format!("{} - needs {} {}", upgrade_type.name(), prerequisite.name(), required_level),
+            None => upgrade_type.name().to_string(),
+        }
+    } else if level >= upgrade_type.max_level() {
+        format!("{} {}/{} - max", upgrade_type.name(), level, upgrade_type.max_level())
+    } else {
+        format!("{} {}/{} - ${}", upgrade_type.name(), level, upgrade_type.max_level(), upgrade_type.cost(level))
+    };
+    (label, if upgrades.can_buy(upgrade_type, state.money) { [1.0, 1.0, 1.0, 1.0] } else { [0.5, 0.5, 0.5, 1.0] })
+}
+
+// Draw text stretched to fill the width of a button
+fn draw_button_label(window: &glium_sdl2::SDL2Facade, button: &Gui, label: &str, color: [f32; 4], frame: &mut glium::Frame, font: &FontTexture) {
+    let system = glium_text::TextSystem::new(window);
+    let text = glium_text::TextDisplay::new(&system, font, label);
+
+    let text_width = text.get_width() as f64;
+    let button_width = button.top_right.x - button.top_left.x;
+    let text_height = 0.05;
+    let matrix = Mat4::init_id_matrix()
+        .translation(Vector4 { x: button.top_left.x, y: button.top_left.y - 0.065, z: 0.0, w: 0.0 })
+        .scale(Vector4 { x: button_width / text_width, y: text_height, z: 1.0, w: 1.0 }).as_f32_array();
+
+    glium_text::draw(&text, &system, frame, matrix, color);
+}
 
 // Draw the remaining number of zombies in the world (number)
 fn draw_remaining_zombie_num(window: &glium_sdl2::SDL2Facade, zombie_num: usize, frame: &mut glium::Frame, font: &FontTexture) {
@@ -2386,6 +2662,24 @@ pub fn display_main_menu(
     }
 }
 
+// List each objective under the stats on the end screens, one line per objective
+fn draw_objective_results(system: &glium_text::TextSystem, frame: &mut glium::Frame, font: &FontTexture,
+                          objectives: &Vec<Objective>, mut matrix: Mat4, color: [f32; 4]) {
+    for objective in objectives {
+        let result = match objective.status {
+            ObjectiveStatus::Passed => "PASSED",
+            ObjectiveStatus::Failed => "FAILED",
+            ObjectiveStatus::InProgress => "INCOMPLETE",
+        };
+        let text_display = format!("[{}] {}", result, objective.description());
+        let str_slice: &str = &text_display[..];
+        let text = glium_text::TextDisplay::new(system, font, str_slice);
+        let translate_offset = Vector4 { x: 0.0, y: -0.15, z: 0.0, w: 0.0 };
+        matrix = matrix.translation(translate_offset);
+        glium_text::draw(&text, system, frame, matrix.as_f32_array(), color);
+    }
+}
+
 fn compute_score(entity_counts: &EntityCounts) -> f64 {
     (100 * (entity_counts.cops + entity_counts.civilians)) as f64
         / entity_counts.total() as f64
@@ -2400,6 +2694,8 @@ pub fn display_loss_screen(
     camera_frame: [[f32; 4]; 4],
     ui: &mut Component,
     entity_counts: &EntityCounts,
+    waves_survived: Option<u32>,
+    objectives: &Vec<Objective>,
     fonts: &FontPkg,
     game_state: &mut GameState,
 ) {
@@ -2502,7 +2798,9 @@ pub fn display_loss_screen(
 
     // Difficulty
     let mut text_diff = "".to_string();
-    if game_state.easy {
+    if game_state.survival {
+        text_diff = "Mode: SURVIVAL".to_string();
+    } else if game_state.easy {
         text_diff = "Difficulty: EASY".to_string();
     } else if game_state.medium {
         text_diff = "Difficulty: MEDIUM".to_string();
@@ -2518,7 +2816,10 @@ pub fn display_loss_screen(
     glium_text::draw(&text, &system, frame, matrix.as_f32_array(), color);
 
     // Score
-    let text_display = format!("Score: {}", score);
+    let text_display = match waves_survived {
+        Some(waves_survived) => format!("Waves survived: {}", waves_survived),
+        None => format!("Score: {}", score),
+    };
     let str_slice: &str = &text_display[..];
     let text = glium_text::TextDisplay::new(&system, font.medres(), str_slice);
     let color = [1.0, 0.0, 0.0, 1.0f32];
@@ -2537,6 +2838,9 @@ pub fn display_loss_screen(
     let translate_offset = Vector4 { x: 0.0, y: -0.2, z: 0.0, w: 0.0 };
     matrix = matrix.translation(translate_offset);
     glium_text::draw(&text, &system, frame, matrix.as_f32_array(), color);
+
+    // Objectives
+    draw_objective_results(&system, frame, font.medres(), objectives, matrix, color);
 }
 
 pub fn display_victory_screen(
@@ -2549,6 +2853,7 @@ pub fn display_victory_screen(
     ui: &mut Component,
     // state: &State,
     entity_counts: &EntityCounts,
+    objectives: &Vec<Objective>,
     fonts: &FontPkg,
     game_state: &mut GameState,
 ) {
@@ -2680,6 +2985,9 @@ pub fn display_victory_screen(
     let translate_offset = Vector4 { x: 0.0, y: -0.2, z: 0.0, w: 0.0 };
     matrix = matrix.translation(translate_offset);
     glium_text::draw(&text, &system, frame, matrix.as_f32_array(), color);
+
+    // Objectives
+    draw_objective_results(&system, frame, font.medres(), objectives, matrix, color);
 }
 
 pub fn display_difficulty_screen(
@@ -2785,3 +3093,300 @@ pub fn display_difficulty_screen(
     glium_text::draw(&text, &system, frame, matrix.as_f32_array(), color);
 }
 
+
+pub fn display_campaign_screen(
+    frame: &mut glium::Frame,
+    window: &glium_sdl2::SDL2Facade,
+    programs: &Programs,
+    _textures: &Textures,
+    params: &glium::DrawParameters,
+    camera_frame: [[f32; 4]; 4],
+    ui: &mut Component,
+    missions: &Vec<Mission>,
+    fonts: &FontPkg,
+) {
+    let font = fonts.get("Consola").unwrap();
+
+    let mat = Mat4::init_id_matrix();
+    draw_main_menu_background(frame, window, _textures, programs, camera_frame, params);
+
+    let mut vertex_buffers_gui = enum_map! {_ => vec!()};
+    let mut button_labels: Vec<(Gui, String, [f32; 4])> = vec![];
+
+    // Compute vertices for GUI
+    for component in &mut ui.components {
+        match &component.id {
+            GuiType::Button { text, highlight } => {
+                let color = if *highlight { [0.1, 0.1, 0.1, 1.0] } else { [1.0, 1.0, 1.0, 1.0] };
+                button_labels.push((component.clone(), text.clone(), color));
+                push_gui_vertices(&mut vertex_buffers_gui[SpriteType::Button], component);
+            }
+            GuiType::MissionButton { mission_index, unlocked, highlight } => {
+                let label = format!("{}. {}", mission_index + 1, missions[*mission_index].name);
+                let color = if !*unlocked {
+                    [0.5, 0.5, 0.5, 1.0]
+                } else if *highlight {
+                    [0.1, 0.1, 0.1, 1.0]
+                } else {
+                    [1.0, 1.0, 1.0, 1.0]
+                };
+                button_labels.push((component.clone(), label, color));
+                push_gui_vertices(&mut vertex_buffers_gui[SpriteType::Button], component);
+            }
+            _ => (),
+        };
+    }
+
+    // Render GUI
+    let mat_gui = mat.as_f32_array();
+    for (_gui_type, vertex_buffer) in &vertex_buffers_gui {
+        if _gui_type == SpriteType::Button {
+            let uniforms = uniform! {
+                    matrix: mat_gui,
+                };
+            draw_color_sprites(
+                frame,
+                window,
+                &vertex_buffer,
+                &programs.gui_program,
+                params,
+                &uniforms);
+        }
+    }
+
+    // Render button text
+    for (button, label, color) in &button_labels {
+        draw_button_label(window, button, label, *color, frame, &font.medres());
+    }
+
+    // Render title text
+    let system = glium_text::TextSystem::new(window);
+    let text = glium_text::TextDisplay::new(&system, font.highres(), "Campaign");
+    let color = [1.0, 1.0, 1.0, 1.0f32];
+    let text_width = text.get_width() as f64;
+    let (w, h) = frame.get_dimensions();
+    let scale_factor = Vector4 { x: 0.5 / text_width, y: 0.5 * (w as f64) / (h as f64) / text_width, z: 1.0, w: 1.0 };
+    let translation_offset = Vector4 { x: -0.25, y: 0.65, z: 0.0, w: 0.0 };
+    let matrix = mat.scale(scale_factor).translation(translation_offset);
+    glium_text::draw(&text, &system, frame, matrix.as_f32_array(), color);
+}
+
+pub fn display_briefing_screen(
+    frame: &mut glium::Frame,
+    window: &glium_sdl2::SDL2Facade,
+    programs: &Programs,
+    _textures: &Textures,
+    params: &glium::DrawParameters,
+    camera_frame: [[f32; 4]; 4],
+    ui: &mut Component,
+    mission: &Mission,
+    fonts: &FontPkg,
+) {
+    let font = fonts.get("Consola").unwrap();
+
+    let mat = Mat4::init_id_matrix();
+    draw_main_menu_background(frame, window, _textures, programs, camera_frame, params);
+
+    let mut vertex_buffers_gui = enum_map! {_ => vec!()};
+    let mut button_labels: Vec<(Gui, String, [f32; 4])> = vec![];
+
+    // Compute vertices for GUI
+    for component in &mut ui.components {
+        match &component.id {
+            GuiType::Button { text, highlight } => {
+                let color = if *highlight { [0.1, 0.1, 0.1, 1.0] } else { [1.0, 1.0, 1.0, 1.0] };
+                button_labels.push((component.clone(), text.clone(), color));
+                push_gui_vertices(&mut vertex_buffers_gui[SpriteType::Button], component);
+            }
+            _ => (),
+        };
+    }
+
+    // Render GUI
+    let mat_gui = mat.as_f32_array();
+    for (_gui_type, vertex_buffer) in &vertex_buffers_gui {
+        if _gui_type == SpriteType::Button {
+            let uniforms = uniform! {
+                    matrix: mat_gui,
+                };
+            draw_color_sprites(
+                frame,
+                window,
+                &vertex_buffer,
+                &programs.gui_program,
+                params,
+                &uniforms);
+        }
+    }
+
+    // Render button text
+    for (button, label, color) in &button_labels {
+        draw_button_label(window, button, label, *color, frame, &font.medres());
+    }
+
+    // Render mission name
+    let system = glium_text::TextSystem::new(window);
+    let text = glium_text::TextDisplay::new(&system, font.highres(), &mission.name[..]);
+    let color = [1.0, 1.0, 1.0, 1.0f32];
+    let text_width = text.get_width() as f64;
+    let (w, h) = frame.get_dimensions();
+    let scale_factor = Vector4 { x: 1.0 / text_width, y: 1.0 * (w as f64) / (h as f64) / text_width, z: 1.0, w: 1.0 };
+    let translation_offset = Vector4 { x: -0.5, y: 0.6, z: 0.0, w: 0.0 };
+    let matrix = mat.scale(scale_factor).translation(translation_offset);
+    glium_text::draw(&text, &system, frame, matrix.as_f32_array(), color);
+
+    // Render the briefing, followed by the objectives
+    let objectives: Vec<String> = mission.objectives.iter()
+        .map(|objective| format!("- {}", objective.description()))
+        .chain(if mission.protect_vip { Some("- Protect the VIP".to_string()) } else { None })
+        .chain(mission.evacuate_to_extraction.map(|required| format!("- Evacuate civilians (0/{})", required)))
+        .collect();
+    let blank_line = String::new();
+    let font_scale_down = 40.0;
+    for (i, line) in mission.briefing.iter().chain(Some(&blank_line)).chain(objectives.iter()).enumerate() {
+        let text = glium_text::TextDisplay::new(&system, font.medres(), &line[..]);
+        let matrix = [
+            [1.0 / font_scale_down, 0.0, 0.0, 0.0],
+            [0.0, 1.0 * (w as f32) / (h as f32) / font_scale_down, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [-0.8, 0.35 - 0.08 * i as f32, 0.0, 1.0f32],
+        ];
+        glium_text::draw(&text, &system, frame, matrix, color);
+    }
+}
+
+fn spawn_zone_color(kind: SpawnKind) -> [f32; 4] {
+    match kind {
+        SpawnKind::Civilian => [0.9, 0.9, 0.9, 0.2],
+        SpawnKind::Cop => [0.2, 0.4, 1.0, 0.25],
+        SpawnKind::Infected => [0.2, 0.9, 0.2, 0.25],
+    }
+}
+
+fn rectangle(min: Vector2, max: Vector2) -> Polygon {
+    Polygon(vec![min, vector2(max.x, min.y), max, vector2(min.x, max.y)])
+}
+
+pub fn display_map_editor(
+    frame: &mut glium::Frame,
+    window: &glium_sdl2::SDL2Facade,
+    programs: &Programs,
+    textures: &Textures,
+    params: &glium::DrawParameters,
+    camera_frame: Mat4,
+    editor: &Editor,
+    fonts: &FontPkg,
+) {
+    let font = fonts.get("Consola").unwrap();
+
+    frame.clear_color(0.2, 0.2, 0.2, 1.0);
+
+    let camera_frame = camera_frame.as_f32_array();
+    draw_outside_background(frame, window, textures, programs, camera_frame, params);
+    draw_background(frame, window, textures, programs, camera_frame, params);
+
+    let buildings = editor.map.buildings.iter()
+        .map(|(building, building_type)| (building, *building_type))
+        .collect();
+    draw_buildings(frame, window, programs, textures, params, camera_frame, buildings);
+
+    // Compute vertices for everything else on the map, in draw order
+    let mut vertex_buffers_shapes = vec!();
+
+    for boundary in &editor.map.boundaries {
+        push_building_vertices(&mut vertex_buffers_shapes, boundary, [0.35, 0.35, 0.35, 1.0]);
+    }
+
+    for zone in &editor.map.spawn_zones {
+        push_building_vertices(&mut vertex_buffers_shapes, &rectangle(zone.min, zone.max), spawn_zone_color(zone.kind));
+    }
+
+    if let (EditorTool::PaintSpawn(kind), Some((start, end))) = (editor.tool, editor.draft_zone) {
+        let min = vector2(start.x.min(end.x), start.y.min(end.y));
+        let max = vector2(start.x.max(end.x), start.y.max(end.y));
+        if max.x > min.x && max.y > min.y {
+            let mut color = spawn_zone_color(kind);
+            color[3] *= 2.0;
+            push_building_vertices(&mut vertex_buffers_shapes, &rectangle(min, max), color);
+        }
+    }
+
+    for extraction_point in &editor.map.extraction_points {
+        push_circle_vertices(&mut vertex_buffers_shapes, extraction_point.center, extraction_point.radius, [0.1, 0.8, 0.2, 0.25]);
+    }
+
+    for position in &editor.map.ammo_crates {
+        let half_size = vector2(0.5 * AMMO_CRATE_SIZE, 0.5 * AMMO_CRATE_SIZE);
+        push_building_vertices(&mut vertex_buffers_shapes, &rectangle(*position - half_size, *position + half_size), [0.45, 0.35, 0.1, 1.0]);
+    }
+
+    for position in &editor.map.police_stations {
+        let half_size = vector2(0.5 * POLICE_STATION_SIZE, 0.5 * POLICE_STATION_SIZE);
+        push_building_vertices(&mut vertex_buffers_shapes, &rectangle(*position - half_size, *position + half_size), [0.1, 0.2, 0.6, 1.0]);
+    }
+
+    // Highlight the selected building and the vertices that can be dragged
+    if let Some(index) = editor.selected {
+        let building = &editor.map.buildings[index].0;
+        push_building_vertices(&mut vertex_buffers_shapes, building, [1.0, 0.85, 0.1, 0.3]);
+        for vertex in &building.0 {
+            push_circle_vertices(&mut vertex_buffers_shapes, *vertex, 0.5 * EDITOR_VERTEX_GRAB_RADIUS, [1.0, 0.85, 0.1, 0.8]);
+        }
+    }
+
+    // Building being drawn
+    for i in 0..editor.draft.len() {
+        push_circle_vertices(&mut vertex_buffers_shapes, editor.draft[i], 0.3, [1.0, 1.0, 1.0, 0.8]);
+        if i > 0 {
+            push_path_vertices(&mut vertex_buffers_shapes, editor.draft[i - 1], editor.draft[i], [1.0, 1.0, 1.0, 0.8]);
+        }
+    }
+
+    // Path preview
+    for point in editor.path_start.iter().chain(editor.path_end.iter()) {
+        push_circle_vertices(&mut vertex_buffers_shapes, *point, 0.5, [1.0, 0.3, 0.3, 0.8]);
+    }
+    if let Some(path) = &editor.path {
+        for i in 1..path.len() {
+            push_path_vertices(&mut vertex_buffers_shapes, path[i - 1], path[i], [1.0, 0.3, 0.3, 1.0]);
+        }
+    }
+
+    {
+        let uniforms = uniform! {
+            matrix: camera_frame
+        };
+        draw_color_sprites(
+            frame,
+            window,
+            &vertex_buffers_shapes,
+            &programs.shape_program,
+            params,
+            &uniforms);
+    }
+
+    // Render the current tool and the controls
+    let system = glium_text::TextSystem::new(window);
+    let font_scale_down = 60.0;
+    let (w, h) = frame.get_dimensions();
+    let lines = [
+        format!("Tool: {}", editor.tool.name()),
+        format!("Building type: {}", editor.building_type),
+        editor.status.clone(),
+        String::new(),
+        "Tab: change tool   0-6: building type".to_string(),
+        "Left click: place/select/drag   Right click: finish/erase".to_string(),
+        "Delete: remove building   Return: finish building".to_string(),
+        "F5: save   F6: test play   Escape: main menu".to_string(),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        let text = glium_text::TextDisplay::new(&system, font.lowres(), &line[..]);
+        let matrix = [
+            [1.0 / font_scale_down, 0.0, 0.0, 0.0],
+            [0.0, 1.0 * (w as f32) / (h as f32) / font_scale_down, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [-0.98, 0.94 - 0.06 * i as f32, 0.0, 1.0f32],
+        ];
+        glium_text::draw(&text, &system, frame, matrix, [1.0, 1.0, 1.0, 1.0f32]);
+    }
+}
@@ -8,7 +8,10 @@ use crate::core::matrix::Mat4;
 use crate::core::vector::*;
 use crate::simulation::control::*;
 use crate::simulation::game_state::GameState;
-use crate::simulation::state::State;
+use crate::simulation::state::{State, CopType};
+use crate::simulation::reinforcements::recruit;
+use crate::simulation::upgrades::*;
+use crate::simulation::campaign::CampaignProgress;
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum GuiType {
@@ -42,6 +45,24 @@ pub enum GuiType {
     MoneyHighlight,
     BuildingModeIcon,
     SelectingModeIcon,
+    RecruitButton {
+        cop_type: CopType,
+        highlight: bool,
+    },
+    UpgradePanel {
+        _window_gui: Box<Gui>,
+        _buttons_gui: Vec<Box<Gui>>,
+        highlight: bool,
+    },
+    UpgradeButton {
+        upgrade_type: UpgradeType,
+        highlight: bool,
+    },
+    MissionButton {
+        mission_index: usize,
+        unlocked: bool,
+        highlight: bool,
+    },
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -51,6 +72,9 @@ pub enum ActiveWindow {
     Instruction,
     MainMenu,
     Difficulty,
+    Upgrades,
+    Campaign,
+    Briefing,
 }
 
 pub static mut CURRENT: ActiveWindow = ActiveWindow::Game;
@@ -75,6 +99,28 @@ impl Component {
         let money_icon = Gui::new(GuiType::MoneyUI, 0.08, 0.08, Vector2 { x: 0.46, y: 0.92 });
         let building_icon = Gui::new(GuiType::BuildingModeIcon, 0.15, 0.15, Vector2 { x: 0.91, y: -0.9 });
         let selecting_icon = Gui::new(GuiType::SelectingModeIcon, 0.15, 0.15, Vector2 { x: 0.91, y: -0.9 });
+        let recruit_cop_ui = Gui::new(GuiType::RecruitButton { cop_type: CopType::Normal, highlight: false },
+                                      0.22, 0.09, Vector2 { x: 0.42, y: -0.92 });
+        let recruit_soldier_ui = Gui::new(GuiType::RecruitButton { cop_type: CopType::Soldier, highlight: false },
+                                          0.22, 0.09, Vector2 { x: 0.66, y: -0.92 });
+
+        // Upgrades that depend on another are indented beneath it
+        let mut upgrade_buttons_ui = vec!();
+        for i in 0..UPGRADE_TYPES.len() {
+            let upgrade_type = UPGRADE_TYPES[i];
+            let indent = match upgrade_type.prerequisite() { Some(_) => 0.06, None => 0.0 };
+            upgrade_buttons_ui.push(Box::new(Gui::new(GuiType::UpgradeButton { upgrade_type, highlight: false },
+                                                      0.8 - 2.0 * indent, 0.09,
+                                                      Vector2 { x: indent, y: 0.3 - 0.12 * i as f64 })));
+        }
+        let upgrade_window_ui = Gui::new(GuiType::Window, 1.0, 0.9, Vector2 { x: 0.0, y: 0.0 });
+        let upgrade_panel_ui = Gui::new(GuiType::UpgradePanel {
+            _window_gui: Box::new(upgrade_window_ui),
+            _buttons_gui: upgrade_buttons_ui,
+            highlight: false,
+        },
+                                        0.22, 0.09,
+                                        Vector2 { x: 0.18, y: -0.92 });
         let drag_ui = Gui::new(GuiType::SelectionDrag, 0.0, 0.0, Vector2 { x: 0.0, y: 0.0 });
         let box_ui = Gui::new(GuiType::Window, 1.8, 1.8, Vector2 { x: 0.0, y: 0.0 });
         let button1 = GuiType::Button { text: "Exit".to_string(), highlight: false };
@@ -100,7 +146,7 @@ impl Component {
 
 
         Component {
-            components: vec![selected_ui, drag_ui, menu_ui, cop_highlight, civilian_highlight, zombie_highlight, money_highlight, cop_ui, civilian_ui, zombie_ui, money_icon, building_icon, selecting_icon],
+            components: vec![selected_ui, drag_ui, menu_ui, cop_highlight, civilian_highlight, zombie_highlight, money_highlight, cop_ui, civilian_ui, zombie_ui, money_icon, building_icon, selecting_icon, recruit_cop_ui, recruit_soldier_ui, upgrade_panel_ui],
             active_window: ActiveWindow::Game,
         }
     }
@@ -109,11 +155,17 @@ impl Component {
     pub fn init_main_menu_gui() -> Component {
         // main menu buttons
         let button_start = GuiType::Button { text: "Start".to_string(), highlight: false };
+        let button_survival = GuiType::Button { text: "Survival".to_string(), highlight: false };
         let button_tutorial = GuiType::Button { text: "Tutorial".to_string(), highlight: false};
         let button_exit = GuiType::Button { text: "Exit".to_string(), highlight: false };
-        let button_start_ui = Gui::new(button_start, 0.20, 0.12, Vector2 { x: 0.0, y: -0.2 });
-        let button_tutorial_ui = Gui::new(button_tutorial, 0.30, 0.12, Vector2 {x: 0.0, y: -0.35});
-        let button_exit_ui = Gui::new(button_exit, 0.15, 0.12, Vector2 { x: 0.0, y: -0.65 });
+        let button_campaign = GuiType::Button { text: "Campaign".to_string(), highlight: false };
+        let button_map_editor = GuiType::Button { text: "Map Editor".to_string(), highlight: false };
+        let button_start_ui = Gui::new(button_start, 0.20, 0.11, Vector2 { x: 0.0, y: -0.08 });
+        let button_campaign_ui = Gui::new(button_campaign, 0.30, 0.11, Vector2 { x: 0.0, y: -0.20 });
+        let button_survival_ui = Gui::new(button_survival, 0.30, 0.11, Vector2 { x: 0.0, y: -0.32 });
+        let button_map_editor_ui = Gui::new(button_map_editor, 0.35, 0.11, Vector2 { x: 0.0, y: -0.44 });
+        let button_tutorial_ui = Gui::new(button_tutorial, 0.30, 0.11, Vector2 {x: 0.0, y: -0.56});
+        let button_exit_ui = Gui::new(button_exit, 0.15, 0.11, Vector2 { x: 0.0, y: -0.8 });
 
         // box containment for main menu settings
         let box_ui = Gui::new(GuiType::Window, 1.8, 1.8, Vector2 { x: 0.0, y: 0.0 });
@@ -133,12 +185,12 @@ impl Component {
             text: "Setting".to_string(),
             highlight: false,
         },
-                                  0.30, 0.12,
-                                  Vector2 { x: 0.0, y: -0.50 });
+                                  0.30, 0.11,
+                                  Vector2 { x: 0.0, y: -0.68 });
 
         // component initialization
         Component {
-            components: vec![button_start_ui, button_tutorial_ui, button_exit_ui, setting_ui],
+            components: vec![button_start_ui, button_campaign_ui, button_survival_ui, button_map_editor_ui, button_tutorial_ui, button_exit_ui, setting_ui],
             active_window: ActiveWindow::MainMenu,
         }
     }
@@ -185,6 +237,49 @@ impl Component {
         }
     }
 
+    // initializer for campaign selection scene's GUI, with one button per mission
+    pub fn init_campaign_gui(mission_count: usize, progress: &CampaignProgress) -> Component {
+        let mut components = vec![];
+        for i in 0..mission_count {
+            let button_mission = GuiType::MissionButton {
+                mission_index: i,
+                unlocked: progress.is_unlocked(i),
+                highlight: false,
+            };
+            components.push(Gui::new(button_mission, 0.8, 0.1, Vector2 { x: 0.0, y: 0.4 - 0.15 * i as f64 }));
+        }
+
+        let button_back = GuiType::Button { text: "Back".to_string(), highlight: false };
+        components.push(Gui::new(button_back, 0.15, 0.09, Vector2 { x: 0.0, y: -0.8 }));
+
+        Component {
+            components,
+            active_window: ActiveWindow::Campaign,
+        }
+    }
+
+    // initializer for mission briefing scene's GUI
+    pub fn init_briefing_gui() -> Component {
+        let button_start = GuiType::Button { text: "Start".to_string(), highlight: false };
+        let button_back = GuiType::Button { text: "Back".to_string(), highlight: false };
+        let button_start_ui = Gui::new(button_start, 0.2, 0.09, Vector2 { x: -0.3, y: -0.7 });
+        let button_back_ui = Gui::new(button_back, 0.15, 0.09, Vector2 { x: 0.3, y: -0.7 });
+        Component {
+            components: vec![button_start_ui, button_back_ui],
+            active_window: ActiveWindow::Briefing,
+        }
+    }
+
+    // Relabel a button, for screens that are reused with different options
+    pub fn rename_button(&mut self, from: &str, to: &str) {
+        for component in &mut self.components {
+            match &mut component.id {
+                GuiType::Button { text, .. } if text == from => *text = to.to_string(),
+                _ => ()
+            }
+        }
+    }
+
     // main game's GUI event handler
     pub fn handle_event(&mut self, event: Event, window: &mut SDL2Facade, camera_frame: Mat4, state: &mut State, game_state: &mut GameState, control: &mut Control) {
         // handle events for any menu laid on top of game
@@ -233,7 +328,12 @@ impl Component {
                                         } else if display_text == "Main Menu" {
                                             game_state.transition_menu = true;
                                         } else if display_text == "Retry" {
-                                            if game_state.easy == true {
+                                            if game_state.survival {
+                                                game_state.survival_game = true;
+                                            } else if game_state.campaign_mission.is_some() {
+                                                // Go back to the campaign screen to replay the mission
+                                                game_state.campaign = true;
+                                            } else if game_state.easy == true {
                                                 game_state.easy_game = true;
                                             } else if game_state.medium == true {
                                                 game_state.medium_game = true;
@@ -324,6 +424,124 @@ impl Component {
                         }
                     }
                 }
+                // Reinforcement shop
+                GuiType::RecruitButton { cop_type, ref mut highlight } => {
+                    if self.active_window == ActiveWindow::Game {
+                        match event {
+                            Event::MouseButtonDown { timestamp: _, window_id: _, which: _, mouse_btn: _, x, y } |
+                            Event::MouseButtonUp { timestamp: _, window_id: _, which: _, mouse_btn: _, x, y } => {
+                                let mouse_pos = &mut Vector2 { x: x as f64, y: y as f64 };
+                                translate_mouse_to_camera(mouse_pos, window.window().size());
+
+                                let top_left = Vector2 { x: component.top_left.x, y: component.top_left.y };
+                                let bot_right = Vector2 { x: component.bot_right.x, y: component.bot_right.y };
+                                if check_bounding_box(top_left, bot_right, *mouse_pos) {
+                                    // Buy on press, and keep the release from reaching the selection controls
+                                    match event {
+                                        Event::MouseButtonDown { .. } => { recruit(state, *cop_type); },
+                                        _ => ()
+                                    }
+                                    handled_event = true;
+                                }
+                            }
+                            Event::MouseMotion { timestamp: _, window_id: _, which: _, x, y, .. } => {
+                                let mouse_pos = &mut Vector2 { x: x as f64, y: y as f64 };
+                                translate_mouse_to_camera(mouse_pos, window.window().size());
+
+                                let top_left = Vector2 { x: component.top_left.x, y: component.top_left.y };
+                                let bot_right = Vector2 { x: component.bot_right.x, y: component.bot_right.y };
+                                *highlight = check_bounding_box(top_left, bot_right, *mouse_pos);
+                            }
+                            _ => ()
+                        }
+                    }
+                }
+                // Upgrade tree, opened from its button or with the U key
+                GuiType::UpgradePanel { ref mut _window_gui, ref mut _buttons_gui, ref mut highlight } => {
+                    match event {
+                        Event::MouseButtonDown { timestamp: _, window_id: _, which: _, mouse_btn: _, x, y } |
+                        Event::MouseButtonUp { timestamp: _, window_id: _, which: _, mouse_btn: _, x, y } => {
+                            let mouse_pos = &mut Vector2 { x: x as f64, y: y as f64 };
+                            translate_mouse_to_camera(mouse_pos, window.window().size());
+                            let is_press = match event { Event::MouseButtonDown { .. } => true, _ => false };
+
+                            if self.active_window == ActiveWindow::Game {
+                                let top_left = Vector2 { x: component.top_left.x, y: component.top_left.y };
+                                let bot_right = Vector2 { x: component.bot_right.x, y: component.bot_right.y };
+                                if check_bounding_box(top_left, bot_right, *mouse_pos) {
+                                    if is_press {
+                                        game_state.game_paused = true;
+                                        self.active_window = ActiveWindow::Upgrades;
+                                    }
+                                    handled_event = true;
+                                }
+                            } else if self.active_window == ActiveWindow::Upgrades {
+                                if is_press {
+                                    for button in _buttons_gui.iter() {
+                                        let top_left = Vector2 { x: button.top_left.x, y: button.top_left.y };
+                                        let bot_right = Vector2 { x: button.bot_right.x, y: button.bot_right.y };
+                                        if check_bounding_box(top_left, bot_right, *mouse_pos) {
+                                            match button.id {
+                                                GuiType::UpgradeButton { upgrade_type, .. } => {
+                                                    buy_upgrade(state, upgrade_type);
+                                                }
+                                                _ => ()
+                                            }
+                                        }
+                                    }
+
+                                    // Close the panel when clicking outside of it
+                                    let top_left = Vector2 { x: _window_gui.top_left.x, y: _window_gui.top_left.y };
+                                    let bot_right = Vector2 { x: _window_gui.bot_right.x, y: _window_gui.bot_right.y };
+                                    if !check_bounding_box(top_left, bot_right, *mouse_pos) {
+                                        self.active_window = ActiveWindow::Game;
+                                        game_state.game_paused = false;
+                                    }
+                                }
+                                handled_event = true;
+                            }
+                        }
+                        Event::MouseMotion { timestamp: _, window_id: _, which: _, x, y, .. } => {
+                            let mouse_pos = &mut Vector2 { x: x as f64, y: y as f64 };
+                            translate_mouse_to_camera(mouse_pos, window.window().size());
+
+                            let top_left = Vector2 { x: component.top_left.x, y: component.top_left.y };
+                            let bot_right = Vector2 { x: component.bot_right.x, y: component.bot_right.y };
+                            *highlight = check_bounding_box(top_left, bot_right, *mouse_pos);
+
+                            for button in _buttons_gui.iter_mut() {
+                                let top_left = Vector2 { x: button.top_left.x, y: button.top_left.y };
+                                let bot_right = Vector2 { x: button.bot_right.x, y: button.bot_right.y };
+                                let check_within_bound = check_bounding_box(top_left, bot_right, *mouse_pos);
+                                match &mut button.id {
+                                    GuiType::UpgradeButton { ref mut highlight, .. } => {
+                                        *highlight = check_within_bound;
+                                    }
+                                    _ => ()
+                                }
+                            }
+                        }
+                        Event::KeyDown { keycode: Some(Keycode::U), .. } => {
+                            if self.active_window == ActiveWindow::Game {
+                                game_state.game_paused = true;
+                                self.active_window = ActiveWindow::Upgrades;
+                                handled_event = true;
+                            } else if self.active_window == ActiveWindow::Upgrades {
+                                self.active_window = ActiveWindow::Game;
+                                game_state.game_paused = false;
+                                handled_event = true;
+                            }
+                        }
+                        Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
+                            if self.active_window == ActiveWindow::Upgrades {
+                                self.active_window = ActiveWindow::Game;
+                                game_state.game_paused = false;
+                                handled_event = true;
+                            }
+                        }
+                        _ => ()
+                    }
+                }
                 _ => ()
             }
         }
@@ -358,6 +576,12 @@ impl Component {
                                     } else if display_text == "Tutorial" {
                                         game_state.transition_game = true;
                                         game_state.tutorial = true;
+                                    } else if display_text == "Survival" {
+                                        game_state.survival_game = true;
+                                    } else if display_text == "Campaign" {
+                                        game_state.campaign = true;
+                                    } else if display_text == "Map Editor" {
+                                        game_state.map_editor = true;
                                     }
                                 }
                             }
@@ -370,7 +594,7 @@ impl Component {
                                 let check_within_bound = check_bounding_box(top_left, bot_right, *mouse_pos);
                                 if check_within_bound {
                                     let display_text = text;
-                                    if display_text == "Start" || display_text == "Exit" || display_text == "Tutorial" {
+                                    if display_text == "Start" || display_text == "Exit" || display_text == "Tutorial" || display_text == "Survival" || display_text == "Campaign" || display_text == "Map Editor" {
                                         *highlight = true;
                                     }
                                 } else {
@@ -531,13 +755,17 @@ impl Component {
                             if check_within_bound {
                                 let display_text = text;
                                 if display_text == "Retry" {
-                                    if game_state.easy == true {
+                                    if game_state.survival {
+                                        game_state.survival_game = true;
+                                    } else if game_state.easy == true {
                                         game_state.easy_game = true;
                                     } else if game_state.medium == true {
                                         game_state.medium_game = true;
                                     } else if game_state.hard == true {
                                         game_state.hard_game = true;
                                     }
+                                } else if display_text == "Campaign" {
+                                    game_state.campaign = true;
                                 } else if display_text == "Main Menu" {
                                     game_state.transition_menu = true;
                                 } else if display_text == "Exit" {
@@ -557,7 +785,7 @@ impl Component {
                             let check_within_bound = check_bounding_box(top_left, bot_right, *mouse_pos);
                             if check_within_bound {
                                 let display_text = text;
-                                if display_text == "Retry" || display_text == "Main Menu" || display_text == "Exit" {
+                                if display_text == "Retry" || display_text == "Campaign" || display_text == "Main Menu" || display_text == "Exit" {
                                     *highlight = true;
                                 }
                             } else {
@@ -597,6 +825,8 @@ impl Component {
                                     } else if game_state.hard == true {
                                         game_state.hard_game = true;
                                     }
+                                } else if display_text == "Campaign" {
+                                    game_state.campaign = true;
                                 } else if display_text == "Main Menu" {
                                     game_state.transition_menu = true;
                                 } else if display_text == "Exit" {
@@ -615,7 +845,7 @@ impl Component {
                             let check_within_bound = check_bounding_box(top_left, bot_right, *mouse_pos);
                             if check_within_bound {
                                 let display_text = text;
-                                if display_text == "Retry" || display_text == "Main Menu" || display_text == "Exit" {
+                                if display_text == "Retry" || display_text == "Campaign" || display_text == "Main Menu" || display_text == "Exit" {
                                     *highlight = true;
                                 }
                             } else {
@@ -679,6 +909,92 @@ impl Component {
             }
         }
     }
+
+    // campaign selection scene's GUI event handler
+    pub fn handle_campaign_event(&mut self, event: &Event, window: &mut SDL2Facade, game_state: &mut GameState) {
+        for i in 0..self.components.len() {
+            let component = &mut self.components[i];
+            let top_left = Vector2 { x: component.top_left.x, y: component.top_left.y };
+            let bot_right = Vector2 { x: component.bot_right.x, y: component.bot_right.y };
+            match &mut component.id {
+                GuiType::MissionButton { mission_index, unlocked, ref mut highlight } => {
+                    match event {
+                        Event::MouseButtonDown { timestamp: _, window_id: _, which: _, mouse_btn: _, x, y } => {
+                            let mouse_pos = &mut Vector2 { x: *x as f64, y: *y as f64 };
+                            translate_mouse_to_camera(mouse_pos, window.window().size());
+
+                            // Locked missions can't be picked until the one before is done
+                            if *unlocked && check_bounding_box(top_left, bot_right, *mouse_pos) {
+                                game_state.campaign_mission = Some(*mission_index);
+                            }
+                        }
+                        Event::MouseMotion { timestamp: _, window_id: _, which: _, x, y, .. } => {
+                            let mouse_pos = &mut Vector2 { x: *x as f64, y: *y as f64 };
+                            translate_mouse_to_camera(mouse_pos, window.window().size());
+
+                            *highlight = *unlocked && check_bounding_box(top_left, bot_right, *mouse_pos);
+                        }
+                        _ => ()
+                    }
+                }
+                GuiType::Button { text, ref mut highlight } => {
+                    match event {
+                        Event::MouseButtonDown { timestamp: _, window_id: _, which: _, mouse_btn: _, x, y } => {
+                            let mouse_pos = &mut Vector2 { x: *x as f64, y: *y as f64 };
+                            translate_mouse_to_camera(mouse_pos, window.window().size());
+
+                            if check_bounding_box(top_left, bot_right, *mouse_pos) && text == "Back" {
+                                game_state.transition_menu = true;
+                            }
+                        }
+                        Event::MouseMotion { timestamp: _, window_id: _, which: _, x, y, .. } => {
+                            let mouse_pos = &mut Vector2 { x: *x as f64, y: *y as f64 };
+                            translate_mouse_to_camera(mouse_pos, window.window().size());
+
+                            *highlight = check_bounding_box(top_left, bot_right, *mouse_pos);
+                        }
+                        _ => ()
+                    }
+                }
+                _ => ()
+            }
+        }
+    }
+
+    // mission briefing scene's GUI event handler
+    pub fn handle_briefing_event(&mut self, event: &Event, window: &mut SDL2Facade, game_state: &mut GameState) {
+        for i in 0..self.components.len() {
+            let component = &mut self.components[i];
+            let top_left = Vector2 { x: component.top_left.x, y: component.top_left.y };
+            let bot_right = Vector2 { x: component.bot_right.x, y: component.bot_right.y };
+            match &mut component.id {
+                GuiType::Button { text, ref mut highlight } => {
+                    match event {
+                        Event::MouseButtonDown { timestamp: _, window_id: _, which: _, mouse_btn: _, x, y } => {
+                            let mouse_pos = &mut Vector2 { x: *x as f64, y: *y as f64 };
+                            translate_mouse_to_camera(mouse_pos, window.window().size());
+
+                            if check_bounding_box(top_left, bot_right, *mouse_pos) {
+                                if text == "Start" {
+                                    game_state.transition_game = true;
+                                } else if text == "Back" {
+                                    game_state.campaign = true;
+                                }
+                            }
+                        }
+                        Event::MouseMotion { timestamp: _, window_id: _, which: _, x, y, .. } => {
+                            let mouse_pos = &mut Vector2 { x: *x as f64, y: *y as f64 };
+                            translate_mouse_to_camera(mouse_pos, window.window().size());
+
+                            *highlight = check_bounding_box(top_left, bot_right, *mouse_pos);
+                        }
+                        _ => ()
+                    }
+                }
+                _ => ()
+            }
+        }
+    }
 }
 
 // GUI structure
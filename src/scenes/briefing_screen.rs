@@ -0,0 +1,97 @@
+use crate::simulation::game_state::GameState;
+use crate::simulation::campaign::*;
+use crate::presentation::ui::gui::Component;
+use crate::scenes::scene::{Scene, UpdateResult};
+use sdl2::EventPump;
+use glium_sdl2::SDL2Facade;
+use crate::presentation::display::{Programs, Textures};
+use glium::DrawParameters;
+use crate::presentation::graphics::font::FontPkg;
+use crate::{simulation, presentation};
+use crate::scenes::{campaign_screen, game};
+use sdl2::keyboard::Keycode;
+use crate::presentation::camera::*;
+
+pub struct BriefingScreen {
+    mission_index: usize,
+    mission: Mission,
+    game_state: GameState,
+    gui: Component,
+    pub camera: Camera,
+}
+
+impl BriefingScreen {
+    pub fn new(mission_index: usize, mission: Mission) -> BriefingScreen {
+        let game_state = simulation::game_state::GameState::new();
+        let gui = presentation::ui::gui::Component::init_briefing_gui();
+        let camera = presentation::camera::Camera::new();
+        BriefingScreen {
+            mission_index: mission_index,
+            mission: mission,
+            game_state: game_state,
+            gui: gui,
+            camera: camera,
+        }
+    }
+}
+
+impl Scene for BriefingScreen {
+    fn update(&mut self,
+              event_pump: &mut EventPump,
+              window: &mut SDL2Facade,
+              delta_time: f64
+    ) -> UpdateResult {
+        match self.game_state {
+            GameState { transition_game, campaign, .. } =>
+                {
+                    if transition_game {
+                        self.game_state.transition_game = false;
+                        let progress = CampaignProgress::load(CAMPAIGN_PROGRESS_PATH);
+                        return UpdateResult::Transition(Box::new(game::Game::new_mission(self.mission_index, &self.mission, &progress)))
+                    }
+                    if campaign {
+                        self.game_state.campaign = false;
+                        return UpdateResult::Transition(Box::new(campaign_screen::CampaignScreen::new()))
+                    }
+                }
+        }
+        for event in event_pump.poll_iter() {
+            use sdl2::event::Event;
+            match event {
+                // Exit window if escape key pressed or quit event triggered
+                Event::Quit { .. } => {
+                    return UpdateResult::Exit
+                },
+                Event::KeyDown { keycode: Some(Keycode::L), .. } => {
+                    println!("Debug info:");
+                    println!("  DT:               {:?}", delta_time);
+                    println!("  FPS:              {:?}", 1.0 / delta_time);
+                }
+                _ => {
+                    self.gui.handle_briefing_event(&event, window, &mut self.game_state);
+                }
+            }
+        }
+        UpdateResult::Continue
+    }
+
+    fn render(&mut self,
+              window: &SDL2Facade,
+              programs: &Programs,
+              textures: &Textures,
+              params: &DrawParameters,
+              fonts: &FontPkg
+    ) {
+        let mut target = window.draw();
+        presentation::display::display_briefing_screen(&mut target,
+                                                       &window,
+                                                       &programs,
+                                                       &textures,
+                                                       &params,
+                                                       self.camera.compute_matrix().as_f32_array(),
+                                                       &mut self.gui,
+                                                       &self.mission,
+                                                       &fonts);
+        target.finish().unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+use crate::simulation::game_state::GameState;
+use crate::simulation::campaign::*;
+use crate::presentation::ui::gui::Component;
+use crate::scenes::scene::{Scene, UpdateResult};
+use sdl2::EventPump;
+use glium_sdl2::SDL2Facade;
+use crate::presentation::display::{Programs, Textures};
+use glium::DrawParameters;
+use crate::presentation::graphics::font::FontPkg;
+use crate::{simulation, presentation};
+use crate::scenes::{briefing_screen, main_menu};
+use sdl2::keyboard::Keycode;
+use crate::presentation::camera::*;
+
+pub struct CampaignScreen {
+    missions: Vec<Mission>,
+    game_state: GameState,
+    gui: Component,
+    pub camera: Camera,
+}
+
+impl CampaignScreen {
+    pub fn new() -> CampaignScreen {
+        let missions = match load_campaign(CAMPAIGN_PATH) {
+            Ok(missions) => missions,
+            Err(e) => {
+                println!("Failed to load campaign: {}", e);
+                vec!()
+            }
+        };
+        let progress = CampaignProgress::load(CAMPAIGN_PROGRESS_PATH);
+        let game_state = simulation::game_state::GameState::new();
+        let gui = presentation::ui::gui::Component::init_campaign_gui(missions.len(), &progress);
+        let camera = presentation::camera::Camera::new();
+        CampaignScreen {
+            missions: missions,
+            game_state: game_state,
+            gui: gui,
+            camera: camera,
+        }
+    }
+}
+
+impl Scene for CampaignScreen {
+    fn update(&mut self,
+              event_pump: &mut EventPump,
+              window: &mut SDL2Facade,
+              delta_time: f64
+    ) -> UpdateResult {
+        match self.game_state {
+            GameState { campaign_mission, transition_menu, .. } =>
+                {
+                    if let Some(mission_index) = campaign_mission {
+                        self.game_state.campaign_mission = None;
+                        let mission = self.missions[mission_index].clone();
+                        return UpdateResult::Transition(Box::new(briefing_screen::BriefingScreen::new(mission_index, mission)))
+                    }
+                    if transition_menu {
+                        self.game_state.transition_menu = false;
+                        return UpdateResult::Transition(Box::new(main_menu::MainMenu::new()))
+                    }
+                }
+        }
+        for event in event_pump.poll_iter() {
+            use sdl2::event::Event;
+            match event {
+                // Exit window if escape key pressed or quit event triggered
+                Event::Quit { .. } => {
+                    return UpdateResult::Exit
+                },
+                Event::KeyDown { keycode: Some(Keycode::L), .. } => {
+                    println!("Debug info:");
+                    println!("  DT:               {:?}", delta_time);
+                    println!("  FPS:              {:?}", 1.0 / delta_time);
+                }
+                _ => {
+                    self.gui.handle_campaign_event(&event, window, &mut self.game_state);
+                }
+            }
+        }
+        UpdateResult::Continue
+    }
+
+    fn render(&mut self,
+              window: &SDL2Facade,
+              programs: &Programs,
+              textures: &Textures,
+              params: &DrawParameters,
+              fonts: &FontPkg
+    ) {
+        let mut target = window.draw();
+        presentation::display::display_campaign_screen(&mut target,
+                                                       &window,
+                                                       &programs,
+                                                       &textures,
+                                                       &params,
+                                                       self.camera.compute_matrix().as_f32_array(),
+                                                       &mut self.gui,
+                                                       &self.missions,
+                                                       &fonts);
+        target.finish().unwrap();
+    }
+}
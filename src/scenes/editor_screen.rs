@@ -0,0 +1,142 @@
+use crate::core::vector::*;
+use crate::simulation::editor::*;
+use crate::simulation::map::Map;
+use crate::simulation::control::{translate_mouse_to_camera, translate_camera_to_world};
+use crate::scenes::scene::{Scene, UpdateResult};
+use sdl2::EventPump;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use glium_sdl2::SDL2Facade;
+use crate::presentation::display::{Programs, Textures};
+use glium::DrawParameters;
+use crate::presentation::graphics::font::FontPkg;
+use crate::presentation;
+use crate::scenes::{game, main_menu};
+use crate::presentation::camera::*;
+
+pub struct EditorScreen {
+    editor: Editor,
+    pub camera: Camera,
+}
+
+impl EditorScreen {
+    pub fn new() -> EditorScreen {
+        EditorScreen::with_editor(Editor::load())
+    }
+
+    // Coming back from a test play picks up where the editing left off
+    pub fn with_map(map: Map) -> EditorScreen {
+        EditorScreen::with_editor(Editor::new(map))
+    }
+
+    fn with_editor(editor: Editor) -> EditorScreen {
+        let mut camera = presentation::camera::Camera::new();
+        camera.set_bounds(editor.map.camera_min, editor.map.camera_max);
+        EditorScreen {
+            editor: editor,
+            camera: camera,
+        }
+    }
+
+    fn mouse_to_world(&self, x: i32, y: i32, window: &SDL2Facade) -> Vector2 {
+        let mouse_pos = &mut vector2(x as f64, y as f64);
+        translate_mouse_to_camera(mouse_pos, window.window().size());
+        translate_camera_to_world(mouse_pos, self.camera.compute_matrix());
+        *mouse_pos
+    }
+}
+
+impl Scene for EditorScreen {
+    fn update(&mut self,
+              event_pump: &mut EventPump,
+              window: &mut SDL2Facade,
+              delta_time: f64
+    ) -> UpdateResult {
+        let keyboard_state = event_pump.keyboard_state();
+        let mouse_state = event_pump.mouse_state();
+        self.camera.update(&keyboard_state, &mouse_state, window, self.camera.compute_matrix(), delta_time);
+        for event in event_pump.poll_iter() {
+            use sdl2::event::Event;
+            match event {
+                // Exit window if escape key pressed or quit event triggered
+                Event::Quit { .. } => {
+                    return UpdateResult::Exit
+                },
+                Event::KeyDown { keycode: Some(key), .. } => {
+                    match key {
+                        // Escape drops the building being drawn first, then leaves the editor
+                        Keycode::Escape => {
+                            if self.editor.draft.is_empty() {
+                                return UpdateResult::Transition(Box::new(main_menu::MainMenu::new()))
+                            }
+                            self.editor.cancel();
+                        }
+                        Keycode::Tab => {
+                            let tool = self.editor.tool.next();
+                            self.editor.set_tool(tool);
+                        }
+                        Keycode::Num0 => self.editor.set_building_type(0),
+                        Keycode::Num1 => self.editor.set_building_type(1),
+                        Keycode::Num2 => self.editor.set_building_type(2),
+                        Keycode::Num3 => self.editor.set_building_type(3),
+                        Keycode::Num4 => self.editor.set_building_type(4),
+                        Keycode::Num5 => self.editor.set_building_type(5),
+                        Keycode::Num6 => self.editor.set_building_type(6),
+                        Keycode::Delete | Keycode::Backspace => self.editor.delete_selected(),
+                        Keycode::Return => self.editor.finish_building(),
+                        Keycode::F5 => self.editor.save(),
+                        Keycode::F6 => {
+                            if let Some(map) = self.editor.playable_map() {
+                                return UpdateResult::Transition(Box::new(game::Game::new_test_play(map)))
+                            }
+                        }
+                        _ => ()
+                    }
+                }
+                Event::MouseWheel { y, .. } => {
+                    self.camera.cursor_zoom(&mouse_state, y, &window, self.camera.compute_matrix());
+                },
+                Event::MouseButtonDown { mouse_btn: MouseButton::Middle, x, y, .. } => {
+                    self.camera.set_initial_mouse_pos(x, y, &window, self.camera.compute_matrix());
+                },
+                Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
+                    let position = self.mouse_to_world(x, y, window);
+                    self.editor.mouse_down(position);
+                },
+                Event::MouseButtonDown { mouse_btn: MouseButton::Right, x, y, .. } => {
+                    let position = self.mouse_to_world(x, y, window);
+                    self.editor.right_click(position);
+                },
+                Event::MouseMotion { x, y, .. } => {
+                    let position = self.mouse_to_world(x, y, window);
+                    self.editor.mouse_move(position);
+                },
+                Event::MouseButtonUp { mouse_btn: MouseButton::Left, x, y, .. } => {
+                    let position = self.mouse_to_world(x, y, window);
+                    self.editor.mouse_up(position);
+                },
+                _ => ()
+            }
+        }
+        UpdateResult::Continue
+    }
+
+    fn render(&mut self,
+              window: &SDL2Facade,
+              programs: &Programs,
+              textures: &Textures,
+              params: &DrawParameters,
+              fonts: &FontPkg
+    ) {
+        let mut target = window.draw();
+        presentation::display::display_map_editor(&mut target,
+                                                  &window,
+                                                  &programs,
+                                                  &textures,
+                                                  &params,
+                                                  self.camera.compute_matrix(),
+                                                  &self.editor,
+                                                  &fonts);
+        target.finish().unwrap();
+    }
+}
@@ -1,5 +1,13 @@
-use crate::simulation::state::State;
+use crate::simulation::state::*;
 use crate::simulation::update::EntityCounts;
+use crate::simulation::reinforcements::{Reinforcements, REINFORCEMENT_CAP_DEFAULT};
+use crate::simulation::day_night::{DayNightCycle, DAY_NIGHT_DEFAULT_STARTING_HOUR, DAY_NIGHT_DEFAULT_CYCLE_LENGTH};
+use crate::simulation::survival::Survival;
+use crate::simulation::objectives::*;
+use crate::simulation::campaign::*;
+use crate::simulation::map::*;
+use crate::simulation::city_generator::generate_city;
+use crate::simulation::reinforcements::{spawn_cop, arrival_position};
 use crate::presentation::ui::gui::Component;
 use crate::presentation::camera::Camera;
 use crate::simulation::control::Control;
@@ -19,6 +27,8 @@ use crate::simulation;
 use crate::scenes::scene::*;
 use crate::scenes::main_menu;
 use crate::scenes::difficulty_screen;
+use crate::scenes::campaign_screen;
+use crate::scenes::editor_screen;
 use crate::presentation::graphics::font::FontPkg;
 use crate::scenes::victory_screen::VictoryScreen;
 use crate::scenes::loss_screen::LossScreen;
@@ -31,6 +41,8 @@ pub struct Game {
     pub control: Control,
     pub camera: Camera,
     pub game_state: GameState,
+    // Map being test played from the editor, which is returned to afterwards
+    pub test_map: Option<Map>,
 }
 
 // Counts are out of 100%
@@ -41,29 +53,104 @@ const MEDIUM_INFECTED_COUNT: Scalar = 0.18;
 const HARD_COP_COUNT: Scalar = 0.04;
 const HARD_INFECTED_COUNT: Scalar = 0.4;
 
+// Hordes are looser on easy, and tighter and better at navigating streets on hard
+const EASY_FLOCKING: FlockingWeights = FlockingWeights {
+    cohesion: 0.05,
+    alignment: 0.2,
+    separation: 1.0,
+    obstacle_avoidance: 1.0,
+};
+const HARD_FLOCKING: FlockingWeights = FlockingWeights {
+    cohesion: 0.2,
+    alignment: 0.5,
+    separation: 1.0,
+    obstacle_avoidance: 3.0,
+};
+
+// Zombies are easier to sneak past on easy, and harder on hard
+const EASY_ZOMBIE_FIELD_OF_VIEW: Scalar = 1.6;
+const HARD_ZOMBIE_FIELD_OF_VIEW: Scalar = 3.0;
+
+// Maximum number of reinforcements that can be bought over a game
+const EASY_REINFORCEMENT_CAP: u32 = 10;
+const HARD_REINFORCEMENT_CAP: u32 = 3;
+
+// Easy games start in the morning, hard games start just before dark with shorter days
+const EASY_STARTING_HOUR: Scalar = 8.0;
+const HARD_STARTING_HOUR: Scalar = 18.0;
+const HARD_DAY_NIGHT_CYCLE_LENGTH: Scalar = 360.0;
+
+// Extra objectives on top of clearing the infection
+const MEDIUM_MAX_CASUALTIES: u32 = 40;
+const HARD_MAX_CASUALTIES: u32 = 25;
+// How long the map's plaza has to be held for
+const HARD_HOLD_AREA_DURATION: Scalar = 60.0;
+
 impl Game {
+    // Games at a difficulty are played on a freshly generated city, the tutorial keeps the default map
     pub fn new(tutorial: bool, difficulty: bool, easy: bool, medium: bool, hard: bool) -> Game {
+        let map = if easy || medium || hard {
+            generate_city(rand::random::<u64>())
+        } else {
+            load_default_map()
+        };
+        Game::new_on_map(map, tutorial, difficulty, easy, medium, hard)
+    }
+
+    fn new_on_map(map: Map, tutorial: bool, difficulty: bool, easy: bool, medium: bool, hard: bool) -> Game {
         let gui = presentation::ui::gui::Component::init_game_gui();
-        let camera = presentation::camera::Camera::new();
+        let mut camera = presentation::camera::Camera::new();
         let control = simulation::control::Control::new();
         let mut game_state: GameState;
         let entity_count = 100;
         let mut cop_entities = 0.05;
         let mut infected_entities = 0.2;
+        let mut flocking = FLOCKING_DEFAULT;
+        let mut zombie_field_of_view = ZOMBIE_FIELD_OF_VIEW;
+        let mut reinforcement_cap = REINFORCEMENT_CAP_DEFAULT;
+        let mut starting_hour = DAY_NIGHT_DEFAULT_STARTING_HOUR;
+        let mut day_night_cycle_length = DAY_NIGHT_DEFAULT_CYCLE_LENGTH;
 
         // Set difficulty
         if easy {
             cop_entities = EASY_COP_COUNT;
             infected_entities = EASY_INFECTED_COUNT;
+            flocking = EASY_FLOCKING;
+            zombie_field_of_view = EASY_ZOMBIE_FIELD_OF_VIEW;
+            reinforcement_cap = EASY_REINFORCEMENT_CAP;
+            starting_hour = EASY_STARTING_HOUR;
         } else if medium {
             cop_entities = MEDIUM_COP_COUNT;
             infected_entities = MEDIUM_INFECTED_COUNT;
         } else if hard {
             cop_entities = HARD_COP_COUNT;
             infected_entities = HARD_INFECTED_COUNT;
+            flocking = HARD_FLOCKING;
+            zombie_field_of_view = HARD_ZOMBIE_FIELD_OF_VIEW;
+            reinforcement_cap = HARD_REINFORCEMENT_CAP;
+            starting_hour = HARD_STARTING_HOUR;
+            day_night_cycle_length = HARD_DAY_NIGHT_CYCLE_LENGTH;
         }
 
-        let state = simulation::initial_state::initial_state(entity_count, cop_entities, infected_entities, rand::random::<u32>());
+        camera.set_bounds(map.camera_min, map.camera_max);
+        let mut state = simulation::initial_state::initial_state(&map, entity_count, cop_entities, infected_entities, rand::random::<u32>());
+        state.flocking = flocking;
+        state.zombie_field_of_view = zombie_field_of_view;
+        state.reinforcements = Reinforcements::new(reinforcement_cap, map.entry);
+        state.day_night = DayNightCycle::new(starting_hour, day_night_cycle_length);
+
+        // Set up the scenario's objectives
+        if medium {
+            state.objectives.push(Objective::limit_casualties(MEDIUM_MAX_CASUALTIES));
+        } else if hard {
+            state.objectives.push(Objective::limit_casualties(HARD_MAX_CASUALTIES));
+            match map.plazas.first() {
+                Some(plaza) =>
+                    state.objectives.push(Objective::hold_area(plaza.center, plaza.radius, HARD_HOLD_AREA_DURATION)),
+                None => println!("Map has no plaza, so there's no area to hold"),
+            }
+            state.objectives.extend(protect_vip(&state.entities));
+        }
         game_state = simulation::game_state::GameState::new();
 
         if difficulty {
@@ -79,11 +166,116 @@ impl Game {
             gui: gui,
             control: control,
             camera: camera,
-            game_state: game_state
+            game_state: game_state,
+            test_map: None,
+        }
+    }
+
+    // Survival games use the medium setup on the default map, with the waves coming in at its
+    // wave spawns, but start with nobody infected since the threat comes from the waves instead
+    pub fn new_survival() -> Game {
+        let map = load_default_map();
+        if map.wave_spawns.is_empty() {
+            println!("Map has no wave spawns, so no waves will come");
+        }
+        let wave_spawns = map.wave_spawns.clone();
+        let mut game = Game::new_on_map(map, false, false, false, true, false);
+        for entity in &mut game.state.entities {
+            match &mut entity.dead_or_alive {
+                DeadOrAlive::Alive { zombie_or_human: ZombieOrHuman::Human { infection, .. }, .. } => {
+                    *infection = INFECTION_MIN;
+                }
+                _ => ()
+            }
+        }
+        game.state.survival = Some(Survival::new(wave_spawns));
+        game.state.objectives = vec!();
+        game.game_state.survival = true;
+        game
+    }
+
+    // Test plays from the editor use the medium setup
+    pub fn new_test_play(map: Map) -> Game {
+        let mut game = Game::new_on_map(map.clone(), false, false, false, true, false);
+        game.test_map = Some(map);
+        game
+    }
+
+    // Campaign missions are set up from the mission data, and the squad, money and
+    // upgrades carry over from the missions before
+    pub fn new_mission(mission_index: usize, mission: &Mission, progress: &CampaignProgress) -> Game {
+        let gui = presentation::ui::gui::Component::init_game_gui();
+        let mut camera = presentation::camera::Camera::new();
+        let control = simulation::control::Control::new();
+
+        let map = match &mission.map {
+            Some(path) => match load_map(path) {
+                Ok(map) => map,
+                Err(e) => {
+                    println!("Failed to load map for mission {}, using the default one: {}", mission.name, e);
+                    load_default_map()
+                }
+            },
+            None => load_default_map(),
+        };
+        camera.set_bounds(map.camera_min, map.camera_max);
+        let mut state = simulation::initial_state::initial_state(&map, mission.entity_count, mission.cop_portion, mission.infected_portion, rand::random::<u32>());
+        state.money = progress.money;
+        state.upgrades = progress.upgrades;
+        state.day_night = DayNightCycle::new(mission.starting_hour, DAY_NIGHT_DEFAULT_CYCLE_LENGTH);
+        state.objectives = mission.objectives.clone();
+        if mission.protect_vip {
+            state.objectives.extend(protect_vip(&state.entities));
+        }
+        if let Some(required) = mission.evacuate_to_extraction {
+            match map.extraction_points.first() {
+                Some(extraction_point) =>
+                    state.objectives.push(Objective::evacuate(extraction_point.center, extraction_point.radius, required)),
+                None => {
+                    println!("Map has no extraction point for mission {}, clearing the infection instead", mission.name);
+                    state.objectives.push(Objective::new(ObjectiveType::ClearInfection));
+                }
+            }
+        }
+        if let Some(duration) = mission.hold_plaza {
+            match map.plazas.first() {
+                Some(plaza) =>
+                    state.objectives.push(Objective::hold_area(plaza.center, plaza.radius, duration)),
+                None => {
+                    println!("Map has no plaza for mission {}, clearing the infection instead", mission.name);
+                    state.objectives.push(Objective::new(ObjectiveType::ClearInfection));
+                }
+            }
+        }
+
+        // The surviving squad drives in where reinforcements do
+        for (i, cop_type) in progress.cops.iter().enumerate() {
+            spawn_cop(&mut state, *cop_type, arrival_position(map.entry, i, progress.cops.len()));
+        }
+
+        let mut game_state = simulation::game_state::GameState::new();
+        game_state.campaign_mission = Some(mission_index);
+
+        Game {
+            state: state,
+            entity_counts: EntityCounts::default(),
+            gui: gui,
+            control: control,
+            camera: camera,
+            game_state: game_state,
+            test_map: None,
         }
     }
 }
 
+// The game can't start without a map, so there's nothing to fall back on
+fn load_default_map() -> Map {
+    match load_map(DEFAULT_MAP_PATH) {
+        Ok(map) => map,
+        Err(e) => panic!("Failed to load map: {}", e),
+    }
+}
+
 impl Scene for Game {
     fn update(&mut self,
               event_pump: &mut EventPump,
@@ -91,7 +283,7 @@ impl Scene for Game {
               delta_time: f64)
               -> UpdateResult {
         match self.game_state {
-            GameState{terminate, transition_menu, transition_game, zombies_win, humans_win, tutorial, summary_text, difficulty, easy, medium, hard, easy_game, medium_game, hard_game, ..} =>
+            GameState{terminate, transition_menu, transition_game, zombies_win, humans_win, tutorial, summary_text, difficulty, easy, medium, hard, easy_game, medium_game, hard_game, survival_game, campaign, ..} =>
                 {
                     if terminate {
                         return UpdateResult::Exit
@@ -101,6 +293,9 @@ impl Scene for Game {
                         return UpdateResult::Transition(Box::new(Game::new(self.game_state.tutorial, false, false, false, false)))
                     }
                     if transition_menu {self.game_state.transition_menu = false;
+                        if let Some(map) = self.test_map.take() {
+                            return UpdateResult::Transition(Box::new(editor_screen::EditorScreen::with_map(map)))
+                        }
                         return UpdateResult::Transition(Box::new(main_menu::MainMenu::new()))
                     }
                     if easy_game {
@@ -118,6 +313,14 @@ impl Scene for Game {
                         self.game_state.hard = true;
                         return UpdateResult::Transition(Box::new(Game::new(false, true, false, false, self.game_state.hard)))
                     }
+                    if survival_game {
+                        self.game_state.survival_game = false;
+                        return UpdateResult::Transition(Box::new(Game::new_survival()))
+                    }
+                    if campaign {
+                        self.game_state.campaign = false;
+                        return UpdateResult::Transition(Box::new(campaign_screen::CampaignScreen::new()))
+                    }
                     // Display difficulty selection screen
                     if difficulty {
                         self.game_state.difficulty = false;
@@ -157,7 +360,10 @@ impl Scene for Game {
                         // wait 2 seconds
                         if self.game_state.trans_wait == 120 {
                             self.game_state.zombies_win = false;
-                            return UpdateResult::Transition(Box::new(LossScreen::new(self.entity_counts.clone(), easy, medium, hard)))
+                            let waves_survived = self.state.survival.as_ref().map(|survival| survival.waves_survived);
+                            return UpdateResult::Transition(Box::new(LossScreen::new(self.entity_counts.clone(), easy, medium, hard,
+                                                                                     waves_survived, self.state.objectives.clone(),
+                                                                                     self.game_state.campaign_mission.is_some())))
                         }
                     }
                     if humans_win {
@@ -165,7 +371,20 @@ impl Scene for Game {
                         // wait 2 seconds
                         if self.game_state.trans_wait == 120 {
                             self.game_state.humans_win = false;
-                            return UpdateResult::Transition(Box::new(VictoryScreen::new(self.entity_counts.clone(), easy, medium, hard)))
+                            match self.game_state.campaign_mission {
+                                Some(mission_index) => {
+                                    let mut progress = CampaignProgress::load(CAMPAIGN_PROGRESS_PATH);
+                                    progress.complete_mission(mission_index, &self.state);
+                                    match progress.save(CAMPAIGN_PROGRESS_PATH) {
+                                        Ok(()) => (),
+                                        Err(e) => println!("Failed to save campaign progress: {}", e),
+                                    }
+                                }
+                                None => ()
+                            }
+                            return UpdateResult::Transition(Box::new(VictoryScreen::new(self.entity_counts.clone(), easy, medium, hard,
+                                                                                        self.state.objectives.clone(),
+                                                                                        self.game_state.campaign_mission.is_some())))
                         }
                     }
                 }
@@ -207,14 +426,25 @@ impl Scene for Game {
                 &mut self.state);
             self.entity_counts = simulation_results.entity_counts;
 
-            if self.entity_counts.infected == 0 && self.entity_counts.zombies == 0 {
-                // The player wins if there are no zombies or infected
-                self.game_state.humans_win = true;
+            if self.state.survival.is_some() {
+                // Survival only ends when the cops are wiped out
+                if self.entity_counts.cops == 0 {
+                    self.game_state.zombies_win = true;
+                }
             }
-            else if self.entity_counts.cops == 0 {
-                // The player loses if there are still zombies or infected,
-                // and no cops to defend the remaining civilians
-                self.game_state.zombies_win = true;
+            else if !self.game_state.humans_win && !self.game_state.zombies_win {
+                match resolve_objectives(&mut self.state.objectives) {
+                    // The player wins once the scenario's objectives are complete
+                    ObjectiveStatus::Passed => self.game_state.humans_win = true,
+                    ObjectiveStatus::Failed => self.game_state.zombies_win = true,
+                    ObjectiveStatus::InProgress => {
+                        if self.entity_counts.cops == 0 {
+                            // The player loses if there are no cops left to finish the job
+                            fail_remaining_objectives(&mut self.state.objectives);
+                            self.game_state.zombies_win = true;
+                        }
+                    }
+                }
             }
 
             presentation::audio::sound_effects::play_sounds(&simulation_results.sounds, &self.camera);
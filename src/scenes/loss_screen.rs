@@ -1,5 +1,6 @@
 use crate::simulation::update::EntityCounts;
 use crate::simulation::game_state::GameState;
+use crate::simulation::objectives::Objective;
 use crate::presentation::ui::gui::Component;
 use crate::scenes::scene::{Scene, UpdateResult};
 use sdl2::EventPump;
@@ -7,25 +8,35 @@ use glium_sdl2::SDL2Facade;
 use crate::presentation::display::{Programs, Textures};
 use glium::DrawParameters;
 use crate::{simulation, presentation};
-use crate::scenes::{game, main_menu};
+use crate::scenes::{game, main_menu, campaign_screen};
 use sdl2::keyboard::Keycode;
 use crate::presentation::graphics::font::FontPkg;
 use crate::presentation::camera::*;
 
 pub struct LossScreen {
     entity_counts: EntityCounts,
+    // Only set for survival games
+    waves_survived: Option<u32>,
+    objectives: Vec<Objective>,
     game_state: GameState,
     gui: Component,
     pub camera: Camera,
 }
 
 impl LossScreen {
-    pub fn new(entity_counts: EntityCounts, easy: bool, medium: bool, hard: bool) -> LossScreen {
-        let game_state = simulation::game_state::GameState::new_difficulty(easy, medium, hard);
-        let gui = presentation::ui::gui::Component::init_loss_gui();
+    pub fn new(entity_counts: EntityCounts, easy: bool, medium: bool, hard: bool, waves_survived: Option<u32>, objectives: Vec<Objective>, campaign: bool) -> LossScreen {
+        let mut game_state = simulation::game_state::GameState::new_difficulty(easy, medium, hard);
+        game_state.survival = waves_survived.is_some();
+        let mut gui = presentation::ui::gui::Component::init_loss_gui();
+        if campaign {
+            // Campaign missions are retried or continued from the campaign screen
+            gui.rename_button("Retry", "Campaign");
+        }
         let camera = presentation::camera::Camera::new();
         LossScreen {
             entity_counts: entity_counts,
+            waves_survived: waves_survived,
+            objectives: objectives,
             game_state: game_state,
             gui: gui,
             camera: camera,
@@ -36,7 +47,7 @@ impl LossScreen {
 impl Scene for LossScreen {
     fn update(&mut self, event_pump: &mut EventPump, window: &mut SDL2Facade, delta_time: f64) -> UpdateResult {
         match self.game_state {
-            GameState { transition_game, transition_menu, terminate, easy_game, medium_game, hard_game, .. } =>
+            GameState { transition_game, transition_menu, terminate, easy_game, medium_game, hard_game, campaign, survival_game, .. } =>
                 {
                     if easy_game {
                         self.game_state.easy_game = false;
@@ -53,10 +64,18 @@ impl Scene for LossScreen {
                         self.game_state.hard = true;
                         return UpdateResult::Transition(Box::new(game::Game::new(false, true, false, false, self.game_state.hard)));
                     }
+                    if survival_game {
+                        self.game_state.survival_game = false;
+                        return UpdateResult::Transition(Box::new(game::Game::new_survival()));
+                    }
                     if transition_game {
                         self.game_state.transition_game = false;
                         return UpdateResult::Transition(Box::new(game::Game::new(self.game_state.tutorial, false, false, false, false)));
                     }
+                    if campaign {
+                        self.game_state.campaign = false;
+                        return UpdateResult::Transition(Box::new(campaign_screen::CampaignScreen::new()));
+                    }
                     if transition_menu {
                         self.game_state.transition_menu = false;
                         return UpdateResult::Transition(Box::new(main_menu::MainMenu::new()));
@@ -96,6 +115,8 @@ impl Scene for LossScreen {
                                                    self.camera.compute_matrix().as_f32_array(),
                                                    &mut self.gui,
                                                    &self.entity_counts,
+                                                   self.waves_survived,
+                                                   &self.objectives,
                                                    &fonts,
                                                    &mut self.game_state);
         target.finish().unwrap();
@@ -9,6 +9,8 @@ use crate::presentation::graphics::font::FontPkg;
 use crate::presentation::ui::gui::Component;
 use crate::scenes::game;
 use crate::scenes::difficulty_screen;
+use crate::scenes::campaign_screen;
+use crate::scenes::editor_screen;
 use crate::scenes::scene::{Scene, UpdateResult};
 use crate::simulation::game_state::GameState;
 use crate::presentation::camera::*;
@@ -39,7 +41,7 @@ impl Scene for MainMenu {
               delta_time: f64)
               -> UpdateResult {
         match self.game_state {
-            GameState { transition_game, difficulty, easy, medium, hard, terminate, .. } =>
+            GameState { transition_game, difficulty, easy, medium, hard, terminate, survival_game, campaign, map_editor, .. } =>
                 {
 
                     if difficulty {
@@ -50,6 +52,18 @@ impl Scene for MainMenu {
                         self.game_state.transition_game = false;
                         return UpdateResult::Transition(Box::new(game::Game::new(self.game_state.tutorial, self.game_state.difficulty, self.game_state.easy_game, self.game_state.medium_game, self.game_state.hard_game)));
                     }
+                    if campaign {
+                        self.game_state.campaign = false;
+                        return UpdateResult::Transition(Box::new(campaign_screen::CampaignScreen::new()));
+                    }
+                    if survival_game {
+                        self.game_state.survival_game = false;
+                        return UpdateResult::Transition(Box::new(game::Game::new_survival()));
+                    }
+                    if map_editor {
+                        self.game_state.map_editor = false;
+                        return UpdateResult::Transition(Box::new(editor_screen::EditorScreen::new()));
+                    }
                     if terminate { return UpdateResult::Exit; }
                 }
         }
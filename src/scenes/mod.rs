@@ -3,4 +3,7 @@ pub mod game;
 pub mod main_menu;
 pub mod victory_screen;
 pub mod loss_screen;
-pub mod difficulty_screen;
\ No newline at end of file
+pub mod difficulty_screen;
+pub mod campaign_screen;
+pub mod briefing_screen;
+pub mod editor_screen;
@@ -1,5 +1,6 @@
 use crate::simulation::update::EntityCounts;
 use crate::simulation::game_state::GameState;
+use crate::simulation::objectives::Objective;
 use crate::presentation::ui::gui::Component;
 use crate::scenes::scene::{Scene, UpdateResult};
 use sdl2::EventPump;
@@ -8,24 +9,30 @@ use crate::presentation::display::{Programs, Textures};
 use glium::DrawParameters;
 use crate::presentation::graphics::font::FontPkg;
 use crate::{simulation, presentation};
-use crate::scenes::{game, main_menu};
+use crate::scenes::{game, main_menu, campaign_screen};
 use sdl2::keyboard::Keycode;
 use crate::presentation::camera::*;
 
 pub struct VictoryScreen {
     entity_counts: EntityCounts,
+    objectives: Vec<Objective>,
     game_state: GameState,
     gui: Component,
     pub camera: Camera,
 }
 
 impl VictoryScreen {
-    pub fn new(entity_counts: EntityCounts, easy: bool, medium: bool, hard: bool) -> VictoryScreen {
+    pub fn new(entity_counts: EntityCounts, easy: bool, medium: bool, hard: bool, objectives: Vec<Objective>, campaign: bool) -> VictoryScreen {
         let game_state = simulation::game_state::GameState::new_difficulty(easy, medium, hard);
-        let gui = presentation::ui::gui::Component::init_victory_gui();
+        let mut gui = presentation::ui::gui::Component::init_victory_gui();
+        if campaign {
+            // Campaign missions are retried or continued from the campaign screen
+            gui.rename_button("Retry", "Campaign");
+        }
         let camera = presentation::camera::Camera::new();
         VictoryScreen {
             entity_counts: entity_counts,
+            objectives: objectives,
             game_state: game_state,
             gui: gui,
             camera: camera,
@@ -40,7 +47,7 @@ impl Scene for VictoryScreen {
               delta_time: f64
     ) -> UpdateResult {
         match self.game_state {
-            GameState{transition_game, transition_menu, terminate, easy_game, medium_game, hard_game, ..} =>
+            GameState{transition_game, transition_menu, terminate, easy_game, medium_game, hard_game, campaign, ..} =>
                 {
                     if easy_game {
                         self.game_state.easy_game = false;
@@ -61,6 +68,10 @@ impl Scene for VictoryScreen {
                         self.game_state.transition_game = false;
                         return UpdateResult::Transition(Box::new(game::Game::new(self.game_state.tutorial, false, false, false, false)))
                     }
+                    if campaign {
+                        self.game_state.campaign = false;
+                        return UpdateResult::Transition(Box::new(campaign_screen::CampaignScreen::new()))
+                    }
                     if transition_menu {
                         self.game_state.transition_menu = false;
                         return UpdateResult::Transition(Box::new(main_menu::MainMenu::new()))
@@ -107,6 +118,7 @@ impl Scene for VictoryScreen {
                                                    &mut self.gui,
 
                                                    &self.entity_counts,
+                                                   &self.objectives,
                                                    &fonts,
                                                       &mut self.game_state);
         target.finish().unwrap();
@@ -0,0 +1,94 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::simulation::ai::nav_graph::NavGraph;
+use crate::simulation::state::ENTITY_RADIUS;
+use crate::simulation::initial_state::initial_state;
+use crate::simulation::map::*;
+use crate::simulation::city_generator::generate_city;
+
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use std::time::{Duration, Instant};
+
+const BENCHMARK_TRIP_COUNT: usize = 40;
+const BENCHMARK_TIMESTEP: Scalar = 1.0 / 60.0;
+// Roughly how fast a sprinting cop covers the ground
+const BENCHMARK_SPEED: Scalar = 4.0;
+// Trips that haven't arrived by then are cut short
+const BENCHMARK_MAX_FRAMES: usize = 2000;
+
+fn seconds(duration: Duration) -> Scalar {
+    duration.as_secs() as Scalar + 1e-9 * duration.subsec_nanos() as Scalar
+}
+
+pub fn benchmark_pathfinding() {
+    match load_map(DEFAULT_MAP_PATH) {
+        Ok(map) => benchmark_map("Default map", &map),
+        Err(e) => panic!("Failed to load map: {}", e),
+    }
+    benchmark_map("Generated city", &generate_city(0));
+}
+
+// Walk a cop across the map between random points, timing the pathfinding done each frame when
+// the path is searched for from scratch against following a path cached on the navigation graph
+fn benchmark_map(name: &str, map: &Map) {
+    println!("{}", name);
+    let state = initial_state(map, 100, 0.05, 0.2, 0);
+    let mut rng = XorShiftRng::seed_from_u64(0);
+
+    let build_start = Instant::now();
+    let nav_graph = NavGraph::new(&state.buildings, ENTITY_RADIUS);
+    println!("Navigation graph built in {:.2} ms", 1000.0 * seconds(build_start.elapsed()));
+
+    let random_open_point = |rng: &mut XorShiftRng| loop {
+        let point = map.random_spawn_point(SpawnKind::Civilian, rng);
+        if !state.buildings.iter().any(|building| building.contains_point(point)) {
+            return point;
+        }
+    };
+
+    let mut frames = 0;
+    let mut cached_time = 0.0;
+    let mut replanned_time = 0.0;
+
+    for _ in 0..BENCHMARK_TRIP_COUNT {
+        let mut position = random_open_point(&mut rng);
+        let goal = random_open_point(&mut rng);
+        let mut path = None;
+
+        for _ in 0..BENCHMARK_MAX_FRAMES {
+            let start = Instant::now();
+            path = nav_graph.follow_path(position, goal, &path);
+            cached_time += seconds(start.elapsed());
+
+            let start = Instant::now();
+            nav_graph.find_path(position, goal);
+            replanned_time += seconds(start.elapsed());
+
+            frames += 1;
+
+            let next = match &path {
+                Some(path) => path.edges[0].end.pos,
+                None => break,
+            };
+            let delta = next - position;
+            let step = BENCHMARK_SPEED * BENCHMARK_TIMESTEP;
+            if delta.length() <= step {
+                position = next;
+                if next == goal {
+                    break;
+                }
+            } else {
+                position += step * delta.normalize();
+            }
+        }
+    }
+
+    let cached_per_frame = 1e6 * cached_time / frames as Scalar;
+    let replanned_per_frame = 1e6 * replanned_time / frames as Scalar;
+    println!("{} trips over {} frames", BENCHMARK_TRIP_COUNT, frames);
+    println!("Searched every frame: {:.1} us per cop per frame", replanned_per_frame);
+    println!("Cached on the graph: {:.1} us per cop per frame", cached_per_frame);
+    println!("Speedup: {:.1}x", replanned_per_frame / cached_per_frame);
+}
@@ -0,0 +1,321 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::core::geo::polygon::*;
+use crate::core::geo::segment2::Segment2;
+
+use crate::simulation::state::*;
+use crate::simulation::barricade::*;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// Side length of the square cells that the map is divided into for the horde's navigation
+pub const FLOW_FIELD_CELL_SIZE: Scalar = 1.0;
+
+// Cells expanded each frame, so a full pass over the map is spread across a few frames
+pub const FLOW_FIELD_CELLS_PER_FRAME: usize = 2500;
+
+const DIAGONAL_COST: Scalar = std::f64::consts::SQRT_2 * FLOW_FIELD_CELL_SIZE;
+
+// Cell waiting to be expanded, ordered so the binary heap pops the closest first
+#[derive(Copy, Clone, PartialEq)]
+struct OpenCell {
+    distance: Scalar,
+    index: usize,
+}
+
+impl Eq for OpenCell {}
+
+impl Ord for OpenCell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.partial_cmp(&self.distance).unwrap_or(Ordering::Equal)
+            .then_with(|| self.index.cmp(&other.index))
+    }
+}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Distance from every cell to the nearest living human, walking around buildings and barricades.
+// Zombies go downhill on it, so the whole horde is steered for the cost of one Dijkstra pass
+#[derive(Clone, Default)]
+pub struct FlowField {
+    pub min: Vector2,
+    pub columns: usize,
+    pub rows: usize,
+    // Cells too close to a building to stand in, which never change
+    building_blocked: Vec<bool>,
+    // Cells too close to a building or barricade to stand in
+    blocked: Vec<bool>,
+    // Distances from the last finished pass, which is what zombies follow
+    distances: Vec<Scalar>,
+    // The pass in progress, worked through a few cells each frame
+    next_distances: Vec<Scalar>,
+    open: BinaryHeap<OpenCell>,
+}
+
+impl FlowField {
+    // Cover the area spanned by the buildings, which includes the world boundary
+    pub fn new(buildings: &Vec<Polygon>) -> FlowField {
+        let mut min = Vector2 { x: INFINITY, y: INFINITY };
+        let mut max = Vector2 { x: NEG_INFINITY, y: NEG_INFINITY };
+
+        for building in buildings {
+            let (building_min, building_max) = building.bounding_box();
+            min.x = min.x.min(building_min.x);
+            min.y = min.y.min(building_min.y);
+            max.x = max.x.max(building_max.x);
+            max.y = max.y.max(building_max.y);
+        }
+
+        let columns = ((max.x - min.x) / FLOW_FIELD_CELL_SIZE).ceil() as usize;
+        let rows = ((max.y - min.y) / FLOW_FIELD_CELL_SIZE).ceil() as usize;
+
+        let mut field = FlowField {
+            min,
+            columns,
+            rows,
+            building_blocked: vec!(),
+            blocked: vec![false; columns * rows],
+            distances: vec![INFINITY; columns * rows],
+            next_distances: vec![INFINITY; columns * rows],
+            open: BinaryHeap::new(),
+        };
+
+        for building in buildings {
+            field.block_cells_near(building);
+        }
+        field.building_blocked = field.blocked.clone();
+        field
+    }
+
+    fn cell_center(&self, index: usize) -> Vector2 {
+        let (column, row) = (index % self.columns, index / self.columns);
+        self.min + FLOW_FIELD_CELL_SIZE * vector2(column as Scalar + 0.5, row as Scalar + 0.5)
+    }
+
+    fn cell_at(&self, position: Vector2) -> Option<usize> {
+        let offset = (position - self.min) / FLOW_FIELD_CELL_SIZE;
+        let (column, row) = (offset.x.floor(), offset.y.floor());
+        if column < 0.0 || row < 0.0 || column >= self.columns as Scalar || row >= self.rows as Scalar {
+            return None;
+        }
+        Some(row as usize * self.columns + column as usize)
+    }
+
+    // Mark the cells an entity would overlap the polygon in
+    fn block_cells_near(&mut self, polygon: &Polygon) {
+        let (low, high) = polygon.bounding_box();
+        let margin = vector2(ENTITY_RADIUS, ENTITY_RADIUS);
+        let first = (low - margin - self.min) / FLOW_FIELD_CELL_SIZE;
+        let last = (high + margin - self.min) / FLOW_FIELD_CELL_SIZE;
+        let sides = polygon.num_sides();
+
+        for row in (first.y.floor().max(0.0) as usize)..(last.y.ceil().max(0.0) as usize).min(self.rows) {
+            for column in (first.x.floor().max(0.0) as usize)..(last.x.ceil().max(0.0) as usize).min(self.columns) {
+                let index = row * self.columns + column;
+                let center = self.cell_center(index);
+                let near_side = (0..sides).any(|i| {
+                    let side = Segment2 { p1: polygon.get(i), p2: polygon.get((i + 1) % sides) };
+                    side.distance_from_segment_to_point_squared(center) < ENTITY_RADIUS * ENTITY_RADIUS
+                });
+                if near_side || polygon.contains_point(center) {
+                    self.blocked[index] = true;
+                }
+            }
+        }
+    }
+
+    // Bring the blocked cells up to date after barricades are built or destroyed. Only the
+    // barricades are gone over again, the buildings are kept from when the map was loaded
+    pub fn update_barricades(&mut self, barricades: &Vec<Barricade>) {
+        self.blocked = self.building_blocked.clone();
+        for barricade in barricades {
+            self.block_cells_near(&barricade.poly);
+        }
+    }
+
+    // Carry on with the current pass, starting a new one from where the humans are now once the
+    // last one is finished
+    pub fn update(&mut self, entities: &Vec<Entity>) {
+        if self.open.is_empty() {
+            for distance in &mut self.next_distances {
+                *distance = INFINITY;
+            }
+            for entity in entities.iter().filter(|entity| entity.is_human()) {
+                if let Some(index) = self.cell_at(entity.position) {
+                    self.next_distances[index] = 0.0;
+                    self.open.push(OpenCell { distance: 0.0, index });
+                }
+            }
+            if self.open.is_empty() {
+                // Nobody left to chase
+                self.distances = self.next_distances.clone();
+                return;
+            }
+        }
+
+        for _ in 0..FLOW_FIELD_CELLS_PER_FRAME {
+            let OpenCell { distance, index } = match self.open.pop() {
+                Some(cell) => cell,
+                None => break,
+            };
+
+            // A cell is pushed again each time a shorter way to it is found, only the first counts
+            if distance > self.next_distances[index] {
+                continue;
+            }
+
+            for (neighbour, step) in self.neighbours(index) {
+                if !self.blocked[neighbour] && distance + step < self.next_distances[neighbour] {
+                    self.next_distances[neighbour] = distance + step;
+                    self.open.push(OpenCell { distance: distance + step, index: neighbour });
+                }
+            }
+        }
+
+        // The pass is finished, so the zombies can start following it
+        if self.open.is_empty() {
+            std::mem::swap(&mut self.distances, &mut self.next_distances);
+        }
+    }
+
+    // Neighbouring cells with the distance to them. Diagonal steps aren't allowed to cut the
+    // corner of a blocked cell
+    fn neighbours(&self, index: usize) -> Vec<(usize, Scalar)> {
+        let (column, row) = ((index % self.columns) as isize, (index / self.columns) as isize);
+        let in_grid = |column: isize, row: isize| {
+            column >= 0 && row >= 0 && column < self.columns as isize && row < self.rows as isize
+        };
+        let open = |column: isize, row: isize| {
+            in_grid(column, row) && !self.blocked[row as usize * self.columns + column as usize]
+        };
+
+        let mut neighbours = vec!();
+        for &(dx, dy) in &[(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            let (next_column, next_row) = (column + dx, row + dy);
+            if !in_grid(next_column, next_row) {
+                continue;
+            }
+            let diagonal = dx != 0 && dy != 0;
+            if diagonal && !(open(column + dx, row) && open(column, row + dy)) {
+                continue;
+            }
+            let step = if diagonal { DIAGONAL_COST } else { FLOW_FIELD_CELL_SIZE };
+            neighbours.push((next_row as usize * self.columns + next_column as usize, step));
+        }
+        neighbours
+    }
+
+    // Walking distance from the position to the nearest human, infinite if there's no way there
+    pub fn distance(&self, position: Vector2) -> Scalar {
+        match self.cell_at(position) {
+            Some(index) => self.distances[index],
+            None => INFINITY,
+        }
+    }
+
+    // Which way to go from the position to get closer to a human, if the field knows a way
+    pub fn direction(&self, position: Vector2) -> Option<Vector2> {
+        let index = self.cell_at(position)?;
+        let mut best = None;
+        let mut best_distance = self.distances[index];
+
+        // Blocked cells only have a distance when a human is standing in one, and then it's
+        // where the zombie wants to go
+        for (neighbour, step) in self.neighbours(index) {
+            if self.distances[neighbour] + step <= best_distance {
+                best_distance = self.distances[neighbour] + step;
+                best = Some(neighbour);
+            }
+        }
+
+        // Already at a human, or cut off from all of them
+        if best_distance == 0.0 || best_distance == INFINITY {
+            return None;
+        }
+
+        best.map(|neighbour| self.cell_center(neighbour) - position)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn civilian_at(position: Vector2) -> Entity {
+        Entity {
+            position,
+            velocity: Vector2::zero(),
+            facing_angle: 0.0,
+            dead_or_alive: DeadOrAlive::Alive {
+                health: ENTITY_HEALTH_MAX,
+                zombie_or_human: ZombieOrHuman::Human {
+                    infection: INFECTION_MIN,
+                    human: Human::Civilian {
+                        state: HumanState::Running,
+                        punch_time_cooldown: 0.0,
+                        left_hand_status: HandStatus::Normal,
+                        right_hand_status: HandStatus::Normal
+                    }
+                }
+            }
+        }
+    }
+
+    fn rectangle(min: Vector2, max: Vector2) -> Polygon {
+        Polygon(vec![min, vector2(max.x, min.y), max, vector2(min.x, max.y)])
+    }
+
+    // A walled yard split by a wall with a gap at the top
+    pub fn split_yard() -> Vec<Polygon> {
+        vec![
+            rectangle(vector2(-1.0, -1.0), vector2(41.0, 0.0)),
+            rectangle(vector2(-1.0, 40.0), vector2(41.0, 41.0)),
+            rectangle(vector2(-1.0, -1.0), vector2(0.0, 41.0)),
+            rectangle(vector2(40.0, -1.0), vector2(41.0, 41.0)),
+            rectangle(vector2(19.0, 0.0), vector2(21.0, 30.0)),
+        ]
+    }
+
+    pub fn finished_field(buildings: &Vec<Polygon>, entities: &Vec<Entity>) -> FlowField {
+        let mut field = FlowField::new(buildings);
+        field.update(entities);
+        while !field.open.is_empty() {
+            field.update(entities);
+        }
+        field
+    }
+
+    #[test]
+    fn field_leads_round_walls() {
+        let buildings = split_yard();
+        let human = vector2(10.0, 20.0);
+        let field = finished_field(&buildings, &vec![civilian_at(human)]);
+
+        assert!(field.distance(human) < FLOW_FIELD_CELL_SIZE);
+        // Straight through the wall it's 20, round the end of it nearly 30
+        let start = vector2(30.0, 20.0);
+        assert!(field.distance(start) > 27.0);
+
+        let mut position = start;
+        for _ in 0..100 {
+            match field.direction(position) {
+                Some(direction) => position += direction.normalize() * 0.5 * FLOW_FIELD_CELL_SIZE,
+                None => break,
+            }
+            assert!(!buildings.iter().any(|building| building.contains_point(position)));
+        }
+        assert!((position - human).length() < 2.0 * FLOW_FIELD_CELL_SIZE);
+    }
+
+    #[test]
+    fn field_is_flat_without_humans() {
+        let field = finished_field(&split_yard(), &vec![]);
+        assert_eq!(field.distance(vector2(10.0, 20.0)), INFINITY);
+        assert!(field.direction(vector2(10.0, 20.0)).is_none());
+    }
+}
@@ -1,2 +1,6 @@
 pub mod pathfinding;
 pub mod path;
+pub mod nav_graph;
+pub mod flow_field;
+pub mod steering;
+pub mod benchmark;
@@ -0,0 +1,672 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::core::geo::polygon::*;
+use crate::core::geo::segment2::Segment2;
+use crate::core::geo::offset::{offset_polygon, offset_region, Join};
+use crate::simulation::ai::path::{Node, Edge, Path};
+use crate::simulation::barricade::*;
+use crate::simulation::state::{COP_MIN_DISTANCE_FROM_WAYPOINT_SQUARED, ENTITY_RADIUS};
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// A path that ends further than this from the goal is planned again, for goals that move
+pub const PATH_GOAL_TOLERANCE_SQUARED: Scalar = 4.0;
+
+// Outlines are kept this much further out than an agent's radius, so agents following them don't
+// scrape the walls
+pub const NAV_CLEARANCE_MULTIPLIER: Scalar = 1.1;
+
+// Sizes of agent that get a navigation graph of their own, smallest first. Agents use the graph
+// for the smallest size they fit in
+pub const NAV_RADIUS_CLASSES: [Scalar; 1] = [ENTITY_RADIUS];
+
+// Whether segments ab and cd cross or touch. Working from which side of each segment the other's
+// ends lie on keeps the answer consistent when a segment passes exactly through a vertex shared
+// by two sides, which is where intersecting the lines loses it to rounding
+fn segments_meet(a: Vector2, b: Vector2, c: Vector2, d: Vector2) -> bool {
+    let direction = b - a;
+    if direction.length_squared() == 0.0 {
+        return false;
+    }
+
+    let c_side = cross(direction, c - a);
+    let d_side = cross(direction, d - a);
+    if c_side == 0.0 && d_side == 0.0 {
+        // Collinear, so they only meet if they overlap
+        let (c_along, d_along) = ((c - a).dot(direction), (d - a).dot(direction));
+        return c_along.max(d_along) >= 0.0 && c_along.min(d_along) <= direction.length_squared();
+    }
+
+    let a_side = cross(d - c, a - c);
+    let b_side = cross(d - c, b - c);
+    c_side * d_side <= 0.0 && a_side * b_side <= 0.0
+}
+
+// Obstacle with its bounding box, so most segments can be ruled out without testing every side
+#[derive(Clone, Debug)]
+struct NavObstacle {
+    polygon: Polygon,
+    min: Vector2,
+    max: Vector2,
+}
+
+impl NavObstacle {
+    fn new(polygon: &Polygon) -> NavObstacle {
+        let (min, max) = polygon.bounding_box();
+        NavObstacle { polygon: polygon.clone(), min, max }
+    }
+
+    // Grazing a corner counts as blocked, so paths can't slip diagonally through a building
+    // between two of its corners
+    fn blocks(&self, start: Vector2, end: Vector2) -> bool {
+        if start.x.max(end.x) < self.min.x || start.x.min(end.x) > self.max.x ||
+            start.y.max(end.y) < self.min.y || start.y.min(end.y) > self.max.y {
+            return false;
+        }
+        let sides = self.polygon.num_sides();
+        (0..sides).any(|i| segments_meet(start, end, self.polygon.get(i), self.polygon.get((i + 1) % sides)))
+    }
+
+    fn contains_point(&self, point: Vector2) -> bool {
+        self.min.x <= point.x && point.x <= self.max.x &&
+            self.min.y <= point.y && point.y <= self.max.y &&
+            self.polygon.contains_point(point)
+    }
+
+    // If the gap between the two obstacles is narrower than min_gap, the segment across its
+    // narrowest point. Any way through the gap has to cross it
+    fn plug(&self, other: &NavObstacle, min_gap: Scalar) -> Option<(Vector2, Vector2)> {
+        if self.min.x - other.max.x >= min_gap || other.min.x - self.max.x >= min_gap ||
+            self.min.y - other.max.y >= min_gap || other.min.y - self.max.y >= min_gap {
+            return None;
+        }
+
+        let mut closest = None;
+        let mut closest_distance_squared = min_gap * min_gap;
+        for (from, to) in &[(self, other), (other, self)] {
+            let sides = to.polygon.num_sides();
+            for vertex in &from.polygon.0 {
+                for i in 0..sides {
+                    let side = Segment2 { p1: to.polygon.get(i), p2: to.polygon.get((i + 1) % sides) };
+                    let nearest = side.nearest_point_on_segment_to_point(*vertex);
+                    let distance_squared = (nearest - *vertex).length_squared();
+                    if distance_squared < closest_distance_squared {
+                        closest_distance_squared = distance_squared;
+                        closest = Some((*vertex, nearest));
+                    }
+                }
+            }
+        }
+        closest
+    }
+}
+
+// The obstacles grown by the radius with rounded corners, which is the area within the radius of
+// them. Growing a concave obstacle can close off a notch into a hole, which is out of reach
+// anyway, so only the outlines are kept
+fn clearances<'a, I: Iterator<Item = &'a Polygon>>(obstacles: I, radius: Scalar) -> Vec<NavObstacle> {
+    obstacles
+        .flat_map(|obstacle| offset_region(obstacle, radius, Join::Round))
+        .filter(|clearance| clearance.is_counterclockwise())
+        .map(|clearance| NavObstacle::new(&clearance))
+        .collect()
+}
+
+// Node waiting to be expanded, ordered so the binary heap pops the lowest estimated total cost first
+#[derive(Copy, Clone, PartialEq)]
+struct OpenNode {
+    estimate: Scalar,
+    index: usize,
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.partial_cmp(&self.estimate).unwrap_or(Ordering::Equal)
+            .then_with(|| self.index.cmp(&other.index))
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Visibility graph over the outline vertices of the buildings and barricades, for agents of one
+// radius. The buildings never move, so which of their vertices can see each other is worked out
+// once when the map is loaded, and only the barricades are looked at again when one is built or
+// destroyed
+#[derive(Clone, Debug, Default)]
+pub struct NavGraph {
+    pub radius: Scalar,
+    buildings: Vec<NavObstacle>,
+    barricades: Vec<NavObstacle>,
+    // The obstacles grown by the radius, which the agent's centre has to stay out of for the
+    // rest of it to clear the obstacles
+    building_clearances: Vec<NavObstacle>,
+    barricade_clearances: Vec<NavObstacle>,
+    // Segments closing off the gaps too narrow to walk through, first between the buildings and
+    // then the ones involving barricades
+    plugs: Vec<(Vector2, Vector2)>,
+    building_plug_count: usize,
+    // Building outline vertices come first, followed by the barricade outline vertices
+    nodes: Vec<Vector2>,
+    building_node_count: usize,
+    // Pairs of building nodes that can see each other past the buildings
+    building_edges: Vec<(usize, usize)>,
+    // Nodes each node can see past the buildings and barricades, with the distance to them
+    neighbours: Vec<Vec<(usize, Scalar)>>,
+    // Bumped whenever the graph changes, so paths planned on an older graph can be spotted
+    pub version: u32,
+}
+
+impl NavGraph {
+    pub fn new(buildings: &Vec<Polygon>, radius: Scalar) -> NavGraph {
+        let mut graph = NavGraph {
+            radius,
+            buildings: buildings.iter().map(NavObstacle::new).collect(),
+            building_clearances: clearances(buildings.iter(), radius),
+            ..NavGraph::default()
+        };
+
+        for i in 0..graph.buildings.len() {
+            for j in (i + 1)..graph.buildings.len() {
+                if let Some(plug) = graph.buildings[i].plug(&graph.buildings[j], 2.0 * radius) {
+                    graph.plugs.push(plug);
+                }
+            }
+        }
+        graph.building_plug_count = graph.plugs.len();
+
+        for building in buildings {
+            graph.add_outline_nodes(building);
+        }
+        graph.building_node_count = graph.nodes.len();
+
+        for i in 0..graph.building_node_count {
+            for j in (i + 1)..graph.building_node_count {
+                let (a, b) = (graph.nodes[i], graph.nodes[j]);
+                if graph.is_wide_enough(a, b) {
+                    graph.building_edges.push((i, j));
+                }
+            }
+        }
+
+        graph.link();
+        graph
+    }
+
+    // Add the corners of the obstacle's outline as nodes, leaving out any that are too close to
+    // another obstacle for the agent to stand on
+    fn add_outline_nodes(&mut self, obstacle: &Polygon) {
+        let outline = offset_polygon(obstacle, self.radius * NAV_CLEARANCE_MULTIPLIER, Join::Miter);
+        for vertex in outline.0 {
+            if self.is_open(vertex) {
+                self.nodes.push(vertex);
+            }
+        }
+    }
+
+    // Bring the graph up to date after barricades are built or destroyed
+    pub fn update_barricades(&mut self, barricades: &Vec<Barricade>) {
+        self.barricades = barricades.iter().map(|barricade| NavObstacle::new(&barricade.poly)).collect();
+        self.barricade_clearances = clearances(barricades.iter().map(|barricade| &barricade.poly), self.radius);
+
+        self.plugs.truncate(self.building_plug_count);
+        for i in 0..self.barricades.len() {
+            let others = self.buildings.iter().chain(self.barricades[(i + 1)..].iter());
+            let plugs: Vec<(Vector2, Vector2)> = others.filter_map(|other| self.barricades[i].plug(other, 2.0 * self.radius)).collect();
+            self.plugs.extend(plugs);
+        }
+
+        self.nodes.truncate(self.building_node_count);
+        for barricade in barricades {
+            self.add_outline_nodes(&barricade.poly);
+        }
+
+        self.link();
+    }
+
+    // Work out the neighbours of every node from the building edges, which only need checking
+    // against the barricades, and the barricade nodes, which need checking against everything
+    fn link(&mut self) {
+        let mut neighbours = vec![vec!(); self.nodes.len()];
+        let mut connect = |i: usize, j: usize, length: Scalar| {
+            neighbours[i].push((j, length));
+            neighbours[j].push((i, length));
+        };
+
+        for &(i, j) in &self.building_edges {
+            let (a, b) = (self.nodes[i], self.nodes[j]);
+            if !self.barricade_clearances.iter().any(|barricade| barricade.blocks(a, b)) {
+                connect(i, j, (b - a).length());
+            }
+        }
+
+        for i in self.building_node_count..self.nodes.len() {
+            for j in 0..i {
+                let (a, b) = (self.nodes[i], self.nodes[j]);
+                if self.is_wide_enough(a, b) {
+                    connect(i, j, (b - a).length());
+                }
+            }
+        }
+
+        self.neighbours = neighbours;
+        self.version = self.version.wrapping_add(1);
+    }
+
+    // Whether an agent can stand with its centre at the point
+    fn is_open(&self, point: Vector2) -> bool {
+        !self.building_clearances.iter().chain(self.barricade_clearances.iter()).any(|obstacle| obstacle.contains_point(point))
+    }
+
+    // Whether nothing stands between the two points, keeping only the agent's centre out of the
+    // obstacles and the gaps too narrow for it
+    pub fn is_clear(&self, start: Vector2, end: Vector2) -> bool {
+        !self.buildings.iter().chain(self.barricades.iter()).any(|obstacle| obstacle.blocks(start, end)) &&
+            !self.plugs.iter().any(|(c, d)| segments_meet(start, end, *c, *d))
+    }
+
+    // Whether the whole of the agent can get between the two points without clipping a corner.
+    // Gaps too narrow for it are closed off by the grown obstacles overlapping, so the plugs
+    // aren't needed
+    pub fn is_wide_enough(&self, start: Vector2, end: Vector2) -> bool {
+        !self.building_clearances.iter().chain(self.barricade_clearances.iter()).any(|obstacle| obstacle.blocks(start, end))
+    }
+
+    // Whether an agent can get between the two points, given whether both have room for it to
+    // stand on. When one doesn't, there's no way of keeping the whole agent clear, so only its
+    // centre is
+    fn can_pass(&self, start: Vector2, end: Vector2, both_open: bool) -> bool {
+        if both_open {
+            self.is_wide_enough(start, end)
+        } else {
+            self.is_clear(start, end)
+        }
+    }
+
+    // Keep following a path while it's still good, and only plan a new one when the graph has
+    // changed, the goal has moved or the way to the next node has been lost
+    pub fn follow_path(&self, position: Vector2, goal: Vector2, path: &Option<Path>) -> Option<Path> {
+        if let Some(path) = path {
+            if path.version == self.version && !path.edges.is_empty() &&
+                (path.edges[path.edges.len() - 1].end.pos - goal).length_squared() < PATH_GOAL_TOLERANCE_SQUARED {
+                let mut path = path.clone();
+
+                // Move on once the next node has been reached, or the one after it comes into view
+                while path.edges.len() > 1 &&
+                    ((path.edges[0].end.pos - position).length_squared() < COP_MIN_DISTANCE_FROM_WAYPOINT_SQUARED ||
+                        self.is_wide_enough(position, path.edges[1].end.pos)) {
+                    path.advance();
+                }
+
+                if self.can_pass(position, path.edges[0].end.pos, self.is_open(position)) {
+                    return Some(path);
+                }
+            }
+        }
+
+        self.find_path(position, goal)
+    }
+
+    // Find the shortest path from start_pos to end_pos with A* over the graph, joining the two
+    // points to every node they can see. Straight line distance never overestimates what's
+    // left to go, so the first path to reach the end is the shortest
+    pub fn find_path(&self, start_pos: Vector2, end_pos: Vector2) -> Option<Path> {
+        let node_count = self.nodes.len();
+        let start_index = node_count;
+        let end_index = node_count + 1;
+        let position = |i: usize| if i == start_index { start_pos } else if i == end_index { end_pos } else { self.nodes[i] };
+        let heuristic = |i: usize| (end_pos - position(i)).length();
+
+        let edge = |from: usize, to: usize| Edge {
+            start: Node { pos: position(from), h: heuristic(from) },
+            end: Node { pos: position(to), h: heuristic(to) },
+            cost: (position(to) - position(from)).length(),
+        };
+
+        let start_open = self.is_open(start_pos);
+        let end_open = self.is_open(end_pos);
+
+        // Nothing between start and end, answer is a straight line
+        if self.can_pass(start_pos, end_pos, start_open && end_open) {
+            let mut path = Path::from_edge(edge(start_index, end_index));
+            path.version = self.version;
+            return Some(path);
+        }
+
+        let end_distances: Vec<Option<Scalar>> = self.nodes.iter()
+            .map(|node| if self.can_pass(*node, end_pos, end_open) { Some((end_pos - *node).length()) } else { None })
+            .collect();
+        if end_distances.iter().all(|distance| distance.is_none()) {
+            return None;
+        }
+
+        let start_links: Vec<(usize, Scalar)> = (0..node_count)
+            .filter(|i| self.can_pass(start_pos, self.nodes[*i], start_open))
+            .map(|i| (i, (self.nodes[i] - start_pos).length()))
+            .collect();
+
+        let mut cost = vec![INFINITY; node_count + 2];
+        let mut came_from: Vec<Option<usize>> = vec![None; node_count + 2];
+        let mut closed = vec![false; node_count + 2];
+        let mut open = BinaryHeap::new();
+        cost[start_index] = 0.0;
+        open.push(OpenNode { estimate: heuristic(start_index), index: start_index });
+
+        while let Some(OpenNode { index: current, .. }) = open.pop() {
+            // A node is pushed again each time a cheaper way to it is found, only the first counts
+            if closed[current] {
+                continue;
+            }
+            closed[current] = true;
+
+            if current == end_index {
+                let mut indices = vec![end_index];
+                while let Some(previous) = came_from[*indices.last().unwrap()] {
+                    indices.push(previous);
+                }
+                indices.reverse();
+
+                let mut path = Path::from_edge(edge(indices[0], indices[1]));
+                for k in 2..indices.len() {
+                    path.append_edge(edge(indices[k - 1], indices[k]));
+                }
+                let mut path = self.smooth_path(&path);
+                path.version = self.version;
+                return Some(path);
+            }
+
+            let links: &[(usize, Scalar)] = if current == start_index { &start_links } else { &self.neighbours[current] };
+            let end_link = if current < node_count { end_distances[current].map(|distance| (end_index, distance)) } else { None };
+
+            for &(next, length) in links.iter().chain(end_link.iter()) {
+                if !closed[next] && cost[current] + length < cost[next] {
+                    cost[next] = cost[current] + length;
+                    came_from[next] = Some(current);
+                    open.push(OpenNode { estimate: cost[next] + heuristic(next), index: next });
+                }
+            }
+        }
+
+        None
+    }
+
+    // Pull the path tight like a string, going from each node straight to the furthest one along
+    // that can be seen from it. Nodes that line up along the way are cut out, so units following
+    // the path don't slow down for turns that aren't there
+    pub fn smooth_path(&self, path: &Path) -> Path {
+        let nodes: Vec<Node> = std::iter::once(path.edges[0].start)
+            .chain(path.edges.iter().map(|edge| edge.end))
+            .collect();
+        let edge = |from: Node, to: Node| Edge { start: from, end: to, cost: (to.pos - from.pos).length() };
+
+        let mut current = 0;
+        let mut smoothed: Option<Path> = None;
+        while current < nodes.len() - 1 {
+            let furthest = ((current + 1)..nodes.len()).rev()
+                .find(|&next| next == current + 1 || self.is_wide_enough(nodes[current].pos, nodes[next].pos))
+                .unwrap();
+            let next_edge = edge(nodes[current], nodes[furthest]);
+            match &mut smoothed {
+                None => smoothed = Some(Path::from_edge(next_edge)),
+                Some(smoothed) => smoothed.append_edge(next_edge),
+            }
+            current = furthest;
+        }
+
+        let mut smoothed = smoothed.unwrap();
+        smoothed.version = path.version;
+        smoothed
+    }
+}
+
+// Navigation graphs for each size of agent, kept up to date together
+#[derive(Clone, Debug, Default)]
+pub struct Navigation {
+    graphs: Vec<NavGraph>,
+}
+
+impl Navigation {
+    pub fn new(buildings: &Vec<Polygon>) -> Navigation {
+        Navigation::with_radius_classes(buildings, &NAV_RADIUS_CLASSES)
+    }
+
+    // Radius classes have to be given smallest first
+    pub fn with_radius_classes(buildings: &Vec<Polygon>, radius_classes: &[Scalar]) -> Navigation {
+        Navigation {
+            graphs: radius_classes.iter().map(|radius| NavGraph::new(buildings, *radius)).collect()
+        }
+    }
+
+    pub fn update_barricades(&mut self, barricades: &Vec<Barricade>) {
+        for graph in &mut self.graphs {
+            graph.update_barricades(barricades);
+        }
+    }
+
+    // The graph for the smallest size the agent fits in. Agents bigger than all of them get the
+    // biggest, which is the closest there is
+    pub fn graph(&self, radius: Scalar) -> &NavGraph {
+        self.graphs.iter()
+            .find(|graph| graph.radius >= radius)
+            .unwrap_or_else(|| self.graphs.last().expect("Navigation graphs haven't been built"))
+    }
+
+    pub fn find_path(&self, start_pos: Vector2, end_pos: Vector2, radius: Scalar) -> Option<Path> {
+        self.graph(radius).find_path(start_pos, end_pos)
+    }
+
+    pub fn follow_path(&self, position: Vector2, goal: Vector2, path: &Option<Path>, radius: Scalar) -> Option<Path> {
+        self.graph(radius).follow_path(position, goal, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::geo::polygon::tests::*;
+    use rand::Rng;
+    use std::f64::consts::PI;
+
+    fn rectangle(min: Vector2, max: Vector2) -> Polygon {
+        Polygon(vec![min, vector2(max.x, min.y), max, vector2(min.x, max.y)])
+    }
+
+    fn path_length(path: &Path) -> Scalar {
+        path.edges.iter().map(|edge| (edge.end.pos - edge.start.pos).length()).sum()
+    }
+
+    // Closest the segment comes to the polygon's outline, or zero if it goes inside
+    fn clearance(polygon: &Polygon, start: Vector2, end: Vector2) -> Scalar {
+        if polygon.contains_point(start) || polygon.num_intersects(start, end) > 0 {
+            return 0.0;
+        }
+        let segment = Segment2 { p1: start, p2: end };
+        let sides = polygon.num_sides();
+        let nearest_vertex = polygon.0.iter()
+            .map(|vertex| segment.distance_from_segment_to_point_squared(*vertex))
+            .fold(INFINITY, |a, b| a.min(b));
+        let nearest_side = (0..sides)
+            .flat_map(|i| {
+                let side = Segment2 { p1: polygon.get(i), p2: polygon.get((i + 1) % sides) };
+                vec![side.distance_from_segment_to_point_squared(start), side.distance_from_segment_to_point_squared(end)]
+            })
+            .fold(INFINITY, |a, b| a.min(b));
+        nearest_vertex.min(nearest_side).sqrt()
+    }
+
+    // Dijkstra over every pair of points that can see each other, without any of the graph's
+    // shortcuts for which pairs to look at
+    fn brute_force_shortest_path(graph: &NavGraph, start: Vector2, end: Vector2) -> Option<Scalar> {
+        let mut points = vec![start, end];
+        points.extend(graph.nodes.iter().cloned());
+        let mut distance = vec![INFINITY; points.len()];
+        let mut done = vec![false; points.len()];
+        distance[0] = 0.0;
+
+        loop {
+            let current = (0..points.len())
+                .filter(|i| !done[*i] && distance[*i] < INFINITY)
+                .min_by(|i, j| distance[*i].partial_cmp(&distance[*j]).unwrap())?;
+            if current == 1 {
+                return Some(distance[1]);
+            }
+            done[current] = true;
+
+            for next in 0..points.len() {
+                if !done[next] && graph.is_wide_enough(points[current], points[next]) {
+                    let through = distance[current] + (points[next] - points[current]).length();
+                    if through < distance[next] {
+                        distance[next] = through;
+                    }
+                }
+            }
+        }
+    }
+
+    // A point an agent of the graph's radius has room to stand on
+    fn random_open_point<R: Rng>(rng: &mut R, graph: &NavGraph) -> Vector2 {
+        loop {
+            let point = vector2(rng.gen_range(-30.0, 30.0), rng.gen_range(-30.0, 30.0));
+            if graph.is_open(point) {
+                return point;
+            }
+        }
+    }
+
+    #[test]
+    fn paths_are_as_short_as_brute_force_ones() {
+        let mut rng = test_rng(8);
+        for _ in 0..20 {
+            // Buildings that don't overlap, with gaps of all sizes between them
+            let mut buildings: Vec<Polygon> = vec!();
+            while buildings.len() < 8 {
+                let center = vector2(rng.gen_range(-25.0, 25.0), rng.gen_range(-25.0, 25.0));
+                let building = random_star_polygon(&mut rng, center, 6.0);
+                let (min, max) = building.bounding_box();
+                if buildings.iter().all(|other| {
+                    let (other_min, other_max) = other.bounding_box();
+                    min.x > other_max.x || max.x < other_min.x || min.y > other_max.y || max.y < other_min.y
+                }) {
+                    buildings.push(building);
+                }
+            }
+
+            let mut graph = NavGraph::new(&buildings, ENTITY_RADIUS);
+            for barricades in 0..2 {
+                if barricades > 0 {
+                    let start = random_open_point(&mut rng, &graph);
+                    let end = start + rng.gen_range(3.0, 10.0) * Vector2::from_angle(rng.gen_range(-PI, PI));
+                    graph.update_barricades(&vec![Barricade::new(start, end)]);
+                }
+
+                for _ in 0..10 {
+                    let start = random_open_point(&mut rng, &graph);
+                    let end = random_open_point(&mut rng, &graph);
+                    let path = graph.find_path(start, end);
+                    let shortest = brute_force_shortest_path(&graph, start, end);
+                    assert_eq!(path.is_some(), shortest.is_some());
+
+                    if let (Some(path), Some(shortest)) = (path, shortest) {
+                        assert!((path_length(&path) - shortest).abs() < 1e-6);
+                        assert_eq!(path.edges[0].start.pos, start);
+                        assert_eq!(path.edges[path.edges.len() - 1].end.pos, end);
+                        for edge in &path.edges {
+                            assert!(graph.is_wide_enough(edge.start.pos, edge.end.pos));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn paths_go_round_an_l_shaped_building() {
+        let building = Polygon(vec![
+            vector2(0.0, 0.0), vector2(10.0, 0.0), vector2(10.0, 4.0),
+            vector2(4.0, 4.0), vector2(4.0, 10.0), vector2(0.0, 10.0),
+        ]);
+        let graph = NavGraph::new(&vec![building.clone()], ENTITY_RADIUS);
+
+        // From the inside of the L to beyond its outside corner, round the end of either arm
+        let (start, end) = (vector2(8.0, 8.0), vector2(-3.0, -3.0));
+        let path = graph.find_path(start, end).unwrap();
+        let around_arm = (vector2(10.0, 4.0) - start).length() + 4.0 + (end - vector2(10.0, 0.0)).length();
+        assert!(path_length(&path) > around_arm);
+        assert!(path_length(&path) < around_arm + 2.0);
+        for edge in &path.edges {
+            assert!(clearance(&building, edge.start.pos, edge.end.pos) > 0.98 * ENTITY_RADIUS);
+        }
+
+        // Nothing in the way along the inside of the L
+        let path = graph.find_path(vector2(5.0, 9.0), vector2(9.0, 5.0)).unwrap();
+        assert_eq!(path.edges.len(), 1);
+    }
+
+    #[test]
+    fn gaps_narrower_than_the_agent_are_refused() {
+        // A long wall broken by a gap, which the straight way from one side to the other goes through
+        let wall = |gap: Scalar| vec![
+            rectangle(vector2(-1.0, -30.0), vector2(1.0, 0.0)),
+            rectangle(vector2(-1.0, gap), vector2(1.0, 30.0)),
+        ];
+        let (start, end) = (vector2(-5.0, 0.0), vector2(5.0, 0.0));
+
+        let narrow = NavGraph::new(&wall(1.8 * ENTITY_RADIUS), ENTITY_RADIUS);
+        let path = narrow.find_path(start, end).unwrap();
+        assert!(path_length(&path) > 60.0);
+
+        let wide = NavGraph::new(&wall(2.2 * ENTITY_RADIUS), ENTITY_RADIUS);
+        let path = wide.find_path(start, end).unwrap();
+        assert!(path_length(&path) < 11.0);
+    }
+
+    #[test]
+    fn agents_get_the_graph_for_their_size() {
+        // A gap that fits an entity but not something twice its size
+        let buildings = vec![
+            rectangle(vector2(-1.0, -30.0), vector2(1.0, 0.0)),
+            rectangle(vector2(-1.0, 3.0 * ENTITY_RADIUS), vector2(1.0, 30.0)),
+        ];
+        let navigation = Navigation::with_radius_classes(&buildings, &[ENTITY_RADIUS, 2.0 * ENTITY_RADIUS]);
+        let (start, end) = (vector2(-5.0, 0.0), vector2(5.0, 0.0));
+
+        let small = navigation.find_path(start, end, ENTITY_RADIUS).unwrap();
+        assert!(path_length(&small) < 11.0);
+
+        // In between sizes use the next class up, and anything bigger than all of them the biggest
+        for radius in &[1.5 * ENTITY_RADIUS, 2.0 * ENTITY_RADIUS, 4.0 * ENTITY_RADIUS] {
+            assert_eq!(navigation.graph(*radius).radius, 2.0 * ENTITY_RADIUS);
+            let big = navigation.find_path(start, end, *radius).unwrap();
+            assert!(path_length(&big) > 60.0);
+        }
+    }
+
+    #[test]
+    fn bigger_agents_keep_further_away() {
+        // A gap that fits an entity but not something three times its size
+        let radius = 3.0 * ENTITY_RADIUS;
+        let buildings = vec![
+            rectangle(vector2(-1.0, -30.0), vector2(1.0, 0.0)),
+            rectangle(vector2(-1.0, 4.0 * ENTITY_RADIUS), vector2(1.0, 30.0)),
+        ];
+        let (start, end) = (vector2(-5.0, 0.0), vector2(5.0, 0.0));
+
+        let small = NavGraph::new(&buildings, ENTITY_RADIUS);
+        assert!(path_length(&small.find_path(start, end).unwrap()) < 11.0);
+
+        let big = NavGraph::new(&buildings, radius);
+        let path = big.find_path(start, end).unwrap();
+        assert!(path_length(&path) > 60.0);
+        for edge in &path.edges {
+            for building in &buildings {
+                assert!(clearance(building, edge.start.pos, edge.end.pos) > 0.98 * radius);
+            }
+        }
+    }
+}
@@ -19,7 +19,9 @@ pub struct Edge {
 #[derive(Clone, Debug)]
 pub struct Path {
     pub edges: Vec<Edge>,
-    pub cost: Scalar
+    pub cost: Scalar,
+    // Version of the navigation graph the path was planned on
+    pub version: u32
 }
 
 impl PartialOrd for Path {
@@ -38,7 +40,16 @@ impl Path {
     pub fn from_edge(e: Edge) -> Path {
         Path {
             edges: vec![e],
-            cost: e.cost
+            cost: e.cost,
+            version: 0
+        }
+    }
+
+    // Drop the first edge once its end has been reached, keeping the last one
+    pub fn advance(&mut self) {
+        if self.edges.len() > 1 {
+            let edge = self.edges.remove(0);
+            self.cost -= edge.cost;
         }
     }
 
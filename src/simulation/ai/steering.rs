@@ -0,0 +1,171 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+
+use crate::simulation::state::*;
+
+// Distance from the end of a path at which units start slowing down to stop on it
+pub const ARRIVAL_SLOWING_RADIUS: Scalar = 1.5;
+
+// Slowest a unit takes a corner at, as a fraction of its top speed, however sharp the turn
+pub const CORNER_MIN_SPEED_FRACTION: Scalar = 0.3;
+
+// How quickly a unit's velocity is brought round to the one it wants, in seconds
+pub const STEERING_RESPONSE_TIME: Scalar = 0.25;
+
+// Hardest a unit can accelerate or brake, as a multiple of its movement force
+pub const STEERING_MAX_ACCELERATION_MULTIPLIER: Scalar = 2.0;
+
+// Units closer than this are pushed apart
+pub const SEPARATION_RADIUS: Scalar = 3.0 * ENTITY_RADIUS;
+
+// How far ahead, in seconds, units look for others they are about to walk into
+pub const AVOIDANCE_TIME_HORIZON: Scalar = 1.5;
+
+// Units only look this far for others to avoid, which rules out most of the map cheaply
+pub const AVOIDANCE_RADIUS: Scalar = 6.0;
+pub const AVOIDANCE_RADIUS_SQUARED: Scalar = AVOIDANCE_RADIUS * AVOIDANCE_RADIUS;
+
+// Relative strength of keeping apart from and steering around other units
+pub const SEPARATION_WEIGHT: Scalar = 1.0;
+pub const AVOIDANCE_WEIGHT: Scalar = 1.5;
+
+// Units crowding round a waypoint that is already taken stop once they are this close to it
+pub const CROWDED_WAYPOINT_RADIUS: Scalar = 2.0;
+
+// Speed that an entity pushed along by a movement force settles at against the drag
+fn top_speed(force: Scalar) -> Scalar {
+    force / ENTITY_DRAG
+}
+
+// Head for a waypoint on a path, slowing down for a sharp turn onto the next one, or to stop
+// on the waypoint when it is the last
+pub fn steer_towards_waypoint(entities: &mut Vec<Entity>,
+                              index: usize,
+                              waypoint: Vector2,
+                              next_waypoint: Option<Vector2>,
+                              force: Scalar,
+                              delta_time: Scalar) {
+    let delta = waypoint - entities[index].position;
+    let distance = delta.length();
+    if distance == 0.0 {
+        return;
+    }
+
+    let max_speed = top_speed(force);
+    let corner_speed = match next_waypoint {
+        // Going straight on needs no slowing down, turning back on itself the most
+        Some(next) if next != waypoint => {
+            let turn_cos = delta.normalize().dot((next - waypoint).normalize());
+            max_speed * (0.5 * (1.0 + turn_cos)).max(CORNER_MIN_SPEED_FRACTION)
+        }
+        Some(_) => max_speed,
+        None => 0.0,
+    };
+
+    let approach = (distance / ARRIVAL_SLOWING_RADIUS).min(1.0);
+    let speed = corner_speed + (max_speed - corner_speed) * approach;
+
+    entities[index].look_along_vector(delta, delta_time);
+    steer(entities, index, speed * delta / distance, force, delta_time);
+}
+
+// Head along a direction at full speed, for units chasing or running rather than following a path
+pub fn steer_along_vector(entities: &mut Vec<Entity>,
+                          index: usize,
+                          direction: Vector2,
+                          force: Scalar,
+                          delta_time: Scalar) {
+    if direction.length_squared() == 0.0 {
+        return;
+    }
+
+    entities[index].look_along_vector(direction, delta_time);
+    steer(entities, index, top_speed(force) * direction.normalize(), force, delta_time);
+}
+
+// Whether another unit on the same side is standing on the waypoint and keeping the entity, which
+// is already close, from getting to it. Cops sent to the same place settle around it this way
+pub fn is_waypoint_taken(entities: &Vec<Entity>, index: usize, waypoint: Vector2) -> bool {
+    let me = &entities[index];
+    if (waypoint - me.position).length_squared() > CROWDED_WAYPOINT_RADIUS * CROWDED_WAYPOINT_RADIUS {
+        return false;
+    }
+
+    entities.iter().enumerate().any(|(i, other)| {
+        i != index && other.is_alive() && other.is_zombie() == me.is_zombie() &&
+            (waypoint - other.position).length_squared() < (waypoint - me.position).length_squared() &&
+            (other.position - me.position).length_squared() < SEPARATION_RADIUS * SEPARATION_RADIUS
+    })
+}
+
+// Bring the entity's velocity round to the desired one, while keeping clear of the units around
+// it. The drag is made up for, so an entity at the desired velocity holds it
+fn steer(entities: &mut Vec<Entity>, index: usize, desired_velocity: Vector2, force: Scalar, delta_time: Scalar) {
+    let desired_velocity = desired_velocity + top_speed(force) * local_avoidance(entities, index);
+
+    let max_acceleration = STEERING_MAX_ACCELERATION_MULTIPLIER * force;
+    let mut acceleration = ENTITY_DRAG * desired_velocity +
+        (desired_velocity - entities[index].velocity) / STEERING_RESPONSE_TIME;
+    if acceleration.length_squared() > max_acceleration * max_acceleration {
+        acceleration = acceleration.normalize_to(max_acceleration);
+    }
+
+    entities[index].velocity += delta_time * acceleration;
+}
+
+// Which way to sidestep the units on the same side as the entity, as a fraction of its top speed.
+// Units already too close are pushed straight apart, and units on course to walk into it are
+// stepped around sideways, sooner the closer the collision is
+fn local_avoidance(entities: &Vec<Entity>, index: usize) -> Vector2 {
+    let me = &entities[index];
+    let is_zombie = me.is_zombie();
+
+    let mut separation = Vector2::zero();
+    let mut avoidance = Vector2::zero();
+
+    for (i, other) in entities.iter().enumerate() {
+        if i == index || !other.is_alive() || other.is_zombie() != is_zombie {
+            continue;
+        }
+
+        let offset = me.position - other.position;
+        let distance_squared = offset.length_squared();
+        if distance_squared > AVOIDANCE_RADIUS_SQUARED {
+            continue;
+        }
+
+        if 0.0 < distance_squared && distance_squared < SEPARATION_RADIUS * SEPARATION_RADIUS {
+            let distance = distance_squared.sqrt();
+            separation += (1.0 - distance / SEPARATION_RADIUS) * offset / distance;
+        }
+
+        // When the two are closest if they both carry on as they are
+        let relative_velocity = me.velocity - other.velocity;
+        let closing_speed_squared = relative_velocity.length_squared();
+        if closing_speed_squared == 0.0 {
+            continue;
+        }
+        let time = -offset.dot(relative_velocity) / closing_speed_squared;
+        if time <= 0.0 || time > AVOIDANCE_TIME_HORIZON {
+            continue;
+        }
+
+        let miss = offset + time * relative_velocity;
+        let miss_distance_squared = miss.length_squared();
+        if miss_distance_squared >= SEPARATION_RADIUS * SEPARATION_RADIUS {
+            continue;
+        }
+
+        // Head-on, so pick a side to pass on
+        let sidestep = if miss_distance_squared > 0.0 { miss.normalize() } else { relative_velocity.normalize().right() };
+        avoidance += (1.0 - time / AVOIDANCE_TIME_HORIZON) * sidestep;
+    }
+
+    // A crowd shouldn't be able to push a unit along faster than it can run
+    let steering = SEPARATION_WEIGHT * separation + AVOIDANCE_WEIGHT * avoidance;
+    if steering.length_squared() > 1.0 {
+        steering.normalize()
+    } else {
+        steering
+    }
+}
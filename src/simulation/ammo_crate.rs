@@ -0,0 +1,88 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::simulation::state::*;
+use crate::simulation::weapon::Weapon;
+
+pub const AMMO_CRATE_COST: u32 = 10;
+pub const AMMO_CRATE_MAGAZINES: i64 = 8;
+pub const AMMO_CRATE_SIZE: Scalar = 0.8;
+
+// Cops within this distance of a crate can restock from it
+pub const AMMO_CRATE_REACH: Scalar = 1.5;
+pub const AMMO_CRATE_REACH_SQUARED: Scalar = AMMO_CRATE_REACH * AMMO_CRATE_REACH;
+
+// Police stations never run out, cops are restocked there within the same reach as a crate
+pub const POLICE_STATION_SIZE: Scalar = 2.0;
+
+#[derive(Clone, Debug)]
+pub struct AmmoCrate {
+    pub position: Vector2,
+    pub magazines: i64,
+}
+
+impl AmmoCrate {
+    pub fn new(position: Vector2) -> AmmoCrate {
+        AmmoCrate {
+            position,
+            magazines: AMMO_CRATE_MAGAZINES,
+        }
+    }
+
+    // Hand over as many magazines for the weapon as the cop has room for
+    pub fn restock(&mut self, weapon: &Weapon, reserve_magazines: &mut i64) {
+        let taken = self.magazines.min(weapon.max_reserve_magazines - *reserve_magazines).max(0);
+        self.magazines -= taken;
+        *reserve_magazines += taken;
+    }
+}
+
+pub fn ammo_crate_valid(position: Vector2, state: &State) -> bool {
+    state.money >= AMMO_CRATE_COST &&
+        state.buildings.iter().all(|building| !building.contains_point(position)) &&
+        state.barricades.iter().all(|barricade| !barricade.poly.contains_point(position))
+}
+
+// Fill up on magazines for the weapon, as a police station does
+pub fn restock_fully(weapon: &Weapon, reserve_magazines: &mut i64) {
+    *reserve_magazines = (*reserve_magazines).max(weapon.max_reserve_magazines);
+}
+
+pub fn is_at_police_station(position: Vector2, police_stations: &Vec<Vector2>) -> bool {
+    police_stations.iter().any(|station| (*station - position).length_squared() < AMMO_CRATE_REACH_SQUARED)
+}
+
+// Find the nearest police station or crate that still has ammo in it
+pub fn nearest_resupply_point(position: Vector2, ammo_crates: &Vec<AmmoCrate>, police_stations: &Vec<Vector2>) -> Option<Vector2> {
+    let stocked_crates = ammo_crates.iter()
+        .filter(|ammo_crate| ammo_crate.magazines > 0)
+        .map(|ammo_crate| ammo_crate.position);
+
+    let mut min_distance_squared = INFINITY;
+    let mut nearest = None;
+
+    for point in stocked_crates.chain(police_stations.iter().cloned()) {
+        let distance_squared = (point - position).length_squared();
+        if distance_squared < min_distance_squared {
+            min_distance_squared = distance_squared;
+            nearest = Some(point);
+        }
+    }
+
+    nearest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resupply_skips_empty_crates() {
+        let mut empty_crate = AmmoCrate::new(vector2(1.0, 0.0));
+        empty_crate.magazines = 0;
+        let ammo_crates = vec![empty_crate, AmmoCrate::new(vector2(5.0, 0.0))];
+
+        assert_eq!(nearest_resupply_point(Vector2::zero(), &ammo_crates, &vec![]), Some(vector2(5.0, 0.0)));
+        assert_eq!(nearest_resupply_point(Vector2::zero(), &ammo_crates, &vec![vector2(0.0, -3.0)]), Some(vector2(0.0, -3.0)));
+        assert_eq!(nearest_resupply_point(Vector2::zero(), &ammo_crates[..1].to_vec(), &vec![]), None);
+    }
+}
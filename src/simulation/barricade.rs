@@ -9,26 +9,13 @@ pub const BARRICADE_MIN_COST: u32 = 5;
 #[derive(Clone, Debug)]
 pub struct Barricade {
     pub poly: Polygon,
-    pub outline: Polygon,
     pub health: Scalar
 }
 
 impl Barricade {
     pub fn new(start: Vector2, end: Vector2) -> Barricade {
-        let poly = barricade_poly(start, end);
-
-        let mut outlines = vec!();
-        let norms = poly.normals();
-
-        for i in 0..poly.num_sides() {
-            let norm_sum = norms[i] + norms[if i < 1 { poly.num_sides() - 1 } else { i - 1 }];
-            let offset = norm_sum * ENTITY_RADIUS * 1.1;
-            outlines.push(offset + poly.get(i));
-        }
-
         Barricade {
-            poly,
-            outline: Polygon(outlines),
+            poly: barricade_poly(start, end),
             health: BARRICADE_HEALTH
         }
     }
@@ -0,0 +1,283 @@
+use crate::core::scalar::*;
+use crate::simulation::state::*;
+use crate::simulation::objectives::*;
+use crate::simulation::upgrades::*;
+use crate::simulation::day_night::DAY_NIGHT_DEFAULT_STARTING_HOUR;
+use crate::simulation::map::{parse_number, parse_vector};
+
+use std::fs;
+
+pub const CAMPAIGN_PATH: &str = "assets/campaign/missions.txt";
+pub const CAMPAIGN_PROGRESS_PATH: &str = "campaign_progress.txt";
+
+pub const CAMPAIGN_STARTING_MONEY: u32 = 20;
+
+#[derive(Clone, Debug)]
+pub struct Mission {
+    pub name: String,
+    // Played on the default map unless one is given
+    pub map: Option<String>,
+    pub briefing: Vec<String>,
+    pub entity_count: u32,
+    pub cop_portion: Scalar,
+    pub infected_portion: Scalar,
+    pub starting_hour: Scalar,
+    pub objectives: Vec<Objective>,
+    // The VIP can only be picked once the entities have been spawned
+    pub protect_vip: bool,
+    // Civilians needed at the map's extraction point, which is only known once the map is loaded
+    pub evacuate_to_extraction: Option<usize>,
+    // Seconds to hold the map's plaza for, which is likewise only known once the map is loaded
+    pub hold_plaza: Option<Scalar>,
+}
+
+impl Mission {
+    fn new(name: String) -> Mission {
+        Mission {
+            name,
+            map: None,
+            briefing: vec!(),
+            entity_count: 100,
+            cop_portion: 0.05,
+            infected_portion: 0.1,
+            starting_hour: DAY_NIGHT_DEFAULT_STARTING_HOUR,
+            objectives: vec!(),
+            protect_vip: false,
+            evacuate_to_extraction: None,
+            hold_plaza: None,
+        }
+    }
+}
+
+// Missions are listed in order, each starting with a "mission <name>" line and followed by
+// one "<key> <values>" line per setting. Blank lines and lines starting with # are ignored
+pub fn parse_campaign(text: &str) -> Result<Vec<Mission>, String> {
+    let mut missions: Vec<Mission> = vec!();
+
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let key = words.next().unwrap();
+        let rest = line[key.len()..].trim().to_string();
+
+        if key == "mission" {
+            missions.push(Mission::new(rest));
+            continue;
+        }
+
+        let mission = match missions.last_mut() {
+            Some(mission) => mission,
+            None => return Err(format!("line {}: \"{}\" comes before the first mission", line_number, key)),
+        };
+
+        match key {
+            "map" if !rest.is_empty() => mission.map = Some(rest),
+            "map" => return Err(format!("line {}: missing map path", line_number)),
+            "briefing" => mission.briefing.push(rest),
+            "entities" => mission.entity_count = parse_number(&mut words, line_number)?,
+            "cops" => mission.cop_portion = parse_number(&mut words, line_number)?,
+            "infected" => mission.infected_portion = parse_number(&mut words, line_number)?,
+            "starting_hour" => mission.starting_hour = parse_number(&mut words, line_number)?,
+            "objective" => {
+                match words.next() {
+                    Some("clear_infection") =>
+                        mission.objectives.push(Objective::new(ObjectiveType::ClearInfection)),
+                    Some("limit_casualties") =>
+                        mission.objectives.push(Objective::limit_casualties(parse_number(&mut words, line_number)?)),
+                    // Either just the duration, to hold the map's plaza, or an area and the duration
+                    Some("hold_area") if words.clone().count() == 1 =>
+                        mission.hold_plaza = Some(parse_number(&mut words, line_number)?),
+                    Some("hold_area") => {
+                        let center = parse_vector(&mut words, line_number)?;
+                        let radius = parse_number(&mut words, line_number)?;
+                        let duration = parse_number(&mut words, line_number)?;
+                        mission.objectives.push(Objective::hold_area(center, radius, duration));
+                    }
+                    // Either just the count, to use the map's extraction point, or a zone and the count
+                    Some("evacuate") if words.clone().count() == 1 =>
+                        mission.evacuate_to_extraction = Some(parse_number(&mut words, line_number)?),
+                    Some("evacuate") => {
+                        let center = parse_vector(&mut words, line_number)?;
+                        let radius = parse_number(&mut words, line_number)?;
+                        let required = parse_number(&mut words, line_number)?;
+                        mission.objectives.push(Objective::evacuate(center, radius, required));
+                    }
+                    Some("protect_vip") => mission.protect_vip = true,
+                    Some(objective) => return Err(format!("line {}: unknown objective \"{}\"", line_number, objective)),
+                    None => return Err(format!("line {}: missing objective type", line_number)),
+                }
+            }
+            _ => return Err(format!("line {}: unknown setting \"{}\"", line_number, key)),
+        }
+    }
+
+    // A mission without objectives could never be won
+    for mission in &mut missions {
+        if mission.objectives.is_empty() && mission.evacuate_to_extraction.is_none() && mission.hold_plaza.is_none() {
+            mission.objectives.push(Objective::new(ObjectiveType::ClearInfection));
+        }
+    }
+
+    Ok(missions)
+}
+
+pub fn load_campaign(path: &str) -> Result<Vec<Mission>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse_campaign(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+// Everything that carries over from one mission to the next
+#[derive(Clone, Debug)]
+pub struct CampaignProgress {
+    pub missions_completed: usize,
+    pub money: u32,
+    pub upgrades: Upgrades,
+    // Cops that survived the last mission, who join the next one
+    pub cops: Vec<CopType>,
+}
+
+impl CampaignProgress {
+    pub fn new() -> CampaignProgress {
+        CampaignProgress {
+            missions_completed: 0,
+            money: CAMPAIGN_STARTING_MONEY,
+            upgrades: Upgrades::default(),
+            cops: vec!(),
+        }
+    }
+
+    // Start over if there's no progress saved yet, or it can't be read
+    pub fn load(path: &str) -> CampaignProgress {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(_) => return CampaignProgress::new(),
+        };
+
+        match CampaignProgress::parse(&text) {
+            Ok(progress) => progress,
+            Err(e) => {
+                println!("Ignoring campaign progress in {}: {}", path, e);
+                CampaignProgress::new()
+            }
+        }
+    }
+
+    fn parse(text: &str) -> Result<CampaignProgress, String> {
+        let mut progress = CampaignProgress::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("missions_completed") => progress.missions_completed = parse_number(&mut words, line_number)?,
+                Some("money") => progress.money = parse_number(&mut words, line_number)?,
+                Some("upgrades") => {
+                    for upgrade_type in UPGRADE_TYPES.iter() {
+                        let level = parse_number(&mut words, line_number)?;
+                        progress.upgrades.set_level(*upgrade_type, level);
+                    }
+                }
+                Some("cops") => {
+                    for word in words {
+                        progress.cops.push(match word {
+                            "Normal" => CopType::Normal,
+                            "Soldier" => CopType::Soldier,
+                            _ => return Err(format!("line {}: unknown cop type \"{}\"", line_number, word)),
+                        });
+                    }
+                }
+                Some(key) => return Err(format!("line {}: unknown setting \"{}\"", line_number, key)),
+                None => (),
+            }
+        }
+
+        Ok(progress)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let upgrades: Vec<String> = UPGRADE_TYPES.iter()
+            .map(|upgrade_type| self.upgrades.level(*upgrade_type).to_string())
+            .collect();
+        let cops: Vec<String> = self.cops.iter()
+            .map(|cop_type| format!("{:?}", cop_type))
+            .collect();
+
+        let text = format!("missions_completed {}\nmoney {}\nupgrades {}\ncops {}\n",
+                           self.missions_completed, self.money, upgrades.join(" "), cops.join(" "));
+        fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    // Missions unlock one at a time, in order
+    pub fn is_unlocked(&self, mission_index: usize) -> bool {
+        mission_index <= self.missions_completed
+    }
+
+    // Record a won mission, keeping whatever the player finished it with. Replays of missions
+    // before the latest one are left out, so they can't undo what's been won since
+    pub fn complete_mission(&mut self, mission_index: usize, state: &State) {
+        if mission_index + 1 < self.missions_completed {
+            return;
+        }
+
+        self.missions_completed = mission_index + 1;
+        self.money = state.money;
+        self.upgrades = state.upgrades;
+        self.cops = state.entities.iter()
+            .filter_map(|entity| match &entity.dead_or_alive {
+                DeadOrAlive::Alive {
+                    zombie_or_human: ZombieOrHuman::Human { infection, human: Human::Cop { cop_type, .. } },
+                    ..
+                } if *infection < INFECTION_EXPONENTIAL_GROWTH_THRESHOLD => Some(*cop_type),
+                _ => None,
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::initial_state::initial_state;
+    use crate::simulation::map::*;
+
+    #[test]
+    fn missions_can_leave_their_areas_to_the_map() {
+        let missions = parse_campaign("mission Square\nmap assets/maps/square.txt\nobjective hold_area 60\n\
+                                       mission Streets\nobjective hold_area 10 20 5 30\n").unwrap();
+
+        assert_eq!(missions[0].map, Some("assets/maps/square.txt".to_string()));
+        assert_eq!(missions[0].hold_plaza, Some(60.0));
+        assert!(missions[0].objectives.is_empty());
+
+        assert_eq!(missions[1].map, None);
+        assert_eq!(missions[1].hold_plaza, None);
+        assert_eq!(missions[1].objectives.len(), 1);
+
+        assert!(parse_campaign("mission Nowhere\nmap\n").is_err());
+    }
+
+    #[test]
+    fn replaying_an_earlier_mission_keeps_later_progress() {
+        let mut state = initial_state(&load_map(DEFAULT_MAP_PATH).unwrap(), 20, 0.1, 0.1, 1);
+        let mut progress = CampaignProgress::new();
+
+        state.money = 50;
+        progress.complete_mission(0, &state);
+        state.money = 80;
+        progress.complete_mission(1, &state);
+        assert_eq!((progress.missions_completed, progress.money), (2, 80));
+
+        state.money = 10;
+        progress.complete_mission(0, &state);
+        assert_eq!((progress.missions_completed, progress.money), (2, 80));
+
+        // Replaying the latest one still counts
+        progress.complete_mission(1, &state);
+        assert_eq!((progress.missions_completed, progress.money), (2, 10));
+    }
+}
@@ -0,0 +1,435 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::core::geo::polygon::*;
+use crate::core::geo::segment2::Segment2;
+use crate::simulation::map::*;
+use crate::simulation::state::ENTITY_RADIUS;
+use crate::simulation::ai::nav_graph::NavGraph;
+
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+// The wall matches the default map so the backgrounds line up, with a ring road inside it
+const CITY_WALL_MIN: Scalar = -25.0;
+const CITY_WALL_MAX: Scalar = 115.0;
+const CITY_WALL_THICKNESS: Scalar = 0.5;
+const CITY_MIN: Scalar = -20.0;
+const CITY_MAX: Scalar = 110.0;
+
+const CITY_BLOCK_SIZE_MIN: Scalar = 16.0;
+const CITY_BLOCK_SIZE_MAX: Scalar = 30.0;
+const CITY_ROAD_WIDTH_MIN: Scalar = 6.0;
+const CITY_ROAD_WIDTH_MAX: Scalar = 10.0;
+
+// Blocks are divided into lots of about this size, with alleys between them wide enough to walk down
+const CITY_LOT_SIZE: Scalar = 12.0;
+const CITY_ALLEY_WIDTH: Scalar = 2.5;
+// Furthest a building sits back from the edge of its lot
+const CITY_SETBACK_MAX: Scalar = 1.5;
+
+const CITY_PARK_CHANCE: f64 = 0.1;
+const CITY_EMPTY_LOT_CHANCE: f64 = 0.1;
+const CITY_AMMO_CRATE_COUNT: usize = 4;
+
+// A city that fails the connectivity check is thrown away and generated again, up to this many
+// times before buildings are knocked down to connect the last one
+const CITY_GENERATION_ATTEMPTS: u32 = 10;
+// Spacing of the grid the open areas are flood filled on
+const CITY_CONNECTIVITY_CELL_SIZE: Scalar = 0.5;
+
+#[derive(Copy, Clone, Debug)]
+enum Footprint {
+    Rectangle,
+    // Rectangle with one corner cut off
+    Chamfered,
+    Octagon,
+    // Rectangle with a rectangular bite taken out of one corner
+    LShape,
+}
+
+const FOOTPRINTS: [Footprint; 4] = [Footprint::Rectangle, Footprint::Chamfered, Footprint::Octagon, Footprint::LShape];
+
+impl Footprint {
+    // Pick a texture that suits the shape
+    fn building_type<R: Rng>(&self, rng: &mut R) -> u32 {
+        match self {
+            Footprint::Rectangle => if rng.gen_bool(0.5) { 0 } else { 4 },
+            Footprint::Octagon => 3,
+            Footprint::Chamfered | Footprint::LShape => [1, 2, 5, 6][rng.gen_range(0, 4)],
+        }
+    }
+
+    // Fit the footprint to the rectangle, with vertices in counterclockwise order
+    fn polygon<R: Rng>(&self, min: Vector2, max: Vector2, rng: &mut R) -> Polygon {
+        let size = max - min;
+        let corners = [min, vector2(max.x, min.y), max, vector2(min.x, max.y)];
+
+        match self {
+            Footprint::Rectangle => Polygon(corners.to_vec()),
+            Footprint::Chamfered => {
+                let cut = rng.gen_range(0.3, 0.5) * size.x.min(size.y);
+                let corner = rng.gen_range(0, 4);
+                let mut vertices = vec!();
+                for i in 0..4 {
+                    if i == corner {
+                        let previous = corners[(i + 3) % 4];
+                        let next = corners[(i + 1) % 4];
+                        vertices.push(corners[i] + cut * (previous - corners[i]).normalize());
+                        vertices.push(corners[i] + cut * (next - corners[i]).normalize());
+                    } else {
+                        vertices.push(corners[i]);
+                    }
+                }
+                Polygon(vertices)
+            }
+            Footprint::Octagon => {
+                let cut = rng.gen_range(0.2, 0.3) * size.x.min(size.y);
+                let mut vertices = vec!();
+                for i in 0..4 {
+                    let previous = corners[(i + 3) % 4];
+                    let next = corners[(i + 1) % 4];
+                    vertices.push(corners[i] + cut * (previous - corners[i]).normalize());
+                    vertices.push(corners[i] + cut * (next - corners[i]).normalize());
+                }
+                Polygon(vertices)
+            }
+            Footprint::LShape => {
+                let bite = vector2(rng.gen_range(0.35, 0.6) * size.x, rng.gen_range(0.35, 0.6) * size.y);
+                let corner = rng.gen_range(0, 4);
+                let to_previous = (corners[(corner + 3) % 4] - corners[corner]).normalize();
+                let to_next = (corners[(corner + 1) % 4] - corners[corner]).normalize();
+                // The two edges at a corner run along different axes
+                let along_previous = to_previous * if to_previous.x != 0.0 { bite.x } else { bite.y };
+                let along_next = to_next * if to_next.x != 0.0 { bite.x } else { bite.y };
+
+                let mut vertices = vec!();
+                for i in 0..4 {
+                    if i == corner {
+                        vertices.push(corners[i] + along_previous);
+                        vertices.push(corners[i] + along_previous + along_next);
+                        vertices.push(corners[i] + along_next);
+                    } else {
+                        vertices.push(corners[i]);
+                    }
+                }
+                Polygon(vertices)
+            }
+        }
+    }
+}
+
+// Divide the span into blocks with roads between them
+fn block_spans<R: Rng>(rng: &mut R) -> Vec<(Scalar, Scalar)> {
+    let mut spans = vec!();
+    let mut start = CITY_MIN;
+
+    loop {
+        let end = start + rng.gen_range(CITY_BLOCK_SIZE_MIN, CITY_BLOCK_SIZE_MAX);
+        let next_start = end + rng.gen_range(CITY_ROAD_WIDTH_MIN, CITY_ROAD_WIDTH_MAX);
+
+        // Stretch the last block to the edge rather than leave a sliver
+        if CITY_MAX - next_start < CITY_BLOCK_SIZE_MIN {
+            spans.push((start, CITY_MAX));
+            return spans;
+        }
+
+        spans.push((start, end));
+        start = next_start;
+    }
+}
+
+// Divide the span into equal lots with alleys between them
+fn lot_spans(start: Scalar, end: Scalar) -> Vec<(Scalar, Scalar)> {
+    let length = end - start;
+    let count = ((length + CITY_ALLEY_WIDTH) / (CITY_LOT_SIZE + CITY_ALLEY_WIDTH)).round().max(1.0) as usize;
+    let lot_length = (length - CITY_ALLEY_WIDTH * (count - 1) as Scalar) / count as Scalar;
+
+    (0..count)
+        .map(|i| {
+            let lot_start = start + i as Scalar * (lot_length + CITY_ALLEY_WIDTH);
+            (lot_start, lot_start + lot_length)
+        })
+        .collect()
+}
+
+fn wall(min: Vector2, max: Vector2) -> Polygon {
+    Polygon(vec![min, vector2(max.x, min.y), max, vector2(min.x, max.y)])
+}
+
+// Generate a city from the seed, the same seed always giving the same city
+pub fn generate_city(seed: u64) -> Map {
+    let mut rng = XorShiftRng::seed_from_u64(seed);
+    let mut map = generate_city_layout(&mut rng);
+
+    for _ in 1..CITY_GENERATION_ATTEMPTS {
+        if is_connected(&map) {
+            return map;
+        }
+        map = generate_city_layout(&mut rng);
+    }
+
+    remove_blocking_buildings(&mut map);
+    map
+}
+
+// Knock down the building nearest to somewhere that can't be reached until everywhere can be,
+// which it always can once there are no buildings left
+fn remove_blocking_buildings(map: &mut Map) {
+    loop {
+        let point = match unreachable_points(map).first() {
+            Some(point) => *point,
+            None => return,
+        };
+
+        let distance_to = |building: &Polygon| {
+            let sides = building.num_sides();
+            (0..sides)
+                .map(|i| Segment2 { p1: building.get(i), p2: building.get((i + 1) % sides) }.distance_from_segment_to_point_squared(point))
+                .fold(INFINITY, |a, b| a.min(b))
+        };
+        let nearest = (0..map.buildings.len())
+            .min_by(|i, j| distance_to(&map.buildings[*i].0).partial_cmp(&distance_to(&map.buildings[*j].0)).unwrap());
+        match nearest {
+            Some(index) => { map.buildings.remove(index); }
+            None => return,
+        }
+    }
+}
+
+fn generate_city_layout<R: Rng>(rng: &mut R) -> Map {
+    let mut map = Map::new();
+    map.camera_min = vector2(CITY_WALL_MIN, CITY_WALL_MIN);
+    map.camera_max = vector2(CITY_WALL_MAX, CITY_WALL_MAX);
+
+    let columns = block_spans(rng);
+    let rows = block_spans(rng);
+
+    // There's always at least one park, so there's somewhere to hold
+    let park = (rng.gen_range(0, columns.len()), rng.gen_range(0, rows.len()));
+
+    for (i, (x0, x1)) in columns.iter().enumerate() {
+        for (j, (y0, y1)) in rows.iter().enumerate() {
+            let block_min = vector2(*x0, *y0);
+            let block_max = vector2(*x1, *y1);
+
+            if (i, j) == park || rng.gen_bool(CITY_PARK_CHANCE) {
+                let size = block_max - block_min;
+                map.plazas.push(Plaza {
+                    center: 0.5 * (block_min + block_max),
+                    radius: 0.4 * size.x.min(size.y),
+                });
+                continue;
+            }
+
+            for (lot_x0, lot_x1) in lot_spans(*x0, *x1) {
+                for (lot_y0, lot_y1) in lot_spans(*y0, *y1) {
+                    if rng.gen_bool(CITY_EMPTY_LOT_CHANCE) {
+                        continue;
+                    }
+
+                    let min = vector2(lot_x0 + rng.gen_range(0.0, CITY_SETBACK_MAX), lot_y0 + rng.gen_range(0.0, CITY_SETBACK_MAX));
+                    let max = vector2(lot_x1 - rng.gen_range(0.0, CITY_SETBACK_MAX), lot_y1 - rng.gen_range(0.0, CITY_SETBACK_MAX));
+                    let footprint = FOOTPRINTS[rng.gen_range(0, FOOTPRINTS.len())];
+                    let building_type = footprint.building_type(rng);
+                    map.buildings.push((footprint.polygon(min, max, rng), building_type));
+                }
+            }
+        }
+    }
+
+    // Perimeter wall
+    let inner_min = CITY_WALL_MIN + CITY_WALL_THICKNESS;
+    let inner_max = CITY_WALL_MAX - CITY_WALL_THICKNESS;
+    map.boundaries.push(wall(vector2(CITY_WALL_MIN, CITY_WALL_MIN), vector2(CITY_WALL_MAX, inner_min)));
+    map.boundaries.push(wall(vector2(inner_max, CITY_WALL_MIN), vector2(CITY_WALL_MAX, CITY_WALL_MAX)));
+    map.boundaries.push(wall(vector2(CITY_WALL_MIN, CITY_WALL_MIN), vector2(inner_min, CITY_WALL_MAX)));
+    map.boundaries.push(wall(vector2(CITY_WALL_MIN, inner_max), vector2(CITY_WALL_MAX, CITY_WALL_MAX)));
+
+    // People start out all over the city like in the default map, except the cops who gather in the park
+    let city_min = vector2(CITY_MIN, CITY_MIN);
+    let city_max = vector2(CITY_MAX, CITY_MAX);
+    let park_min = vector2(columns[park.0].0, rows[park.1].0);
+    let park_max = vector2(columns[park.0].1, rows[park.1].1);
+    map.spawn_zones.push(SpawnZone { kind: SpawnKind::Infected, min: city_min, max: city_max });
+    map.spawn_zones.push(SpawnZone { kind: SpawnKind::Cop, min: park_min, max: park_max });
+    map.spawn_zones.push(SpawnZone { kind: SpawnKind::Civilian, min: city_min, max: city_max });
+
+    // Road intersections are always clear, so the extraction point, ammo crates and police station go there
+    let mut intersections = vec!();
+    for i in 1..columns.len() {
+        for j in 1..rows.len() {
+            intersections.push(vector2(0.5 * (columns[i - 1].1 + columns[i].0), 0.5 * (rows[j - 1].1 + rows[j].0)));
+        }
+    }
+    if intersections.is_empty() {
+        intersections.push(0.5 * (city_min + city_max));
+    }
+
+    // Extract from an intersection on the southernmost road, like the bus stop in the default map
+    let southernmost = intersections.iter().map(|intersection| intersection.y).fold(INFINITY, |a, b| a.min(b));
+    let southern: Vec<Vector2> = intersections.iter().cloned().filter(|intersection| intersection.y == southernmost).collect();
+    let road_width = rows.get(1).map_or(CITY_ROAD_WIDTH_MIN, |row| row.0 - rows[0].1);
+    let extraction_center = southern[rng.gen_range(0, southern.len())];
+    map.extraction_points.push(ExtractionPoint {
+        center: extraction_center,
+        radius: 0.5 * road_width,
+    });
+
+    // Reinforcements come in on the ring road south of the extraction point, and survival waves
+    // come in on the ring road in the middle of each side and head for the nearest intersection
+    let ring_min = 0.5 * (inner_min + CITY_MIN);
+    let ring_max = 0.5 * (inner_max + CITY_MAX);
+    let middle = 0.5 * (CITY_MIN + CITY_MAX);
+    map.entry = vector2(extraction_center.x, ring_min);
+    for position in &[vector2(middle, ring_min), vector2(ring_max, middle), vector2(middle, ring_max), vector2(ring_min, middle)] {
+        let rally_point = intersections.iter().cloned()
+            .min_by(|a, b| (*a - *position).length_squared().partial_cmp(&(*b - *position).length_squared()).unwrap())
+            .unwrap();
+        map.wave_spawns.push(WaveSpawn { position: *position, rally_point });
+    }
+
+    for _ in 0..CITY_AMMO_CRATE_COUNT {
+        let position = intersections[rng.gen_range(0, intersections.len())];
+        if !map.ammo_crates.contains(&position) {
+            map.ammo_crates.push(position);
+        }
+    }
+
+    // One police station, somewhere the crates aren't
+    let free: Vec<Vector2> = intersections.iter().cloned().filter(|position| !map.ammo_crates.contains(position)).collect();
+    if !free.is_empty() {
+        map.police_stations.push(free[rng.gen_range(0, free.len())]);
+    }
+
+    map
+}
+
+// Everywhere open has to be reachable from everywhere else
+pub fn is_connected(map: &Map) -> bool {
+    unreachable_points(map).is_empty()
+}
+
+// Places that can't be got to from the ring road. The open areas are flood filled to find any
+// that are closed off, then the pathfinding is checked against each plaza and extraction point
+fn unreachable_points(map: &Map) -> Vec<Vector2> {
+    let mut obstacles: Vec<Polygon> = map.buildings.iter().map(|(building, _)| building.clone()).collect();
+    obstacles.extend(map.boundaries.iter().cloned());
+
+    let min = map.camera_min;
+    let columns = ((map.camera_max.x - min.x) / CITY_CONNECTIVITY_CELL_SIZE) as usize;
+    let rows = ((map.camera_max.y - min.y) / CITY_CONNECTIVITY_CELL_SIZE) as usize;
+    // Cell centres are offset a little, so the point in polygon test doesn't land on vertices
+    let cell_center = |column: usize, row: usize| min + vector2(
+        (column as Scalar + 0.4837) * CITY_CONNECTIVITY_CELL_SIZE,
+        (row as Scalar + 0.5163) * CITY_CONNECTIVITY_CELL_SIZE);
+
+    let bounding_boxes: Vec<(Vector2, Vector2)> = obstacles.iter().map(|obstacle| obstacle.bounding_box()).collect();
+    let is_open = |point: Vector2| {
+        (0..obstacles.len()).all(|i| {
+            let (low, high) = bounding_boxes[i];
+            if point.x < low.x - ENTITY_RADIUS || point.x > high.x + ENTITY_RADIUS ||
+                point.y < low.y - ENTITY_RADIUS || point.y > high.y + ENTITY_RADIUS {
+                return true;
+            }
+            let obstacle = &obstacles[i];
+            let near_edge = (0..obstacle.num_sides()).any(|j| {
+                let edge = Segment2 { p1: obstacle.get(j), p2: obstacle.get((j + 1) % obstacle.num_sides()) };
+                edge.distance_from_segment_to_point_squared(point) < ENTITY_RADIUS * ENTITY_RADIUS
+            });
+            !near_edge && !obstacle.contains_point(point)
+        })
+    };
+
+    let open: Vec<bool> = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .map(|(column, row)| is_open(cell_center(column, row)))
+        .collect();
+
+    // Flood fill from the first open cell, which is on the ring road
+    let start = open.iter().position(|cell| *cell);
+    let start = match start {
+        Some(start) => start,
+        None => return vec![map.camera_min],
+    };
+    let mut reached = vec![false; open.len()];
+    let mut stack = vec![start];
+    reached[start] = true;
+    while let Some(cell) = stack.pop() {
+        let (column, row) = (cell % columns, cell / columns);
+        let mut neighbours = vec!();
+        if column > 0 { neighbours.push(cell - 1); }
+        if column + 1 < columns { neighbours.push(cell + 1); }
+        if row > 0 { neighbours.push(cell - columns); }
+        if row + 1 < rows { neighbours.push(cell + columns); }
+
+        for neighbour in neighbours {
+            if open[neighbour] && !reached[neighbour] {
+                reached[neighbour] = true;
+                stack.push(neighbour);
+            }
+        }
+    }
+
+    let cut_off: Vec<Vector2> = (0..open.len())
+        .filter(|cell| open[*cell] && !reached[*cell])
+        .map(|cell| cell_center(cell % columns, cell / columns))
+        .collect();
+    if !cut_off.is_empty() {
+        return cut_off;
+    }
+
+    // Cops have to be able to find their way to everywhere that matters
+    let nav_graph = NavGraph::new(&obstacles, ENTITY_RADIUS);
+    let origin = cell_center(start % columns, start / columns);
+    map.plazas.iter().map(|plaza| plaza.center)
+        .chain(map.extraction_points.iter().map(|extraction_point| extraction_point.center))
+        .filter(|point| nav_graph.find_path(origin, *point).is_none())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_cities_are_connected() {
+        for seed in 0..8 {
+            let map = generate_city(seed);
+            assert!(is_connected(&map));
+            assert_eq!(validate_map(&map), Ok(()));
+        }
+    }
+
+    #[test]
+    fn footprints_are_counterclockwise_and_fit_their_lot() {
+        let mut rng = XorShiftRng::seed_from_u64(0);
+        let (min, max) = (vector2(0.0, 0.0), vector2(12.0, 8.0));
+        for _ in 0..20 {
+            for footprint in FOOTPRINTS.iter() {
+                let polygon = footprint.polygon(min, max, &mut rng);
+                assert!(polygon.is_counterclockwise());
+                assert!(polygon.area() <= 96.0 + 1e-9);
+                assert!(polygon.0.iter().all(|v| min.x <= v.x && v.x <= max.x && min.y <= v.y && v.y <= max.y));
+            }
+        }
+    }
+
+    #[test]
+    fn buildings_closing_off_a_yard_are_knocked_down() {
+        let mut rng = XorShiftRng::seed_from_u64(0);
+        let mut map = generate_city_layout(&mut rng);
+        map.buildings.clear();
+
+        // Four walls round a yard in the middle of the city, with no way in
+        let (min, max) = (vector2(40.0, 40.0), vector2(50.0, 50.0));
+        map.buildings.push((wall(min, vector2(max.x, min.y + 1.0)), 0));
+        map.buildings.push((wall(vector2(max.x - 1.0, min.y), max), 0));
+        map.buildings.push((wall(vector2(min.x, max.y - 1.0), max), 0));
+        map.buildings.push((wall(min, vector2(min.x + 1.0, max.y)), 0));
+        assert!(!is_connected(&map));
+
+        remove_blocking_buildings(&mut map);
+        assert!(is_connected(&map));
+        assert_eq!(map.buildings.len(), 3);
+    }
+}
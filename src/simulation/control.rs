@@ -6,8 +6,11 @@ use crate::core::geo::segment2::*;
 use crate::core::geo::polygon::*;
 use crate::simulation::game_state::GameState;
 use crate::simulation::state::MoveMode;
-use crate::simulation::ai::pathfinding::*;
 use crate::simulation::barricade::*;
+use crate::simulation::fog_of_war::*;
+use crate::simulation::ammo_crate::*;
+use crate::simulation::weapon::swap_weapons;
+use crate::simulation::ai::nav_graph::NAV_CLEARANCE_MULTIPLIER;
 
 use glium_sdl2::SDL2Facade;
 use sdl2::event::Event;
@@ -27,7 +30,8 @@ pub struct Control {
     pub drag_vertex_end: Vector2,
     pub last_click_time: Instant,
     pub last_right_click_time: Instant,
-    pub building_mode: bool
+    pub building_mode: bool,
+    pub show_vision_cones: bool
 }
 
 impl Control {
@@ -42,7 +46,8 @@ impl Control {
             drag_vertex_end: Vector2::zero(),
             last_click_time: Instant::now(),
             last_right_click_time: Instant::now(),
-            building_mode: false
+            building_mode: false,
+            show_vision_cones: false
         }
     }
 
@@ -152,7 +157,7 @@ impl Control {
                 }
 
                 // Offset waypoint to be outside the closest edge
-                let offset = (distance_squared.sqrt() + ENTITY_RADIUS * 1.1) * normal;
+                let offset = (distance_squared.sqrt() + ENTITY_RADIUS * NAV_CLEARANCE_MULTIPLIER) * normal;
                 m_pos.x = m_pos.x + offset.x;
                 m_pos.y = m_pos.y + offset.y;
 
@@ -164,7 +169,7 @@ impl Control {
 
         // Check if any zombie is within the click
         for i in 0..simulation.entities.len() {
-            if simulation.entities[i].is_zombie() {
+            if simulation.entities[i].is_zombie() && is_visible_to_player(simulation, i) {
                 let entity_pos = simulation.entities[i].position;
                 if is_click_on_entity(entity_pos, m_pos) {
                     zombie_index = Some(i);
@@ -188,12 +193,7 @@ impl Control {
                     // Make the cop stop what they are doing
                     state_stack.clear();
 
-                    let path = find_path(
-                        *position,
-                        m_pos,
-                        &simulation.buildings,
-                        &simulation.building_outlines,
-                        &simulation.barricades);
+                    let path = simulation.navigation.find_path(*position, m_pos, ENTITY_RADIUS);
 
                     // If no zombie clicked, issue regular move order, else issue special attack order
                     state_stack.push(match zombie_index {
@@ -218,6 +218,47 @@ impl Control {
         }
     }
 
+    // Send selected police to restock at the nearest police station or ammo crate
+    pub fn issue_resupply_order(&mut self, simulation: &mut State) {
+        for i in &simulation.selection {
+
+            let Entity {position, dead_or_alive, ..} = &mut simulation.entities[*i];
+
+            match dead_or_alive {
+                DeadOrAlive::Alive {
+                    zombie_or_human: ZombieOrHuman::Human {
+                        human: Human::Cop { state_stack, .. },
+                        ..
+                    },
+                    ..
+                } => {
+                    match nearest_resupply_point(*position, &simulation.ammo_crates, &simulation.police_stations) {
+                        None => (),
+                        Some(waypoint) => {
+                            // Make the cop stop what they are doing
+                            state_stack.clear();
+
+                            let path = simulation.navigation.find_path(*position, waypoint, ENTITY_RADIUS);
+
+                            state_stack.push(CopState::Resupplying { waypoint, path });
+                        }
+                    }
+                }
+                _ => ()
+            }
+        }
+    }
+
+    // Have selected police swap the weapon in hand for the one they have holstered
+    pub fn issue_swap_weapons_order(&mut self, simulation: &mut State) {
+        for i in &simulation.selection {
+            match &mut simulation.entities[*i].dead_or_alive {
+                DeadOrAlive::Alive { zombie_or_human: ZombieOrHuman::Human { human, .. }, .. } => swap_weapons(human),
+                _ => ()
+            }
+        }
+    }
+
     pub fn update_drag_start(&mut self, new_drag_start: Vector2, window: &SDL2Facade) {
         let drag_start_proj = &mut Vector2{ x : new_drag_start.x, y : new_drag_start.y};
         self.drag_start_mouse_coord.x = new_drag_start.x;
@@ -267,6 +308,21 @@ impl Control {
                     Keycode::B => {
                         self.building_mode = !self.building_mode;
                     },
+                    Keycode::R => {
+                        self.issue_resupply_order(state);
+                    },
+                    Keycode::X => {
+                        self.issue_swap_weapons_order(state);
+                    },
+                    Keycode::C => {
+                        self.show_vision_cones = !self.show_vision_cones;
+                    },
+                    Keycode::V => {
+                        state.fog_of_war = match state.fog_of_war {
+                            None => Some(FogOfWar::new(&state.buildings)),
+                            Some(_) => None
+                        };
+                    },
                     _ => ()
                 }
             }
@@ -320,7 +376,10 @@ impl Control {
 
                                 if barricade_valid(start, end, state) {
                                     state.money = state.money - barricade_cost(start, end);
-                                    state.barricades.push(Barricade::new(start, end));
+                                    let mut barricade = Barricade::new(start, end);
+                                    barricade.health = state.upgrades.barricade_health();
+                                    state.barricades.push(barricade);
+                                    state.update_barricades();
                                 }
                             } else {
                                 // Select one police if delta of drag is too small, else select all police in drag
@@ -360,6 +419,21 @@ impl Control {
 
                             if icon_rect.contains_point(gui_mouse_pos) {
                                 self.building_mode = !self.building_mode;
+                            } else if self.building_mode {
+                                // Barricades are dragged out, and crates need shift held so a
+                                // stray click doesn't spend money
+                                if !self.shift_pressed {
+                                    return;
+                                }
+
+                                let mut position = mouse_pos;
+                                translate_mouse_to_camera(&mut position, window.window().size());
+                                translate_camera_to_world(&mut position, camera_frame);
+
+                                if ammo_crate_valid(position, state) {
+                                    state.money = state.money - AMMO_CRATE_COST;
+                                    state.ammo_crates.push(AmmoCrate::new(position));
+                                }
                             } else {
                                 let current_time = Instant::now();
                                 let duration = current_time.duration_since(self.last_click_time);
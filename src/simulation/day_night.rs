@@ -0,0 +1,69 @@
+use crate::core::scalar::*;
+
+pub const HOURS_PER_DAY: Scalar = 24.0;
+
+pub const DAY_NIGHT_DEFAULT_STARTING_HOUR: Scalar = 17.0;
+// Real seconds for a full day to pass
+pub const DAY_NIGHT_DEFAULT_CYCLE_LENGTH: Scalar = 480.0;
+
+// Dusk and dawn each take this many hours to go between fully light and fully dark
+const TWILIGHT_HOURS: Scalar = 2.0;
+const DUSK_HOUR: Scalar = 19.0;
+const DAWN_HOUR: Scalar = 6.0;
+
+// How much is lost at the darkest point of the night
+const NIGHT_SIGHT_REDUCTION: Scalar = 0.5;
+const NIGHT_ACCURACY_PENALTY: Scalar = 1.0;
+const NIGHT_ZOMBIE_ACTIVITY_BONUS: Scalar = 0.4;
+
+#[derive(Copy, Clone, Debug)]
+pub struct DayNightCycle {
+    // Current hour of the day, in [0, 24)
+    pub hour: Scalar,
+    pub cycle_length: Scalar,
+}
+
+impl DayNightCycle {
+    pub fn new(starting_hour: Scalar, cycle_length: Scalar) -> DayNightCycle {
+        DayNightCycle {
+            hour: starting_hour % HOURS_PER_DAY,
+            cycle_length,
+        }
+    }
+
+    pub fn advance(&mut self, dt: Scalar) {
+        self.hour = (self.hour + dt * HOURS_PER_DAY / self.cycle_length) % HOURS_PER_DAY;
+    }
+
+    // 0 in full daylight, 1 in the dead of night
+    pub fn darkness(&self) -> Scalar {
+        if self.hour >= DUSK_HOUR + TWILIGHT_HOURS || self.hour < DAWN_HOUR - TWILIGHT_HOURS {
+            1.0
+        } else if self.hour >= DUSK_HOUR {
+            (self.hour - DUSK_HOUR) / TWILIGHT_HOURS
+        } else if self.hour < DAWN_HOUR {
+            (DAWN_HOUR - self.hour) / TWILIGHT_HOURS
+        } else {
+            0.0
+        }
+    }
+
+    pub fn sight_radius(&self, daylight_radius: Scalar) -> Scalar {
+        daylight_radius * (1.0 - NIGHT_SIGHT_REDUCTION * self.darkness())
+    }
+
+    pub fn sight_radius_squared(&self, daylight_radius: Scalar) -> Scalar {
+        let radius = self.sight_radius(daylight_radius);
+        radius * radius
+    }
+
+    // Multiplier on the spread of gunshots
+    pub fn accuracy_penalty(&self) -> Scalar {
+        1.0 + NIGHT_ACCURACY_PENALTY * self.darkness()
+    }
+
+    // Multiplier on how hard zombies move
+    pub fn zombie_activity(&self) -> Scalar {
+        1.0 + NIGHT_ZOMBIE_ACTIVITY_BONUS * self.darkness()
+    }
+}
@@ -0,0 +1,345 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::core::geo::polygon::*;
+use crate::core::geo::boolean::{intersection, union_all};
+use crate::simulation::map::*;
+use crate::simulation::state::ENTITY_RADIUS;
+use crate::simulation::ai::pathfinding::find_path;
+
+pub const EDITOR_MAP_PATH: &str = "assets/maps/custom.txt";
+
+// Everything placed in the editor lands on this grid
+pub const EDITOR_GRID_SIZE: Scalar = 0.5;
+// How close a click has to be to a vertex to grab it
+pub const EDITOR_VERTEX_GRAB_RADIUS: Scalar = 1.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EditorTool {
+    Select,
+    DrawBuilding,
+    PaintSpawn(SpawnKind),
+    PreviewPath,
+}
+
+impl EditorTool {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EditorTool::Select => "Select",
+            EditorTool::DrawBuilding => "Draw building",
+            EditorTool::PaintSpawn(SpawnKind::Civilian) => "Paint civilian spawn",
+            EditorTool::PaintSpawn(SpawnKind::Cop) => "Paint cop spawn",
+            EditorTool::PaintSpawn(SpawnKind::Infected) => "Paint infected spawn",
+            EditorTool::PreviewPath => "Preview path",
+        }
+    }
+
+    // Order the tools are cycled through in
+    pub fn next(&self) -> EditorTool {
+        match self {
+            EditorTool::Select => EditorTool::DrawBuilding,
+            EditorTool::DrawBuilding => EditorTool::PaintSpawn(SpawnKind::Civilian),
+            EditorTool::PaintSpawn(SpawnKind::Civilian) => EditorTool::PaintSpawn(SpawnKind::Cop),
+            EditorTool::PaintSpawn(SpawnKind::Cop) => EditorTool::PaintSpawn(SpawnKind::Infected),
+            EditorTool::PaintSpawn(SpawnKind::Infected) => EditorTool::PreviewPath,
+            EditorTool::PreviewPath => EditorTool::Select,
+        }
+    }
+}
+
+// What the left mouse button is currently dragging
+#[derive(Copy, Clone, Debug)]
+enum EditorDrag {
+    Nothing,
+    Building { index: usize, last_position: Vector2 },
+    Vertex { index: usize, vertex: usize },
+    SpawnZone { start: Vector2 },
+}
+
+#[derive(Clone)]
+pub struct Editor {
+    pub map: Map,
+    pub tool: EditorTool,
+    // Type given to new buildings
+    pub building_type: u32,
+    pub selected: Option<usize>,
+    // Vertices of the building being drawn
+    pub draft: Vec<Vector2>,
+    // Spawn zone being painted, from where the drag started to the cursor
+    pub draft_zone: Option<(Vector2, Vector2)>,
+    pub path_start: Option<Vector2>,
+    pub path_end: Option<Vector2>,
+    pub path: Option<Vec<Vector2>>,
+    // Last thing that happened, shown to the user
+    pub status: String,
+    drag: EditorDrag,
+}
+
+pub fn snap_to_grid(position: Vector2) -> Vector2 {
+    vector2((position.x / EDITOR_GRID_SIZE).round() * EDITOR_GRID_SIZE,
+            (position.y / EDITOR_GRID_SIZE).round() * EDITOR_GRID_SIZE)
+}
+
+fn zone_contains_point(zone: &SpawnZone, point: Vector2) -> bool {
+    zone.min.x <= point.x && point.x <= zone.max.x && zone.min.y <= point.y && point.y <= zone.max.y
+}
+
+impl Editor {
+    pub fn new(map: Map) -> Editor {
+        Editor {
+            map,
+            tool: EditorTool::Select,
+            building_type: 0,
+            selected: None,
+            draft: vec!(),
+            draft_zone: None,
+            path_start: None,
+            path_end: None,
+            path: None,
+            status: String::new(),
+            drag: EditorDrag::Nothing,
+        }
+    }
+
+    // Pick up the custom map if there is one, otherwise start from the default city
+    pub fn load() -> Editor {
+        match load_map(EDITOR_MAP_PATH).or_else(|_| load_map(DEFAULT_MAP_PATH)) {
+            Ok(map) => Editor::new(map),
+            Err(e) => panic!("Failed to load map: {}", e),
+        }
+    }
+
+    pub fn set_tool(&mut self, tool: EditorTool) {
+        self.cancel();
+        self.tool = tool;
+        self.status = tool.name().to_string();
+    }
+
+    // Applies to the selected building too, if there is one
+    pub fn set_building_type(&mut self, building_type: u32) {
+        if building_type >= MAP_BUILDING_TYPE_COUNT {
+            return;
+        }
+        self.building_type = building_type;
+        if let Some(index) = self.selected {
+            self.map.buildings[index].1 = building_type;
+        }
+        self.status = format!("Building type {}", building_type);
+    }
+
+    // Drop whatever is half finished
+    pub fn cancel(&mut self) {
+        self.draft.clear();
+        self.draft_zone = None;
+        self.drag = EditorDrag::Nothing;
+    }
+
+    // The topmost building under the point
+    pub fn building_at(&self, position: Vector2) -> Option<usize> {
+        (0..self.map.buildings.len()).rev()
+            .find(|i| self.map.buildings[*i].0.contains_point(position))
+    }
+
+    fn vertex_at(&self, index: usize, position: Vector2) -> Option<usize> {
+        let building = &self.map.buildings[index].0;
+        (0..building.num_sides())
+            .find(|j| (building.get(*j) - position).length_squared() < EDITOR_VERTEX_GRAB_RADIUS * EDITOR_VERTEX_GRAB_RADIUS)
+    }
+
+    pub fn mouse_down(&mut self, position: Vector2) {
+        let snapped = snap_to_grid(position);
+
+        match self.tool {
+            EditorTool::Select => {
+                // Vertices of the selected building take priority so its corners can be grabbed
+                // from outside the building
+                if let Some(index) = self.selected {
+                    if let Some(vertex) = self.vertex_at(index, position) {
+                        self.drag = EditorDrag::Vertex { index, vertex };
+                        return;
+                    }
+                }
+
+                self.selected = self.building_at(position);
+                if let Some(index) = self.selected {
+                    self.building_type = self.map.buildings[index].1;
+                    self.drag = EditorDrag::Building { index, last_position: snapped };
+                }
+            }
+            EditorTool::DrawBuilding => self.draft.push(snapped),
+            EditorTool::PaintSpawn(_) => {
+                self.drag = EditorDrag::SpawnZone { start: snapped };
+                self.draft_zone = Some((snapped, snapped));
+            }
+            EditorTool::PreviewPath => {
+                // Every other click starts a new path
+                if self.path_start.is_none() || self.path_end.is_some() {
+                    self.path_start = Some(position);
+                    self.path_end = None;
+                    self.path = None;
+                } else {
+                    self.path_end = Some(position);
+                    self.preview_path();
+                }
+            }
+        }
+    }
+
+    pub fn mouse_move(&mut self, position: Vector2) {
+        let snapped = snap_to_grid(position);
+
+        match self.drag {
+            EditorDrag::Nothing => (),
+            EditorDrag::Building { index, last_position } => {
+                let delta = snapped - last_position;
+                let building = &mut self.map.buildings[index].0;
+                for vertex in building.0.iter_mut() {
+                    *vertex += delta;
+                }
+                self.drag = EditorDrag::Building { index, last_position: snapped };
+            }
+            EditorDrag::Vertex { index, vertex } => {
+                // Don't let the building be squashed flat, it couldn't be drawn
+                let mut reshaped = self.map.buildings[index].0.clone();
+                reshaped.0[vertex] = snapped;
+                if has_area(&reshaped) {
+                    self.map.buildings[index].0 = reshaped;
+                }
+            }
+            EditorDrag::SpawnZone { start } => self.draft_zone = Some((start, snapped)),
+        }
+    }
+
+    pub fn mouse_up(&mut self, position: Vector2) {
+        self.mouse_move(position);
+
+        match self.drag {
+            EditorDrag::SpawnZone { start } => {
+                let end = snap_to_grid(position);
+                let min = vector2(start.x.min(end.x), start.y.min(end.y));
+                let max = vector2(start.x.max(end.x), start.y.max(end.y));
+
+                if let EditorTool::PaintSpawn(kind) = self.tool {
+                    if max.x > min.x && max.y > min.y {
+                        self.map.spawn_zones.push(SpawnZone { kind, min, max });
+                    }
+                }
+                self.draft_zone = None;
+            }
+            EditorDrag::Building { .. } | EditorDrag::Vertex { .. } => self.refresh_path(),
+            EditorDrag::Nothing => (),
+        }
+
+        self.drag = EditorDrag::Nothing;
+    }
+
+    pub fn right_click(&mut self, position: Vector2) {
+        match self.tool {
+            EditorTool::Select => self.selected = None,
+            EditorTool::DrawBuilding => self.finish_building(),
+            // Erase the zone under the cursor
+            EditorTool::PaintSpawn(kind) => {
+                let zone = (0..self.map.spawn_zones.len()).rev()
+                    .find(|i| self.map.spawn_zones[*i].kind == kind && zone_contains_point(&self.map.spawn_zones[*i], position));
+                if let Some(i) = zone {
+                    self.map.spawn_zones.remove(i);
+                }
+            }
+            EditorTool::PreviewPath => {
+                self.path_start = None;
+                self.path_end = None;
+                self.path = None;
+            }
+        }
+    }
+
+    // Turn the draft into a building, if it has enough vertices to be one. Buildings it overlaps
+    // are merged with it, since overlapping walls can't tell inside from out
+    pub fn finish_building(&mut self) {
+        let building = Polygon(self.draft.clone());
+        self.draft.clear();
+
+        if !has_area(&building) {
+            self.status = "A building needs at least three vertices".to_string();
+            return;
+        }
+
+        let mut merged = vec![building];
+        let mut i = 0;
+        while i < self.map.buildings.len() {
+            if intersection(&merged[0], &self.map.buildings[i].0).is_empty() {
+                i += 1;
+            } else {
+                merged.push(self.map.buildings.remove(i).0);
+            }
+        }
+
+        // Any courtyard closed off between them is filled in
+        let outlines: Vec<Polygon> = union_all(&merged).into_iter().filter(|outline| outline.is_counterclockwise()).collect();
+        if outlines.is_empty() {
+            self.selected = None;
+            self.status = "A building has to enclose some space".to_string();
+            return;
+        }
+
+        for outline in outlines {
+            self.map.buildings.push((outline, self.building_type));
+        }
+        self.selected = Some(self.map.buildings.len() - 1);
+        self.status = match merged.len() {
+            1 => "Building placed".to_string(),
+            count => format!("Building merged with {} others", count - 1),
+        };
+        self.refresh_path();
+    }
+
+    pub fn delete_selected(&mut self) {
+        if let Some(index) = self.selected {
+            self.map.buildings.remove(index);
+            self.selected = None;
+            self.drag = EditorDrag::Nothing;
+            self.status = "Building deleted".to_string();
+            self.refresh_path();
+        }
+    }
+
+    // Run the same pathfinding the cops use between the two clicked points
+    pub fn preview_path(&mut self) {
+        let (start, end) = match (self.path_start, self.path_end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return,
+        };
+
+        let mut obstacles: Vec<Polygon> = self.map.buildings.iter().map(|(building, _)| building.clone()).collect();
+        obstacles.extend(self.map.boundaries.iter().cloned());
+        self.path = find_path(start, end, &obstacles, &vec!(), ENTITY_RADIUS).map(|path| path.to_vec());
+        self.status = match &self.path {
+            Some(_) => "Path found".to_string(),
+            None => "No path between those points".to_string(),
+        };
+    }
+
+    // Keep the preview up to date as the buildings change
+    fn refresh_path(&mut self) {
+        if self.path_end.is_some() {
+            self.preview_path();
+        }
+    }
+
+    pub fn save(&mut self) {
+        self.status = match save_map(&self.map, EDITOR_MAP_PATH) {
+            Ok(()) => format!("Saved to {}", EDITOR_MAP_PATH),
+            Err(e) => format!("Can't save: {}", e),
+        };
+    }
+
+    // The map has to be playable before it can be test played
+    pub fn playable_map(&mut self) -> Option<Map> {
+        match validate_map(&self.map) {
+            Ok(()) => Some(self.map.clone()),
+            Err(e) => {
+                self.status = format!("Can't play: {}", e);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::core::geo::polygon::*;
+
+use crate::simulation::state::*;
+
+// Side length of the square cells that the map is divided into for exploration
+pub const FOG_CELL_SIZE: Scalar = 4.0;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Visibility {
+    // Never seen by a cop: nothing is shown
+    Unexplored,
+    // Seen before but not right now: buildings are shown, units are not
+    Explored,
+    // Currently seen by at least one living cop
+    Visible,
+}
+
+#[derive(Clone)]
+pub struct FogOfWar {
+    pub min: Vector2,
+    pub columns: usize,
+    pub rows: usize,
+    pub cells: Vec<Visibility>,
+    pub visible_entities: Vec<bool>,
+}
+
+impl FogOfWar {
+    // Cover the area spanned by the buildings, which includes the world boundary
+    pub fn new(buildings: &Vec<Polygon>) -> FogOfWar {
+        let mut min = Vector2 { x: INFINITY, y: INFINITY };
+        let mut max = Vector2 { x: NEG_INFINITY, y: NEG_INFINITY };
+
+        for building in buildings {
+            let (building_min, building_max) = building.bounding_box();
+            min.x = min.x.min(building_min.x);
+            min.y = min.y.min(building_min.y);
+            max.x = max.x.max(building_max.x);
+            max.y = max.y.max(building_max.y);
+        }
+
+        let columns = ((max.x - min.x) / FOG_CELL_SIZE).ceil() as usize;
+        let rows = ((max.y - min.y) / FOG_CELL_SIZE).ceil() as usize;
+
+        FogOfWar {
+            min,
+            columns,
+            rows,
+            cells: vec![Visibility::Unexplored; columns * rows],
+            visible_entities: vec!(),
+        }
+    }
+
+    pub fn get(&self, column: usize, row: usize) -> Visibility {
+        self.cells[row * self.columns + column]
+    }
+
+    // Get the bottom left and top right corners of a cell in world coordinates
+    pub fn cell_bounds(&self, column: usize, row: usize) -> (Vector2, Vector2) {
+        let bottom_left = self.min + FOG_CELL_SIZE * vector2(column as Scalar, row as Scalar);
+        (bottom_left, bottom_left + vector2(FOG_CELL_SIZE, FOG_CELL_SIZE))
+    }
+
+    pub fn is_entity_visible(&self, index: usize) -> bool {
+        // Entities that haven't been checked yet are shown until the next update
+        index >= self.visible_entities.len() || self.visible_entities[index]
+    }
+
+    // Recompute what the living cops can currently see
+    pub fn update(&mut self, entities: &Vec<Entity>, buildings: &Vec<Polygon>, sight_radius: Scalar) {
+        for cell in &mut self.cells {
+            if *cell == Visibility::Visible {
+                *cell = Visibility::Explored;
+            }
+        }
+
+        // What each cop can see is worked out once, then each cell and entity near them is
+        // looked up in it
+        let views: Vec<VisibilityRegion> = entities.iter()
+            .filter(|e| e.is_cop())
+            .map(|e| visibility_region(e.position, sight_radius, buildings))
+            .collect();
+
+        let sight_radius_squared = sight_radius * sight_radius;
+        let cells_in_radius = (sight_radius / FOG_CELL_SIZE).ceil() as isize;
+
+        for view in &views {
+            let offset = (view.viewpoint - self.min) / FOG_CELL_SIZE;
+            let center_column = offset.x.floor() as isize;
+            let center_row = offset.y.floor() as isize;
+
+            for row in (center_row - cells_in_radius)..(center_row + cells_in_radius + 1) {
+                for column in (center_column - cells_in_radius)..(center_column + cells_in_radius + 1) {
+                    if row < 0 || column < 0 || row >= self.rows as isize || column >= self.columns as isize {
+                        continue;
+                    }
+
+                    let index = row as usize * self.columns + column as usize;
+                    if self.cells[index] == Visibility::Visible {
+                        continue;
+                    }
+
+                    let (bottom_left, top_right) = self.cell_bounds(column as usize, row as usize);
+                    let center = 0.5 * (bottom_left + top_right);
+
+                    if (center - view.viewpoint).length_squared() < sight_radius_squared &&
+                        can_see_cell(view, buildings, center) {
+                        self.cells[index] = Visibility::Visible;
+                    }
+                }
+            }
+        }
+
+        self.visible_entities = entities.iter()
+            .map(|entity| entity.is_cop() || views.iter().any(|view| view.contains_point(entity.position)))
+            .collect();
+    }
+}
+
+// Whether the cell's centre can be seen, or for a cell inside a building whether the only thing
+// in the way is the wall of that building
+fn can_see_cell(view: &VisibilityRegion, buildings: &Vec<Polygon>, center: Vector2) -> bool {
+    if view.contains_point(center) {
+        return true;
+    }
+    match view.blocker_at((center - view.viewpoint).angle()) {
+        Some(index) => buildings[index].contains_point(center) && buildings[index].num_intersects(view.viewpoint, center) == 1,
+        None => false,
+    }
+}
+
+// Whether the player can currently see the entity at the given index
+pub fn is_visible_to_player(state: &State, index: usize) -> bool {
+    match &state.fog_of_war {
+        None => true,
+        Some(fog_of_war) => fog_of_war.is_entity_visible(index)
+    }
+}
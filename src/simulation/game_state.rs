@@ -36,6 +36,17 @@ pub struct GameState {
     pub easy: bool,
     pub medium: bool,
     pub hard: bool,
+
+    /* SURVIVAL MODE */
+    pub survival_game: bool,
+    pub survival: bool,
+
+    /* CAMPAIGN */
+    pub campaign: bool,
+    pub campaign_mission: Option<usize>,
+
+    /* MAP EDITOR */
+    pub map_editor: bool,
 }
 
 impl GameState {
@@ -67,6 +78,11 @@ impl GameState {
             easy: false,
             medium: false,
             hard: false,
+            survival_game: false,
+            survival: false,
+            campaign: false,
+            campaign_mission: None,
+            map_editor: false,
         }
     }
 
@@ -98,6 +114,11 @@ impl GameState {
             easy: easy_state,
             medium: medium_state,
             hard: hard_state,
+            survival_game: false,
+            survival: false,
+            campaign: false,
+            campaign_mission: None,
+            map_editor: false,
         }
     }
 
@@ -129,6 +150,11 @@ impl GameState {
             easy: false,
             medium: false,
             hard: false,
+            survival_game: false,
+            survival: false,
+            campaign: false,
+            campaign_mission: None,
+            map_editor: false,
         }
     }
 }
\ No newline at end of file
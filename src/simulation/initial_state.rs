@@ -3,14 +3,22 @@ use rand::*;
 use rand_xorshift::XorShiftRng;
 use std::collections::HashSet;
 
-use crate::core::geo::polygon::*;
 use crate::core::scalar::*;
 use crate::core::vector::*;
 use std::collections::HashMap;
 
 use super::state::*;
-
-pub fn initial_state(entity_count: u32, cop_entities: f64, infected_entities: f64, random_seed: u32) -> State {
+use super::ammo_crate::*;
+use super::weapon::HolsteredWeapon;
+use super::reinforcements::*;
+use super::upgrades::Upgrades;
+use super::day_night::*;
+use super::objectives::{Objective, ObjectiveType};
+use super::map::*;
+use super::ai::nav_graph::Navigation;
+use super::ai::flow_field::FlowField;
+
+pub fn initial_state(map: &Map, entity_count: u32, cop_entities: f64, infected_entities: f64, random_seed: u32) -> State {
     let portion_of_entities_cop = cop_entities as Scalar;
     let portion_of_entities_infected = infected_entities as Scalar;
     let entity_count_fp = entity_count as Scalar;
@@ -25,42 +33,61 @@ pub fn initial_state(entity_count: u32, cop_entities: f64, infected_entities: f6
     let mut state = State {
         entities: vec!(),
         buildings: vec!(),
-        building_outlines: vec!(),
         building_type: HashMap::new(),
+        navigation: Navigation::default(),
+        flow_field: FlowField::default(),
         barricades: vec!(),
+        ammo_crates: vec!(),
+        police_stations: map.police_stations.clone(),
         selection: HashSet::new(),
         projectiles: vec!(),
         rng: XorShiftRng::seed_from_u64(random_seed as u64),
-        money: 20
+        money: 20,
+        reinforcements: Reinforcements::new(REINFORCEMENT_CAP_DEFAULT, map.entry),
+        upgrades: Upgrades::default(),
+        flocking: FLOCKING_DEFAULT,
+        fog_of_war: None,
+        zombie_field_of_view: ZOMBIE_FIELD_OF_VIEW,
+        day_night: DayNightCycle::new(DAY_NIGHT_DEFAULT_STARTING_HOUR, DAY_NIGHT_DEFAULT_CYCLE_LENGTH),
+        survival: None,
+        objectives: vec![Objective::new(ObjectiveType::ClearInfection)],
+        casualties: 0,
     };
 
     let entities = &mut state.entities;
     let buildings = &mut state.buildings;
-    let building_outlines = &mut state.building_outlines;
     let building_type = &mut state.building_type;
 
-    // We want the spawn area to be proportional to the number of entities
-    // let side_length_of_spawn_area = 3.0 * entity_count_fp.sqrt();
-    let side_length_of_spawn_area = 100.0;
-
     for i in 0..entity_count {
         // TODO: need to optimize this later with housing units and two entities shouldn't be placed on same tile
-        let x = state.rng.gen_range(0.0, side_length_of_spawn_area);
-        let y = state.rng.gen_range(0.0, side_length_of_spawn_area);
+        let spawn_kind = if i < infected_count {
+            SpawnKind::Infected
+        } else if i < infected_count + cop_count {
+            SpawnKind::Cop
+        } else {
+            SpawnKind::Civilian
+        };
+        let position = map.random_spawn_point(spawn_kind, &mut state.rng);
         let facing_angle = state.rng.gen_range(0.0, 1 as Scalar);
-        let position = vector2(x, y);
         let velocity = Vector2::zero();
 
-        let infection = if i < infected_count {
+        let infection = if spawn_kind == SpawnKind::Infected {
             INFECTION_MIN.lerp(INFECTION_MAX, state.rng.gen_range(0.0, 1.0))
         }
         else {
             INFECTION_MIN
         };
 
-        let human = if infected_count <= i && i < infected_count + cop_count {
+        let human = if spawn_kind == SpawnKind::Cop {
             let cop_type = if i == infected_count { CopType::Soldier } else { CopType::Normal };
-            Human::Cop { cop_type, rounds_in_magazine: cop_type.magazine_capacity(), state_stack: vec!() }
+            Human::Cop {
+                cop_type,
+                weapon: cop_type.weapon(),
+                rounds_in_magazine: cop_type.weapon().magazine_capacity,
+                reserve_magazines: cop_type.weapon().starting_reserve_magazines,
+                holstered_weapon: HolsteredWeapon::new(cop_type.holstered_weapon()),
+                state_stack: vec!()
+            }
         }
         else {
             Human::Civilian {
@@ -81,158 +108,19 @@ pub fn initial_state(entity_count: u32, cop_entities: f64, infected_entities: f6
         entities.push(Entity { position, velocity, facing_angle, dead_or_alive });
     }
 
-    // Generate some buildings
-    let mut building_x = -20.0;
-    let mut building_y = 0.0;
-    let mut current_index: usize = 0;
-
-    // Neighbourhood on south side
-    while building_x < (side_length_of_spawn_area) + 20.0 {
-        building_y = -20.0;
-
-        while building_y < side_length_of_spawn_area / 2.0 - 20.0 {
-            buildings.push(Polygon(vec![
-                Vector2 { x: building_x, y: building_y },
-                Vector2 { x: building_x + 10.0, y: building_y },
-                Vector2 { x: building_x + 10.0, y: building_y + 10.0 },
-                Vector2 { x: building_x, y: building_y + 10.0 }
-            ]));
-            building_type.insert(current_index, 0);
-            current_index += 1;
-
-            building_y += 20.0;
-        }
-
-        building_x += 20.0;
+    // Buildings come first so their indices line up with their types, then the world boundary
+    for (building, t) in &map.buildings {
+        building_type.insert(buildings.len(), *t);
+        buildings.push(building.clone());
     }
+    buildings.extend(map.boundaries.iter().cloned());
 
-    // Southwest building in the square
-    buildings.push(Polygon(vec![
-        Vector2 { x: 10.0, y: building_y },
-        Vector2 { x: 30.0, y: building_y },
-        Vector2 { x: 30.0, y: building_y + 10.0 },
-        Vector2 { x: 20.0, y: building_y + 20.0},
-        Vector2 { x: 10.0, y: building_y + 20.0 }
-    ]));
-    building_type.insert(current_index, 1);
-    current_index += 1;
-
-    // Southeast building in the square
-    buildings.push(Polygon(vec![
-        Vector2 { x: 70.0, y: building_y },
-        Vector2 { x: 50.0, y: building_y },
-        Vector2 { x: 50.0, y: building_y + 10.0 },
-        Vector2 { x: 60.0, y: building_y + 20.0 },
-        Vector2 { x: 70.0, y: building_y + 20.0 }
-    ]));
-    building_type.insert(current_index, 2);
-    current_index += 1;
-
-    // Octagonal building in the middle of the square
-    buildings.push(Polygon(vec![
-        Vector2 { x: 32.5, y: building_y + 22.5 },
-        Vector2 { x: 32.5, y: building_y + 27.5 },
-        Vector2 { x: 37.5, y: building_y + 32.5 },
-        Vector2 { x: 42.5, y: building_y + 32.5 },
-        Vector2 { x: 47.5, y: building_y + 27.5 },
-        Vector2 { x: 47.5, y: building_y + 22.5 },
-        Vector2 { x: 42.5, y: building_y + 17.5 },
-        Vector2 { x: 37.5, y: building_y + 17.5 }
-    ]));
-    building_type.insert(current_index, 3);
-    current_index += 1;
-
-    buildings.push(Polygon(vec![
-        Vector2 { x: -20.0, y: building_y },
-        Vector2 { x: -20.0, y: building_y + 30.0 },
-        Vector2 { x: -5.0, y: building_y + 30.0 },
-        Vector2 { x: -5.0, y: building_y }
-    ]));
-    building_type.insert(current_index, 4);
-    current_index += 1;
-
-    buildings.push(Polygon(vec![
-        Vector2 { x: 95.0, y: building_y },
-        Vector2 { x: 95.0, y: building_y + 30.0 },
-        Vector2 { x: 80.0, y: building_y + 30.0 },
-        Vector2 { x: 80.0, y: building_y }
-    ]));
-    building_type.insert(current_index, 4);
-    current_index += 1;
-
-    building_y = building_y + 30.0;
-
-    // Northwest building in the square
-    buildings.push(Polygon(vec![
-        Vector2 { x: 10.0, y: building_y },
-        Vector2 { x: 10.0, y: building_y + 20.0 },
-        Vector2 { x: 30.0, y: building_y + 20.0 },
-        Vector2 { x: 30.0, y: building_y + 10.0 },
-        Vector2 { x: 20.0, y: building_y }
-    ]));
-    building_type.insert(current_index, 5);
-    current_index += 1;
-
-    // Northeast building in the square
-    buildings.push(Polygon(vec![
-        Vector2 { x: 70.0, y: building_y },
-        Vector2 { x: 70.0, y: building_y + 20.0 },
-        Vector2 { x: 50.0, y: building_y + 20.0 },
-        Vector2 { x: 50.0, y: building_y + 10.0 },
-        Vector2 { x: 60.0, y: building_y }
-    ]));
-    building_type.insert(current_index, 6);
-
-    // Generate World Boundary
-
-    let border_top_x = 0.0;
-    let border_top_y = 0.0;
-
-
-    // Lower Boundary
-    buildings.push(Polygon(vec![
-        Vector2 { x: border_top_x - 25.0, y: border_top_y - 24.5 },
-        Vector2 { x: border_top_x + 115.0, y: border_top_y - 24.5 },
-        Vector2 { x: border_top_x + 115.0, y: border_top_y - 25.0 },
-        Vector2 { x: border_top_x - 25.0, y: border_top_y - 25.0 }
-    ]));
-
-    // Right Boundary
-    buildings.push(Polygon(vec![
-        Vector2 { x: border_top_x + 114.5, y: border_top_y + 115.0 },
-        Vector2 { x: border_top_x + 115.0, y: border_top_y + 115.0 },
-        Vector2 { x: border_top_x + 114.5, y: border_top_y - 25.0 },
-        Vector2 { x: border_top_x + 115.0, y: border_top_y - 25.0 }
-    ]));
-    // Left Boundary
-    buildings.push(Polygon(vec![
-        Vector2 { x: border_top_x - 24.5, y: border_top_y + 115.0 },
-        Vector2 { x: border_top_x - 25.0, y: border_top_y + 115.0 },
-        Vector2 { x: border_top_x - 24.5, y: border_top_y - 25.0 },
-        Vector2 { x: border_top_x - 25.0, y: border_top_y - 25.0 }
-    ]));
-
-    // Upper Boundary
-    buildings.push(Polygon(vec![
-        Vector2 { x: border_top_x - 25.0, y: border_top_y + 115.0 },
-        Vector2 { x: border_top_x + 115.0, y: border_top_y + 115.0 },
-        Vector2 { x: border_top_x + 115.0, y: border_top_y + 114.5 },
-        Vector2 { x: border_top_x - 25.0, y: border_top_y + 114.5 }
-    ]));
-
-    // Generate outlines around all buildings for building A* pathfinding graphs
-    for i in 0..buildings.len() {
-        let mut outlines = vec!();
-        let norms = buildings[i].normals();
-
-        for j in 0..buildings[i].num_sides() {
-            let norm_sum = norms[j] + norms[if j < 1 { buildings[i].num_sides() - 1 } else { j - 1 }];
-            let offset = norm_sum * ENTITY_RADIUS * 1.1;
-            outlines.push(offset + buildings[i].get(j));
-        }
-
-        building_outlines.push(Polygon(outlines));
+    for position in &map.ammo_crates {
+        state.ammo_crates.push(AmmoCrate::new(*position));
     }
 
+    state.navigation = Navigation::new(&state.buildings);
+    state.flow_field = FlowField::new(&state.buildings);
+
     state
 }
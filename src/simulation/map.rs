@@ -0,0 +1,427 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::core::geo::polygon::*;
+
+use rand::Rng;
+
+use std::fs;
+use std::str::SplitWhitespace;
+
+pub const DEFAULT_MAP_PATH: &str = "assets/maps/default.txt";
+
+// Building types index into the building textures
+pub const MAP_BUILDING_TYPE_COUNT: u32 = 7;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpawnKind {
+    Civilian,
+    Cop,
+    Infected,
+}
+
+// Axis aligned area where entities of one kind start out
+#[derive(Copy, Clone, Debug)]
+pub struct SpawnZone {
+    pub kind: SpawnKind,
+    pub min: Vector2,
+    pub max: Vector2,
+}
+
+impl SpawnZone {
+    pub fn area(&self) -> Scalar {
+        (self.max.x - self.min.x) * (self.max.y - self.min.y)
+    }
+
+    pub fn random_point<R: Rng>(&self, rng: &mut R) -> Vector2 {
+        let x = rng.gen_range(self.min.x, self.max.x);
+        let y = rng.gen_range(self.min.y, self.max.y);
+        vector2(x, y)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ExtractionPoint {
+    pub center: Vector2,
+    pub radius: Scalar,
+}
+
+// Open square, where the hold area objective is placed
+#[derive(Copy, Clone, Debug)]
+pub struct Plaza {
+    pub center: Vector2,
+    pub radius: Scalar,
+}
+
+// Where survival waves come in at the edge of the map, and the rally point along a clear street
+// they head for
+#[derive(Copy, Clone, Debug)]
+pub struct WaveSpawn {
+    pub position: Vector2,
+    pub rally_point: Vector2,
+}
+
+#[derive(Clone, Debug)]
+pub struct Map {
+    // Each building with its type
+    pub buildings: Vec<(Polygon, u32)>,
+    pub boundaries: Vec<Polygon>,
+    pub spawn_zones: Vec<SpawnZone>,
+    pub extraction_points: Vec<ExtractionPoint>,
+    pub plazas: Vec<Plaza>,
+    pub ammo_crates: Vec<Vector2>,
+    pub police_stations: Vec<Vector2>,
+    pub wave_spawns: Vec<WaveSpawn>,
+    // Where reinforcements drive in
+    pub entry: Vector2,
+    pub camera_min: Vector2,
+    pub camera_max: Vector2,
+}
+
+impl Map {
+    pub fn new() -> Map {
+        Map {
+            buildings: vec!(),
+            boundaries: vec!(),
+            spawn_zones: vec!(),
+            extraction_points: vec!(),
+            plazas: vec!(),
+            ammo_crates: vec!(),
+            police_stations: vec!(),
+            wave_spawns: vec!(),
+            entry: Vector2::zero(),
+            camera_min: Vector2::zero(),
+            camera_max: Vector2::zero(),
+        }
+    }
+
+    pub fn spawn_zones_of_kind(&self, kind: SpawnKind) -> Vec<SpawnZone> {
+        self.spawn_zones.iter()
+            .filter(|zone| zone.kind == kind)
+            .cloned()
+            .collect()
+    }
+
+    // Pick a zone of the kind with a chance proportional to its area, so the spawn density is even
+    pub fn random_spawn_point<R: Rng>(&self, kind: SpawnKind, rng: &mut R) -> Vector2 {
+        let zones = self.spawn_zones_of_kind(kind);
+        if zones.len() == 1 {
+            return zones[0].random_point(rng);
+        }
+
+        let total_area: Scalar = zones.iter().map(|zone| zone.area()).sum();
+        let mut choice = rng.gen_range(0.0, total_area);
+        for zone in &zones {
+            if choice < zone.area() {
+                return zone.random_point(rng);
+            }
+            choice -= zone.area();
+        }
+        zones[zones.len() - 1].random_point(rng)
+    }
+}
+
+pub fn parse_number<T: std::str::FromStr>(words: &mut SplitWhitespace, line_number: usize) -> Result<T, String> {
+    match words.next() {
+        Some(word) => word.parse::<T>()
+            .map_err(|_| format!("line {}: expected a number but found \"{}\"", line_number, word)),
+        None => Err(format!("line {}: missing a number", line_number)),
+    }
+}
+
+pub fn parse_vector(words: &mut SplitWhitespace, line_number: usize) -> Result<Vector2, String> {
+    let x = parse_number(words, line_number)?;
+    let y = parse_number(words, line_number)?;
+    Ok(vector2(x, y))
+}
+
+// Read vertices up to the end of the line
+fn parse_polygon(words: &mut SplitWhitespace, line_number: usize) -> Result<Polygon, String> {
+    let numbers = words
+        .map(|word| word.parse::<Scalar>()
+            .map_err(|_| format!("line {}: expected a number but found \"{}\"", line_number, word)))
+        .collect::<Result<Vec<Scalar>, String>>()?;
+
+    if numbers.len() % 2 != 0 {
+        return Err(format!("line {}: vertex is missing its y coordinate", line_number));
+    }
+    if numbers.len() < 6 {
+        return Err(format!("line {}: polygon needs at least three vertices", line_number));
+    }
+
+    let polygon = Polygon(numbers.chunks(2).map(|xy| vector2(xy[0], xy[1])).collect());
+    if !has_area(&polygon) {
+        return Err(format!("line {}: polygon has no area", line_number));
+    }
+
+    Ok(polygon)
+}
+
+// Polygon::bounding_box panics on flat polygons, so they have to be caught before drawing
+pub fn has_area(polygon: &Polygon) -> bool {
+    let (min_x, max_x) = polygon.0.iter().fold((INFINITY, NEG_INFINITY), |(lo, hi), v| (lo.min(v.x), hi.max(v.x)));
+    let (min_y, max_y) = polygon.0.iter().fold((INFINITY, NEG_INFINITY), |(lo, hi), v| (lo.min(v.y), hi.max(v.y)));
+    polygon.num_sides() >= 3 && max_x > min_x && max_y > min_y
+}
+
+// Corners can be given in any order
+fn parse_box(words: &mut SplitWhitespace, line_number: usize) -> Result<(Vector2, Vector2), String> {
+    let a = parse_vector(words, line_number)?;
+    let b = parse_vector(words, line_number)?;
+    let min = vector2(a.x.min(b.x), a.y.min(b.y));
+    let max = vector2(a.x.max(b.x), a.y.max(b.y));
+
+    if max.x <= min.x || max.y <= min.y {
+        return Err(format!("line {}: area is empty", line_number));
+    }
+
+    Ok((min, max))
+}
+
+fn is_in_box(point: Vector2, min: Vector2, max: Vector2) -> bool {
+    min.x <= point.x && point.x <= max.x && min.y <= point.y && point.y <= max.y
+}
+
+// See assets/maps/default.txt for a description of the format
+pub fn parse_map(text: &str) -> Result<Map, String> {
+    let mut map = Map::new();
+    let mut has_camera = false;
+    let mut has_entry = false;
+
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        match words.next().unwrap() {
+            "building" => {
+                let building_type = parse_number(&mut words, line_number)?;
+                if building_type >= MAP_BUILDING_TYPE_COUNT {
+                    return Err(format!("line {}: unknown building type {}", line_number, building_type));
+                }
+                map.buildings.push((parse_polygon(&mut words, line_number)?, building_type));
+            }
+            "boundary" => map.boundaries.push(parse_polygon(&mut words, line_number)?),
+            "spawn" => {
+                let kind = match words.next() {
+                    Some("civilian") => SpawnKind::Civilian,
+                    Some("cop") => SpawnKind::Cop,
+                    Some("infected") => SpawnKind::Infected,
+                    Some(kind) => return Err(format!("line {}: unknown spawn kind \"{}\"", line_number, kind)),
+                    None => return Err(format!("line {}: missing spawn kind", line_number)),
+                };
+                let (min, max) = parse_box(&mut words, line_number)?;
+                map.spawn_zones.push(SpawnZone { kind, min, max });
+            }
+            "extraction" => {
+                let center = parse_vector(&mut words, line_number)?;
+                let radius = parse_number(&mut words, line_number)?;
+                if radius <= 0.0 {
+                    return Err(format!("line {}: extraction radius must be positive", line_number));
+                }
+                map.extraction_points.push(ExtractionPoint { center, radius });
+            }
+            "plaza" => {
+                let center = parse_vector(&mut words, line_number)?;
+                let radius = parse_number(&mut words, line_number)?;
+                if radius <= 0.0 {
+                    return Err(format!("line {}: plaza radius must be positive", line_number));
+                }
+                map.plazas.push(Plaza { center, radius });
+            }
+            "ammo_crate" => map.ammo_crates.push(parse_vector(&mut words, line_number)?),
+            "police_station" => map.police_stations.push(parse_vector(&mut words, line_number)?),
+            "wave_spawn" => {
+                let position = parse_vector(&mut words, line_number)?;
+                let rally_point = parse_vector(&mut words, line_number)?;
+                map.wave_spawns.push(WaveSpawn { position, rally_point });
+            }
+            "entry" => {
+                map.entry = parse_vector(&mut words, line_number)?;
+                has_entry = true;
+            }
+            "camera" => {
+                let (min, max) = parse_box(&mut words, line_number)?;
+                map.camera_min = min;
+                map.camera_max = max;
+                has_camera = true;
+            }
+            key => return Err(format!("line {}: unknown setting \"{}\"", line_number, key)),
+        }
+
+        if let Some(word) = words.next() {
+            return Err(format!("line {}: unexpected \"{}\"", line_number, word));
+        }
+    }
+
+    if !has_camera {
+        return Err("missing camera bounds".to_string());
+    }
+    if !has_entry {
+        return Err("missing entry point".to_string());
+    }
+
+    validate_map(&map)?;
+    Ok(map)
+}
+
+// Check the parts of the map that depend on each other
+pub fn validate_map(map: &Map) -> Result<(), String> {
+    // Every kind of entity needs somewhere to start
+    for (kind, name) in &[(SpawnKind::Civilian, "civilian"), (SpawnKind::Cop, "cop"), (SpawnKind::Infected, "infected")] {
+        if map.spawn_zones_of_kind(*kind).is_empty() {
+            return Err(format!("missing a {} spawn zone", name));
+        }
+    }
+
+    for zone in &map.spawn_zones {
+        if !is_in_box(zone.min, map.camera_min, map.camera_max) || !is_in_box(zone.max, map.camera_min, map.camera_max) {
+            return Err(format!("spawn zone from ({}, {}) to ({}, {}) is outside the camera bounds",
+                               zone.min.x, zone.min.y, zone.max.x, zone.max.y));
+        }
+    }
+
+    let blocked = |point: Vector2| map.buildings.iter().any(|(building, _)| building.contains_point(point));
+
+    if !is_in_box(map.entry, map.camera_min, map.camera_max) {
+        return Err(format!("entry point at ({}, {}) is outside the camera bounds", map.entry.x, map.entry.y));
+    }
+    if blocked(map.entry) {
+        return Err(format!("entry point at ({}, {}) is inside a building", map.entry.x, map.entry.y));
+    }
+
+    for extraction_point in &map.extraction_points {
+        if blocked(extraction_point.center) {
+            return Err(format!("extraction point at ({}, {}) is inside a building",
+                               extraction_point.center.x, extraction_point.center.y));
+        }
+    }
+
+    for plaza in &map.plazas {
+        if blocked(plaza.center) {
+            return Err(format!("plaza at ({}, {}) is inside a building", plaza.center.x, plaza.center.y));
+        }
+    }
+
+    for position in &map.ammo_crates {
+        if blocked(*position) {
+            return Err(format!("ammo crate at ({}, {}) is inside a building", position.x, position.y));
+        }
+    }
+
+    for position in &map.police_stations {
+        if blocked(*position) {
+            return Err(format!("police station at ({}, {}) is inside a building", position.x, position.y));
+        }
+    }
+
+    for wave_spawn in &map.wave_spawns {
+        if !is_in_box(wave_spawn.position, map.camera_min, map.camera_max) {
+            return Err(format!("wave spawn at ({}, {}) is outside the camera bounds", wave_spawn.position.x, wave_spawn.position.y));
+        }
+        if blocked(wave_spawn.position) {
+            return Err(format!("wave spawn at ({}, {}) is inside a building", wave_spawn.position.x, wave_spawn.position.y));
+        }
+        if blocked(wave_spawn.rally_point) {
+            return Err(format!("rally point at ({}, {}) is inside a building", wave_spawn.rally_point.x, wave_spawn.rally_point.y));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn load_map(path: &str) -> Result<Map, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse_map(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn polygon_to_text(polygon: &Polygon) -> String {
+    let vertices: Vec<String> = polygon.0.iter()
+        .map(|vertex| format!("{} {}", vertex.x, vertex.y))
+        .collect();
+    vertices.join("  ")
+}
+
+// Write the map back out in the same format it's loaded from
+pub fn map_to_text(map: &Map) -> String {
+    let mut lines = vec!();
+
+    for (building, building_type) in &map.buildings {
+        lines.push(format!("building {} {}", building_type, polygon_to_text(building)));
+    }
+    for boundary in &map.boundaries {
+        lines.push(format!("boundary {}", polygon_to_text(boundary)));
+    }
+    for zone in &map.spawn_zones {
+        let kind = match zone.kind {
+            SpawnKind::Civilian => "civilian",
+            SpawnKind::Cop => "cop",
+            SpawnKind::Infected => "infected",
+        };
+        lines.push(format!("spawn {} {} {} {} {}", kind, zone.min.x, zone.min.y, zone.max.x, zone.max.y));
+    }
+    for extraction_point in &map.extraction_points {
+        lines.push(format!("extraction {} {} {}", extraction_point.center.x, extraction_point.center.y, extraction_point.radius));
+    }
+    for plaza in &map.plazas {
+        lines.push(format!("plaza {} {} {}", plaza.center.x, plaza.center.y, plaza.radius));
+    }
+    for position in &map.ammo_crates {
+        lines.push(format!("ammo_crate {} {}", position.x, position.y));
+    }
+    for position in &map.police_stations {
+        lines.push(format!("police_station {} {}", position.x, position.y));
+    }
+    for wave_spawn in &map.wave_spawns {
+        lines.push(format!("wave_spawn {} {} {} {}", wave_spawn.position.x, wave_spawn.position.y,
+                           wave_spawn.rally_point.x, wave_spawn.rally_point.y));
+    }
+    lines.push(format!("entry {} {}", map.entry.x, map.entry.y));
+    lines.push(format!("camera {} {} {} {}", map.camera_min.x, map.camera_min.y, map.camera_max.x, map.camera_max.y));
+
+    lines.join("\n") + "\n"
+}
+
+// Refuse to save a map that couldn't be loaded again
+pub fn save_map(map: &Map, path: &str) -> Result<(), String> {
+    let text = map_to_text(map);
+    parse_map(&text)?;
+    fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_maps_load_the_same() {
+        let map = load_map(DEFAULT_MAP_PATH).unwrap();
+        let reloaded = parse_map(&map_to_text(&map)).unwrap();
+
+        assert_eq!(map_to_text(&reloaded), map_to_text(&map));
+        assert_eq!(reloaded.entry, map.entry);
+        assert_eq!(reloaded.wave_spawns.len(), map.wave_spawns.len());
+    }
+
+    #[test]
+    fn entry_and_wave_spawns_are_checked() {
+        let text = map_to_text(&load_map(DEFAULT_MAP_PATH).unwrap());
+        let without_entry: Vec<&str> = text.lines().filter(|line| !line.starts_with("entry")).collect();
+        assert_eq!(parse_map(&without_entry.join("\n")).err(), Some("missing entry point".to_string()));
+
+        // The first building of the default map covers (-15, -15)
+        let mut map = parse_map(&text).unwrap();
+        map.entry = vector2(-15.0, -15.0);
+        assert!(validate_map(&map).is_err());
+
+        let mut map = parse_map(&text).unwrap();
+        map.entry = vector2(200.0, 0.0);
+        assert!(validate_map(&map).is_err());
+
+        let mut map = parse_map(&text).unwrap();
+        map.wave_spawns[0].rally_point = vector2(-15.0, -15.0);
+        assert!(validate_map(&map).is_err());
+    }
+}
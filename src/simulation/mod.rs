@@ -5,3 +5,15 @@ pub mod control;
 pub mod ai;
 pub mod game_state;
 pub mod barricade;
+pub mod fog_of_war;
+pub mod ammo_crate;
+pub mod weapon;
+pub mod reinforcements;
+pub mod upgrades;
+pub mod day_night;
+pub mod survival;
+pub mod objectives;
+pub mod map;
+pub mod campaign;
+pub mod editor;
+pub mod city_generator;
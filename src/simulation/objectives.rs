@@ -0,0 +1,249 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::simulation::state::*;
+use crate::simulation::update::EntityCounts;
+
+use std::collections::HashSet;
+
+#[derive(Clone, Debug)]
+pub enum ObjectiveType {
+    // Kill every zombie and cure or kill every infected
+    ClearInfection,
+    // Get civilians into the extraction zone, each one counts once
+    Evacuate {
+        zone_center: Vector2,
+        zone_radius: Scalar,
+        required: usize,
+        evacuated: HashSet<usize>,
+    },
+    // Keep the entity at the given index alive and uninfected
+    ProtectVip {
+        vip_index: usize,
+    },
+    // Have cops in the area with no zombies in it for long enough
+    HoldArea {
+        center: Vector2,
+        radius: Scalar,
+        duration: Scalar,
+        time_held: Scalar,
+    },
+    // Lose no more than this many humans to death or infection
+    LimitCasualties {
+        max_casualties: u32,
+        casualties: u32,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ObjectiveStatus {
+    InProgress,
+    Passed,
+    Failed,
+}
+
+#[derive(Clone, Debug)]
+pub struct Objective {
+    pub objective_type: ObjectiveType,
+    pub status: ObjectiveStatus,
+}
+
+impl Objective {
+    pub fn new(objective_type: ObjectiveType) -> Objective {
+        Objective {
+            objective_type,
+            status: ObjectiveStatus::InProgress,
+        }
+    }
+
+    pub fn evacuate(zone_center: Vector2, zone_radius: Scalar, required: usize) -> Objective {
+        Objective::new(ObjectiveType::Evacuate { zone_center, zone_radius, required, evacuated: HashSet::new() })
+    }
+
+    pub fn hold_area(center: Vector2, radius: Scalar, duration: Scalar) -> Objective {
+        Objective::new(ObjectiveType::HoldArea { center, radius, duration, time_held: 0.0 })
+    }
+
+    pub fn limit_casualties(max_casualties: u32) -> Objective {
+        Objective::new(ObjectiveType::LimitCasualties { max_casualties, casualties: 0 })
+    }
+
+    // Objectives that can only be failed, and count as passed once everything else is done
+    pub fn is_ongoing(&self) -> bool {
+        match self.objective_type {
+            ObjectiveType::ProtectVip { .. } | ObjectiveType::LimitCasualties { .. } => true,
+            _ => false,
+        }
+    }
+
+    // Short description with the current progress, for the HUD and end screens
+    pub fn description(&self) -> String {
+        match &self.objective_type {
+            ObjectiveType::ClearInfection => "Eliminate the infection".to_string(),
+            ObjectiveType::Evacuate { required, evacuated, .. } =>
+                format!("Evacuate civilians ({}/{})", evacuated.len().min(*required), required),
+            ObjectiveType::ProtectVip { .. } => "Protect the VIP".to_string(),
+            ObjectiveType::HoldArea { duration, time_held, .. } =>
+                format!("Hold the area ({}/{}s)", time_held.min(*duration) as u32, *duration as u32),
+            ObjectiveType::LimitCasualties { max_casualties, casualties } =>
+                format!("Keep casualties low ({}/{})", casualties, max_casualties),
+        }
+    }
+}
+
+fn is_healthy_human(entity: &Entity) -> bool {
+    match &entity.dead_or_alive {
+        DeadOrAlive::Alive { zombie_or_human: ZombieOrHuman::Human { infection, .. }, .. } =>
+            *infection < INFECTION_EXPONENTIAL_GROWTH_THRESHOLD,
+        _ => false,
+    }
+}
+
+fn is_healthy_civilian(entity: &Entity) -> bool {
+    is_healthy_human(entity) && !entity.is_cop()
+}
+
+fn is_in_circle(position: Vector2, center: Vector2, radius: Scalar) -> bool {
+    (position - center).length_squared() < radius * radius
+}
+
+// Update progress on every objective that hasn't been decided yet
+pub fn update_objectives(dt: Scalar, state: &mut State, entity_counts: &EntityCounts) {
+    let entities = &state.entities;
+    let casualties = state.casualties;
+
+    for objective in &mut state.objectives {
+        if objective.status != ObjectiveStatus::InProgress {
+            continue;
+        }
+
+        objective.status = match &mut objective.objective_type {
+            ObjectiveType::ClearInfection => {
+                if entity_counts.infected == 0 && entity_counts.zombies == 0 {
+                    ObjectiveStatus::Passed
+                } else {
+                    ObjectiveStatus::InProgress
+                }
+            }
+            ObjectiveType::Evacuate { zone_center, zone_radius, required, evacuated } => {
+                for i in 0..entities.len() {
+                    if is_healthy_civilian(&entities[i]) && is_in_circle(entities[i].position, *zone_center, *zone_radius) {
+                        evacuated.insert(i);
+                    }
+                }
+
+                // Fail as soon as there aren't enough civilians left to make up the difference
+                let remaining = (0..entities.len())
+                    .filter(|i| !evacuated.contains(i) && is_healthy_civilian(&entities[*i]))
+                    .count();
+
+                if evacuated.len() >= *required {
+                    ObjectiveStatus::Passed
+                } else if evacuated.len() + remaining < *required {
+                    ObjectiveStatus::Failed
+                } else {
+                    ObjectiveStatus::InProgress
+                }
+            }
+            ObjectiveType::ProtectVip { vip_index } => {
+                if is_healthy_human(&entities[*vip_index]) {
+                    ObjectiveStatus::InProgress
+                } else {
+                    ObjectiveStatus::Failed
+                }
+            }
+            ObjectiveType::HoldArea { center, radius, duration, time_held } => {
+                let cops_present = entities.iter()
+                    .any(|e| e.is_cop() && is_in_circle(e.position, *center, *radius));
+                let zombies_present = entities.iter()
+                    .any(|e| e.is_zombie() && is_in_circle(e.position, *center, *radius));
+
+                if cops_present && !zombies_present {
+                    *time_held += dt;
+                }
+
+                if *time_held >= *duration {
+                    ObjectiveStatus::Passed
+                } else {
+                    ObjectiveStatus::InProgress
+                }
+            }
+            ObjectiveType::LimitCasualties { max_casualties, casualties: objective_casualties } => {
+                *objective_casualties = casualties;
+                if casualties > *max_casualties {
+                    ObjectiveStatus::Failed
+                } else {
+                    ObjectiveStatus::InProgress
+                }
+            }
+        };
+    }
+}
+
+fn infection(entity: &Entity) -> Scalar {
+    match &entity.dead_or_alive {
+        DeadOrAlive::Alive { zombie_or_human: ZombieOrHuman::Human { infection, .. }, .. } => *infection,
+        _ => INFECTION_MAX,
+    }
+}
+
+// The least infected of the healthy civilians is the VIP. The infected are spawned first, so
+// just taking the first civilian would nearly always pick someone already turning
+pub fn protect_vip(entities: &Vec<Entity>) -> Option<Objective> {
+    (0..entities.len())
+        .filter(|i| is_healthy_civilian(&entities[*i]))
+        .min_by(|a, b| infection(&entities[*a]).partial_cmp(&infection(&entities[*b])).unwrap())
+        .map(|vip_index| Objective::new(ObjectiveType::ProtectVip { vip_index }))
+}
+
+// Work out whether the scenario is over. The player wins once every objective that can be
+// completed has been, at which point the ongoing ones are settled as passed too
+pub fn resolve_objectives(objectives: &mut Vec<Objective>) -> ObjectiveStatus {
+    if objectives.iter().any(|objective| objective.status == ObjectiveStatus::Failed) {
+        return ObjectiveStatus::Failed;
+    }
+
+    let completed = objectives.iter()
+        .filter(|objective| !objective.is_ongoing())
+        .all(|objective| objective.status == ObjectiveStatus::Passed);
+
+    if !completed {
+        return ObjectiveStatus::InProgress;
+    }
+
+    for objective in objectives.iter_mut() {
+        objective.status = ObjectiveStatus::Passed;
+    }
+
+    ObjectiveStatus::Passed
+}
+
+// Anything left unfinished when the game is lost counts as failed
+pub fn fail_remaining_objectives(objectives: &mut Vec<Objective>) {
+    for objective in objectives.iter_mut() {
+        if objective.status == ObjectiveStatus::InProgress {
+            objective.status = ObjectiveStatus::Failed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::initial_state::initial_state;
+    use crate::simulation::map::{load_map, DEFAULT_MAP_PATH};
+
+    #[test]
+    fn vip_starts_healthy() {
+        let map = load_map(DEFAULT_MAP_PATH).unwrap();
+        for seed in 0..20 {
+            let state = initial_state(&map, 100, 0.1, 0.3, seed);
+            match protect_vip(&state.entities).unwrap().objective_type {
+                ObjectiveType::ProtectVip { vip_index } => {
+                    assert!(is_healthy_civilian(&state.entities[vip_index]), "seed {}", seed);
+                    assert_eq!(infection(&state.entities[vip_index]), INFECTION_MIN, "seed {}", seed);
+                }
+                _ => panic!("Should be a VIP objective")
+            }
+        }
+    }
+}
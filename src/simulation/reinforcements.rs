@@ -0,0 +1,167 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::simulation::state::*;
+use crate::simulation::weapon::HolsteredWeapon;
+
+use std::f64::consts::PI;
+
+pub const REINFORCEMENT_ARRIVAL_TIME: Scalar = 8.0;
+pub const REINFORCEMENT_CAP_DEFAULT: u32 = 6;
+
+// Gap between cops that arrive together, who line up side by side across the road
+pub const REINFORCEMENT_SPACING: Scalar = 1.5;
+
+#[derive(Copy, Clone, Debug)]
+pub struct IncomingCop {
+    pub cop_type: CopType,
+    pub arrival_time_remaining: Scalar,
+}
+
+#[derive(Clone, Debug)]
+pub struct Reinforcements {
+    pub incoming: Vec<IncomingCop>,
+    pub normal_cooldown: Scalar,
+    pub soldier_cooldown: Scalar,
+    // Number of cops recruited so far, which can't exceed the cap
+    pub recruited: u32,
+    pub cap: u32,
+    // Where the map has them arrive
+    pub entry_point: Vector2,
+}
+
+impl Reinforcements {
+    pub fn new(cap: u32, entry_point: Vector2) -> Reinforcements {
+        Reinforcements {
+            incoming: vec!(),
+            normal_cooldown: 0.0,
+            soldier_cooldown: 0.0,
+            recruited: 0,
+            cap,
+            entry_point,
+        }
+    }
+
+    pub fn cooldown(&self, cop_type: CopType) -> Scalar {
+        match cop_type {
+            CopType::Normal => self.normal_cooldown,
+            CopType::Soldier => self.soldier_cooldown,
+        }
+    }
+
+    fn cooldown_mut(&mut self, cop_type: CopType) -> &mut Scalar {
+        match cop_type {
+            CopType::Normal => &mut self.normal_cooldown,
+            CopType::Soldier => &mut self.soldier_cooldown,
+        }
+    }
+
+    pub fn can_recruit(&self, cop_type: CopType, money: u32) -> bool {
+        self.recruited < self.cap &&
+            self.cooldown(cop_type) <= 0.0 &&
+            money >= cop_type.recruit_cost()
+    }
+}
+
+// Pay for a new cop, who will show up at the entry point after a delay
+pub fn recruit(state: &mut State, cop_type: CopType) -> bool {
+    if !state.reinforcements.can_recruit(cop_type, state.money) {
+        return false;
+    }
+
+    state.money -= cop_type.recruit_cost();
+
+    let reinforcements = &mut state.reinforcements;
+    reinforcements.recruited += 1;
+    *reinforcements.cooldown_mut(cop_type) = cop_type.recruit_cooldown();
+    reinforcements.incoming.push(IncomingCop {
+        cop_type,
+        arrival_time_remaining: REINFORCEMENT_ARRIVAL_TIME,
+    });
+
+    true
+}
+
+// Count down cooldowns and bring in any cops that have arrived
+pub fn update_reinforcements(dt: Scalar, state: &mut State) {
+    let reinforcements = &mut state.reinforcements;
+    reinforcements.normal_cooldown = (reinforcements.normal_cooldown - dt).max(0.0);
+    reinforcements.soldier_cooldown = (reinforcements.soldier_cooldown - dt).max(0.0);
+
+    for incoming in &mut reinforcements.incoming {
+        incoming.arrival_time_remaining -= dt;
+    }
+
+    let (arrived, incoming): (Vec<IncomingCop>, Vec<IncomingCop>) = reinforcements.incoming.drain(..)
+        .partition(|incoming| incoming.arrival_time_remaining <= 0.0);
+    reinforcements.incoming = incoming;
+
+    let entry_point = reinforcements.entry_point;
+    let count = arrived.len();
+    for (i, cop) in arrived.iter().enumerate() {
+        spawn_cop(state, cop.cop_type, arrival_position(entry_point, i, count));
+    }
+}
+
+// Where the ith of a group of cops arriving together turns up, so no two land on the same spot
+pub fn arrival_position(entry_point: Vector2, i: usize, count: usize) -> Vector2 {
+    let width = count as Scalar - 1.0;
+    entry_point + Vector2 { x: REINFORCEMENT_SPACING * (i as Scalar - 0.5 * width), y: 0.0 }
+}
+
+// Add a fully stocked cop, facing north into the map
+pub fn spawn_cop(state: &mut State, cop_type: CopType, position: Vector2) {
+    state.entities.push(Entity {
+        position,
+        velocity: Vector2::zero(),
+        facing_angle: 0.5 * PI,
+        dead_or_alive: DeadOrAlive::Alive {
+            health: ENTITY_HEALTH_MAX,
+            zombie_or_human: ZombieOrHuman::Human {
+                infection: INFECTION_MIN,
+                human: Human::Cop {
+                    cop_type,
+                    weapon: cop_type.weapon(),
+                    rounds_in_magazine: state.upgrades.magazine_capacity(&cop_type.weapon()),
+                    reserve_magazines: cop_type.weapon().starting_reserve_magazines,
+                    holstered_weapon: HolsteredWeapon {
+                        rounds_in_magazine: state.upgrades.magazine_capacity(&cop_type.holstered_weapon()),
+                        ..HolsteredWeapon::new(cop_type.holstered_weapon())
+                    },
+                    state_stack: vec!()
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::initial_state::initial_state;
+    use crate::simulation::map::{load_map, DEFAULT_MAP_PATH};
+    use crate::simulation::update::{update, UpdateArgs};
+
+    #[test]
+    fn cops_arriving_together_are_pushed_apart() {
+        let map = load_map(DEFAULT_MAP_PATH).unwrap();
+        let mut state = initial_state(&map, 10, 0.1, 0.1, 1);
+        let first_arrival = state.entities.len();
+        for _ in 0..3 {
+            state.reinforcements.incoming.push(IncomingCop { cop_type: CopType::Normal, arrival_time_remaining: 0.0 });
+        }
+
+        for _ in 0..60 {
+            update(&UpdateArgs { dt: 1.0 / 60.0 }, &mut state);
+        }
+
+        let arrivals = &state.entities[first_arrival..];
+        assert_eq!(arrivals.len(), 3);
+        for (i, cop) in arrivals.iter().enumerate() {
+            assert!(cop.position.x.is_finite() && cop.position.y.is_finite());
+            assert!(cop.velocity.x.is_finite() && cop.velocity.y.is_finite());
+            for other in &arrivals[i + 1..] {
+                assert!((cop.position - other.position).length() > ENTITY_RADIUS);
+            }
+        }
+    }
+}
@@ -3,7 +3,17 @@ use crate::core::scalar::Scalar;
 use crate::core::geo::polygon::*;
 
 use crate::simulation::ai::path::Path;
+use crate::simulation::ai::nav_graph::Navigation;
+use crate::simulation::ai::flow_field::FlowField;
 use crate::simulation::barricade::*;
+use crate::simulation::fog_of_war::FogOfWar;
+use crate::simulation::ammo_crate::AmmoCrate;
+use crate::simulation::reinforcements::Reinforcements;
+use crate::simulation::upgrades::Upgrades;
+use crate::simulation::day_night::DayNightCycle;
+use crate::simulation::survival::Survival;
+use crate::simulation::objectives::Objective;
+use crate::simulation::weapon::*;
 
 use std::collections::HashSet;
 use std::collections::HashMap;
@@ -12,13 +22,35 @@ use std::collections::HashMap;
 pub struct State {
     pub entities: Vec<Entity>,
     pub buildings: Vec<Polygon>,
-    pub building_outlines: Vec<Polygon>,
     pub building_type: HashMap<usize, u32>,
+    pub navigation: Navigation,
+    // Leads the zombies to the humans
+    pub flow_field: FlowField,
     pub barricades: Vec<Barricade>,
+    pub ammo_crates: Vec<AmmoCrate>,
+    pub police_stations: Vec<Vector2>,
     pub selection: HashSet<usize>,
     pub projectiles: Vec<Projectile>,
     pub rng: rand_xorshift::XorShiftRng,
-    pub money: u32
+    pub money: u32,
+    pub reinforcements: Reinforcements,
+    pub upgrades: Upgrades,
+    pub flocking: FlockingWeights,
+    pub fog_of_war: Option<FogOfWar>,
+    pub zombie_field_of_view: Scalar,
+    pub day_night: DayNightCycle,
+    pub survival: Option<Survival>,
+    pub objectives: Vec<Objective>,
+    // Humans killed or turned into zombies so far
+    pub casualties: u32,
+}
+
+impl State {
+    // Bring the navigation up to date after barricades are built or destroyed
+    pub fn update_barricades(&mut self) {
+        self.navigation.update_barricades(&self.barricades);
+        self.flow_field.update_barricades(&self.barricades);
+    }
 }
 
 pub const ENTITY_RADIUS: Scalar = 0.5;
@@ -45,13 +77,17 @@ impl Entity {
     pub fn look_along_vector(&mut self, vector: Vector2, delta_time: Scalar) {
         self.look_along_angle(vector.angle(), delta_time);
     }
+    // Check if a point is within the cone centred on the facing angle, ignoring obstacles.
+    // Points that are close enough are always noticed, whichever way the entity is facing
+    pub fn is_in_field_of_view(&self, point: Vector2, field_of_view: Scalar) -> bool {
+        let delta = point - self.position;
+        let distance_squared = delta.length_squared();
+        distance_squared < PERIPHERAL_AWARENESS_RADIUS_SQUARED ||
+            self.get_facing_normal().dot(delta) >= (0.5 * field_of_view).cos() * distance_squared.sqrt()
+    }
     pub fn look_at_point(&mut self, point: Vector2, delta_time: Scalar) {
         self.look_along_vector(point - self.position, delta_time);
     }
-    pub fn accelerate_along_vector(&mut self, vector: Vector2, delta_time: Scalar, force: Scalar) {
-        self.look_along_vector(vector, delta_time);
-        self.velocity += delta_time * force * vector.normalize();
-    }
 
     // Query functions
     pub fn is_dead(&self) -> bool {
@@ -110,6 +146,45 @@ pub const COP_MIN_DISTANCE_FROM_WAYPOINT_SQUARED: Scalar = 0.2;
 pub const ZOMBIE_SIGHT_RADIUS: f64 = 30.0;
 pub const ZOMBIE_SIGHT_RADIUS_SQUARE: f64 = ZOMBIE_SIGHT_RADIUS * ZOMBIE_SIGHT_RADIUS;
 
+// The flow field counts as leading to a zombie's target when it has a human this close to it
+pub const ZOMBIE_FLOW_FIELD_TOLERANCE: Scalar = 5.0;
+// Roaming zombies within this walking distance of a human pick up the scent and drift along the
+// flow field towards them
+pub const ZOMBIE_SCENT_DISTANCE: Scalar = 40.0;
+pub const ZOMBIE_SCENT_PULL: Scalar = 0.5;
+
+// Fields of view are the full angle of the vision cone, in radians
+pub const ZOMBIE_FIELD_OF_VIEW: Scalar = 2.1;
+pub const CIVILIAN_FIELD_OF_VIEW: Scalar = 3.5;
+
+// Anything within this radius is noticed regardless of field of view
+pub const PERIPHERAL_AWARENESS_RADIUS: Scalar = 2.0;
+pub const PERIPHERAL_AWARENESS_RADIUS_SQUARED: Scalar = PERIPHERAL_AWARENESS_RADIUS * PERIPHERAL_AWARENESS_RADIUS;
+
+// Roaming zombies within this radius of each other move together as a horde
+pub const HORDE_NEIGHBOUR_RADIUS: Scalar = 8.0;
+pub const HORDE_NEIGHBOUR_RADIUS_SQUARED: Scalar = HORDE_NEIGHBOUR_RADIUS * HORDE_NEIGHBOUR_RADIUS;
+pub const HORDE_SEPARATION_RADIUS: Scalar = 2.0;
+pub const HORDE_SEPARATION_RADIUS_SQUARED: Scalar = HORDE_SEPARATION_RADIUS * HORDE_SEPARATION_RADIUS;
+pub const HORDE_OBSTACLE_LOOKAHEAD: Scalar = 3.0;
+pub const HORDE_MAX_STEERING: Scalar = 1.0;
+
+// Relative strength of each of the horde steering behaviours
+#[derive(Copy, Clone, Debug)]
+pub struct FlockingWeights {
+    pub cohesion: Scalar,
+    pub alignment: Scalar,
+    pub separation: Scalar,
+    pub obstacle_avoidance: Scalar,
+}
+
+pub const FLOCKING_DEFAULT: FlockingWeights = FlockingWeights {
+    cohesion: 0.1,
+    alignment: 0.3,
+    separation: 1.0,
+    obstacle_avoidance: 2.0,
+};
+
 pub const ZOMBIE_HUMAN_COLLISION_INFECTION_RATE: f64 = 0.01;
 
 pub const HUMAN_SIGHT_RADIUS: f64 = 40.0;
@@ -166,39 +241,51 @@ pub enum Human {
     },
     Cop {
         cop_type: CopType,
+        // The weapon in hand, and the ammunition for it
+        weapon: Weapon,
         rounds_in_magazine: i64,
+        reserve_magazines: i64,
+        holstered_weapon: HolsteredWeapon,
         state_stack: Vec<CopState>,
     },
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum CopType {
     Normal,
     Soldier,
 }
 impl CopType {
-    pub fn reload_time(self) -> Scalar {
+    // What cops of the type are sent out with, in hand and holstered. Soldiers keep a shotgun
+    // for when zombies get too close for the rifle
+    pub fn weapon(self) -> Weapon {
         match self {
-            CopType::Normal => 4.0,
-            CopType::Soldier => 4.0,
+            CopType::Normal => PISTOL,
+            CopType::Soldier => RIFLE,
         }
     }
-    pub fn aim_time_mean(self) -> Scalar {
+    pub fn holstered_weapon(self) -> Weapon {
         match self {
-            CopType::Normal => 2.0,
-            CopType::Soldier => 5.0,
+            CopType::Normal => BATON,
+            CopType::Soldier => SHOTGUN,
         }
     }
-    pub fn magazine_capacity(self) -> i64 {
+    pub fn recruit_cost(self) -> u32 {
         match self {
-            CopType::Normal => 6,
-            CopType::Soldier => 20,
+            CopType::Normal => 15,
+            CopType::Soldier => 40,
         }
     }
-    pub fn angular_accuracy_std_dev(self) -> Scalar {
+    pub fn recruit_cooldown(self) -> Scalar {
         match self {
-            CopType::Normal => 0.01,
-            CopType::Soldier => 0.005
+            CopType::Normal => 10.0,
+            CopType::Soldier => 30.0,
+        }
+    }
+    pub fn field_of_view(self) -> Scalar {
+        match self {
+            CopType::Normal => 1.6,
+            CopType::Soldier => 1.2,
         }
     }
 }
@@ -221,6 +308,10 @@ pub enum CopState {
         target_index: usize,
         path: Option<Path>,
     },
+    Resupplying {
+        waypoint: Vector2,
+        path: Option<Path>,
+    },
 }
 
 #[derive(Clone, PartialEq)]
@@ -244,6 +335,10 @@ pub enum ZombieState {
 #[derive(Clone, PartialEq)]
 pub enum HumanState {
     Running,
+    // Running from the zombie
+    Fleeing {
+        zombie_index: usize
+    },
     Fighting {
         target_index: usize,
         punch_time_remaining: Scalar
@@ -271,6 +366,15 @@ pub const BULLET_DAMAGE_MAX: Scalar = 1.0;
 pub const BULLET_MAX_DAMAGE_DISTANCE_FROM_ENTITY_CENTER: Scalar = 0.25 * ENTITY_RADIUS;
 pub const BULLET_MIN_DAMAGE_DISTANCE_FROM_ENTITY_CENTER: Scalar = 1.0 * ENTITY_RADIUS;
 
+// Bullets glance off walls they hit at less than this angle, in radians, instead of stopping
+pub const RICOCHET_MAX_ANGLE: Scalar = 0.35;
+
+// Portion of its energy a bullet keeps when it glances off a wall
+pub const RICOCHET_ENERGY_RETAINED: Scalar = 0.5;
+
+// How far off the wall a bullet that glances off it carries on from, so it doesn't hit it again
+pub const RICOCHET_OFFSET: Scalar = 1e-3;
+
 pub const FIST_RADIUS: Scalar = 0.3;
 pub const FIST_SPEED: Scalar = 0.6;
 pub const FIST_SPEED_MIN: Scalar = 0.3;
@@ -281,7 +385,13 @@ pub const FIST_DAMAGE: Scalar = 0.50;
 // When left hand is true, hand generated is left, else hand generated is right
 #[derive(Copy, Clone, PartialEq)]
 pub enum ProjectileKind {
-    Bullet,
+    // Energy is the portion of its damage the bullet still does, which it loses going through
+    // bodies and glancing off walls. Distance is how far it has come from the weapon that fired it
+    Bullet {
+        weapon: Weapon,
+        energy: Scalar,
+        distance: Scalar,
+    },
     Casing,
     Fist {
         owner_index: usize,
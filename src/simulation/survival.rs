@@ -0,0 +1,133 @@
+use crate::core::vector::*;
+use crate::core::scalar::*;
+use crate::simulation::state::*;
+use crate::simulation::map::WaveSpawn;
+
+use rand::Rng;
+
+// Zombies are scattered this far around their spawn point so they don't start stacked
+const SURVIVAL_SPAWN_SPREAD: Scalar = 1.5;
+
+pub const SURVIVAL_FIRST_WAVE_DELAY: Scalar = 30.0;
+pub const SURVIVAL_WAVE_INTERVAL: Scalar = 60.0;
+// Once a wave is wiped out, the next one comes after at most this long
+pub const SURVIVAL_INTERMISSION: Scalar = 20.0;
+
+pub const SURVIVAL_FIRST_WAVE_SIZE: u32 = 6;
+pub const SURVIVAL_WAVE_SIZE_GROWTH: u32 = 4;
+
+// Paid out for clearing a wave, on top of the bounty for each zombie
+pub const SURVIVAL_WAVE_BOUNTY: u32 = 15;
+
+#[derive(Clone, Debug)]
+pub struct Survival {
+    // The wave currently in progress, 0 before the first one arrives
+    pub wave: u32,
+    pub waves_survived: u32,
+    pub time_until_next_wave: Scalar,
+    pub wave_cleared: bool,
+    // Each wave enters at the map edge and heads along a clear street to its rally point
+    pub wave_spawns: Vec<WaveSpawn>,
+}
+
+impl Survival {
+    pub fn new(wave_spawns: Vec<WaveSpawn>) -> Survival {
+        Survival {
+            wave: 0,
+            waves_survived: 0,
+            time_until_next_wave: SURVIVAL_FIRST_WAVE_DELAY,
+            wave_cleared: true,
+            wave_spawns,
+        }
+    }
+
+    pub fn wave_size(wave: u32) -> u32 {
+        SURVIVAL_FIRST_WAVE_SIZE + SURVIVAL_WAVE_SIZE_GROWTH * (wave - 1)
+    }
+}
+
+// Count down to the next wave, and reward the player for clearing the current one
+pub fn update_survival(dt: Scalar, state: &mut State) {
+    let mut survival = match state.survival.take() {
+        Some(survival) => survival,
+        None => return
+    };
+
+    if !survival.wave_cleared && !state.entities.iter().any(|entity| entity.is_zombie()) {
+        survival.wave_cleared = true;
+        survival.waves_survived += 1;
+        survival.time_until_next_wave = survival.time_until_next_wave.min(SURVIVAL_INTERMISSION);
+        state.money += SURVIVAL_WAVE_BOUNTY;
+    }
+
+    survival.time_until_next_wave -= dt;
+    if survival.time_until_next_wave <= 0.0 {
+        // Holding out until the next wave arrives counts as surviving the current one
+        if !survival.wave_cleared {
+            survival.waves_survived += 1;
+        }
+
+        survival.wave += 1;
+        survival.wave_cleared = false;
+        survival.time_until_next_wave = SURVIVAL_WAVE_INTERVAL;
+        spawn_wave(state, &survival.wave_spawns, Survival::wave_size(survival.wave));
+    }
+
+    state.survival = Some(survival);
+}
+
+fn spawn_wave(state: &mut State, wave_spawns: &Vec<WaveSpawn>, size: u32) {
+    if wave_spawns.is_empty() {
+        return;
+    }
+
+    for i in 0..size {
+        let WaveSpawn { position: spawn_point, rally_point } = wave_spawns[i as usize % wave_spawns.len()];
+        let offset = vector2(
+            state.rng.gen_range(-SURVIVAL_SPAWN_SPREAD, SURVIVAL_SPAWN_SPREAD),
+            state.rng.gen_range(-SURVIVAL_SPAWN_SPREAD, SURVIVAL_SPAWN_SPREAD));
+
+        let position = if state.buildings.iter().any(|building| building.contains_point(spawn_point + offset)) {
+            spawn_point
+        } else {
+            spawn_point + offset
+        };
+
+        state.entities.push(Entity {
+            position,
+            velocity: Vector2::zero(),
+            facing_angle: (rally_point - position).angle(),
+            dead_or_alive: DeadOrAlive::Alive {
+                health: ENTITY_HEALTH_MAX,
+                zombie_or_human: ZombieOrHuman::Zombie {
+                    state: ZombieState::Moving { waypoint: rally_point },
+                    left_hand_status: HandStatus::Normal,
+                    right_hand_status: HandStatus::Normal
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::geo::polygon::Polygon;
+    use crate::simulation::map::*;
+    use crate::simulation::ai::nav_graph::Navigation;
+
+    #[test]
+    fn default_waves_can_reach_their_rally_points() {
+        let map = load_map(DEFAULT_MAP_PATH).unwrap();
+        assert!(!map.wave_spawns.is_empty());
+
+        let mut obstacles: Vec<Polygon> = map.buildings.iter().map(|(building, _)| building.clone()).collect();
+        obstacles.extend(map.boundaries.iter().cloned());
+        let navigation = Navigation::new(&obstacles);
+
+        for wave_spawn in &map.wave_spawns {
+            assert!(navigation.find_path(wave_spawn.position, wave_spawn.rally_point, ENTITY_RADIUS).is_some(),
+                    "no path from ({}, {})", wave_spawn.position.x, wave_spawn.position.y);
+        }
+    }
+}
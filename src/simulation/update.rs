@@ -1,20 +1,50 @@
 use lerp::*;
 use rand::distributions::*;
+use rand_xorshift::XorShiftRng;
 
 use crate::core::geo::circle::*;
 use crate::core::geo::intersect::segment_circle::*;
+use crate::core::geo::intersect::circle_polygon::*;
 use crate::core::geo::polygon::*;
 use crate::core::geo::segment2::*;
 
-use crate::simulation::ai::pathfinding::find_path;
 use crate::simulation::state::MoveMode;
 use crate::simulation::barricade::*;
+use crate::simulation::ammo_crate::*;
+use crate::simulation::reinforcements::update_reinforcements;
+use crate::simulation::survival::update_survival;
+use crate::simulation::objectives::update_objectives;
+use crate::simulation::ai::steering::*;
+use crate::simulation::ai::flow_field::FlowField;
+use crate::simulation::upgrades::Upgrades;
+use crate::simulation::weapon::*;
 
 use super::state::*;
 
-const SPRING_CONSTANT: f64 = 32.0;
+// Overlap with buildings and barricades that's left alone, so entities resting against a wall
+// aren't pushed out and fall back in every frame
+const COLLISION_SLOP: Scalar = 0.01;
 
-#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+// Portion of the overlap with buildings and barricades taken out each frame
+const COLLISION_CORRECTION: Scalar = 0.8;
+
+// Portion of the speed into a building or barricade an entity bounces back with
+const COLLISION_RESTITUTION: Scalar = 0.0;
+
+// Entities that move further than this in one step are swept along the way, so they can't pass
+// through the corner of a thin barricade or wall
+const CONTINUOUS_COLLISION_MIN_DISTANCE: Scalar = 0.5 * ENTITY_RADIUS;
+
+// Gap left between an entity and what it was swept into
+const CONTINUOUS_COLLISION_SKIN: Scalar = 1e-3;
+
+// Barricade health a zombie knocks off for every unit of speed it runs into the barricade with
+const BARRICADE_DAMAGE_PER_IMPACT_SPEED: Scalar = 2.0;
+
+// Portion of a sound's audible radius that remains after passing through a building
+const SOUND_BUILDING_ATTENUATION: Scalar = 0.4;
+
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
 pub enum SoundType {
     GunshotHandgun,
     GunshotRifle,
@@ -23,9 +53,24 @@ pub enum SoundType {
     ZombieDeath,
 }
 
+impl SoundType {
+    // Distance at which zombies can hear the sound in the open
+    pub fn audible_radius(self) -> Scalar {
+        match self {
+            SoundType::GunshotHandgun => 45.0,
+            SoundType::GunshotRifle => 80.0,
+            SoundType::Reload => 8.0,
+            SoundType::PersonInfected => 15.0,
+            SoundType::ZombieDeath => 10.0,
+        }
+    }
+}
+
 pub struct Sound {
     pub position: Vector2,
     pub sound_type: SoundType,
+    // Distance at which zombies can hear it in the open, which for gunshots depends on the weapon
+    pub audible_radius: Scalar,
 }
 
 pub struct UpdateArgs {
@@ -58,10 +103,17 @@ pub fn update(args: &UpdateArgs, state: &mut State) -> SimulationResults {
 
     const DOUBLE_ENTITY_RADIUS_SQUARED: f64 = 4.0 * ENTITY_RADIUS * ENTITY_RADIUS;
 
+    state.day_night.advance(args.dt);
+
+    // Bring in any reinforcements that have arrived
+    update_reinforcements(args.dt, state);
+
+    // Send in the next zombie wave in survival mode
+    update_survival(args.dt, state);
+
     // Check for collisions
     for i in 0..state.entities.len() {
         let p1 = state.entities[i].position;
-        let circle = Circle { center: p1, radius: ENTITY_RADIUS };
 
         if state.entities[i].is_dead() {
             continue
@@ -86,60 +138,30 @@ pub fn update(args: &UpdateArgs, state: &mut State) -> SimulationResults {
             }
         }
 
-        // Collisions with buildings
+        // Collisions with buildings. Every building touched is dealt with, so entities wedged
+        // into a corner between two of them are pushed out of both
         for j in 0..state.buildings.len() {
-            // Check if position is inside the building
-            let mut overlap = state.buildings[j].contains_point(p1);
-            let inside = overlap;
-
-            // Don't bother doing this if we already know there's an overlap
-            if !inside {
-                // Check if one of the building's sides intersects the entity
-                for k in 0..state.buildings[j].num_sides() {
-                    let segment = Segment2 {
-                        p1: state.buildings[j].get(k),
-                        p2: state.buildings[j].get((k + 1) % state.buildings[j].num_sides())
-                    };
-
-                    if segment_circle_has_intersection(&segment, &circle) {
-                        overlap = true;
-                        break;
-                    }
-                }
-            }
-
-            if overlap {
-                handle_building_collision(args, &mut state.entities[i], &state.buildings[j], inside);
-                break;
+            let circle = Circle { center: state.entities[i].position, radius: ENTITY_RADIUS };
+            if let Some(contact) = circle_polygon_contact(&circle, &state.buildings[j]) {
+                resolve_contact(&mut state.entities[i], &contact);
             }
         }
 
-        // Collisions with barricades
+        // Collisions with barricades, which zombies damage by running into them
         for j in 0..state.barricades.len() {
-            let mut overlap = state.barricades[j].poly.contains_point(p1);
-            let inside = overlap;
-
-            if !inside {
-                for k in 0..state.barricades[j].poly.num_sides() {
-                    let segment = Segment2 {
-                        p1: state.barricades[j].poly.get(k),
-                        p2: state.barricades[j].poly.get((k + 1) % state.barricades[j].poly.num_sides())
-                    };
-
-                    if segment_circle_has_intersection(&segment, &circle) {
-                        overlap = true;
-                        break;
-                    }
+            let circle = Circle { center: state.entities[i].position, radius: ENTITY_RADIUS };
+            if let Some(contact) = circle_polygon_contact(&circle, &state.barricades[j].poly) {
+                let impact_speed = resolve_contact(&mut state.entities[i], &contact);
+                if state.entities[i].is_zombie() {
+                    state.barricades[j].health -= BARRICADE_DAMAGE_PER_IMPACT_SPEED * impact_speed;
                 }
             }
-
-            if overlap {
-                handle_barricade_collision(args, &mut state.entities[i], &mut state.barricades[j], inside);
-                break;
-            }
         }
     }
 
+    // Carry on working out the way to the humans for the zombies
+    state.flow_field.update(&state.entities);
+
     // Apply individual behaviours
     for i in 0..state.entities.len() {
 
@@ -156,11 +178,12 @@ pub fn update(args: &UpdateArgs, state: &mut State) -> SimulationResults {
             DeadOrAlive::Alive { zombie_or_human, health } => {
                 if *health <= ENTITY_HEALTH_MIN {
                     // Pay out bounty if zombie is killed
-                    if entity.is_zombie() { state.money += 1 }
+                    if entity.is_zombie() { state.money += 1 } else { state.casualties += 1 }
                     state.entities[i].dead_or_alive = DeadOrAlive::Dead;
                     sounds.push(Sound {
                         position: state.entities[i].position,
-                        sound_type: SoundType::ZombieDeath
+                        sound_type: SoundType::ZombieDeath,
+                        audible_radius: SoundType::ZombieDeath.audible_radius()
                     });
                 }
                 else {
@@ -178,6 +201,7 @@ pub fn update(args: &UpdateArgs, state: &mut State) -> SimulationResults {
                                 *infection *= infection_growth_factor;
                             }
                             if *infection >= INFECTION_MAX {
+                                state.casualties += 1;
                                 *zombie_or_human = ZombieOrHuman::Zombie {
                                     state: ZombieState::Roaming {
                                         jerk: Vector2::zero(),
@@ -188,7 +212,8 @@ pub fn update(args: &UpdateArgs, state: &mut State) -> SimulationResults {
                                 };
                                 sounds.push(Sound {
                                     position: state.entities[i].position,
-                                    sound_type: SoundType::PersonInfected
+                                    sound_type: SoundType::PersonInfected,
+                                    audible_radius: SoundType::PersonInfected.audible_radius()
                                 });
                             }
                             else {
@@ -212,17 +237,33 @@ pub fn update(args: &UpdateArgs, state: &mut State) -> SimulationResults {
         }
     }
 
+    // Zombies investigate the noises made this frame
+    zombies_hear_sounds(state, &sounds);
+
     // Apply acceleration
     for e in &mut state.entities {
         let displacement = args.dt * e.velocity;
-        e.position += displacement;
         e.velocity -= ENTITY_DRAG * displacement;
+
+        if displacement.length_squared() > CONTINUOUS_COLLISION_MIN_DISTANCE * CONTINUOUS_COLLISION_MIN_DISTANCE {
+            move_swept(e, displacement, &state.buildings, &state.barricades);
+        } else {
+            e.position += displacement;
+        }
+    }
+
+    // Update what the cops can see
+    match &mut state.fog_of_war {
+        Some(fog_of_war) => fog_of_war.update(&state.entities, &state.buildings, state.day_night.sight_radius(COP_SIGHT_RADIUS)),
+        None => ()
     }
 
     // Remove motionless bullets
     state.projectiles.retain(
-        |p| p.kind != ProjectileKind::Bullet ||
-            p.velocity.length_squared() > BULLET_SPEED_MIN
+        |p| match p.kind {
+            ProjectileKind::Bullet { .. } => p.velocity.length_squared() > BULLET_SPEED_MIN,
+            _ => true
+        }
     );
 
     // Remove fist and return to its owner
@@ -281,8 +322,13 @@ pub fn update(args: &UpdateArgs, state: &mut State) -> SimulationResults {
 
         p.position = segment.p2;
 
-        if p.kind == ProjectileKind::Casing {
-            continue;
+        match p.kind {
+            ProjectileKind::Casing => continue,
+            ProjectileKind::Bullet { .. } => {
+                update_bullet(p, &segment, &mut state.entities, &state.buildings);
+                continue;
+            }
+            _ => ()
         }
 
         struct Collision {
@@ -328,32 +374,6 @@ pub fn update(args: &UpdateArgs, state: &mut State) -> SimulationResults {
         }
 
         match p.kind {
-            ProjectileKind::Bullet => {
-                match &first_collision {
-                    None => (),
-                    Some(Collision{entity_id: i, ..}) => {
-
-                        let distance_from_entity_center = segment
-                            .distance_from_ray_to_point_squared(state.entities[*i].position)
-                            .sqrt();
-
-                        let distance_normalized
-                        = (distance_from_entity_center - BULLET_MAX_DAMAGE_DISTANCE_FROM_ENTITY_CENTER)
-                            / (BULLET_MIN_DAMAGE_DISTANCE_FROM_ENTITY_CENTER -
-                            BULLET_MAX_DAMAGE_DISTANCE_FROM_ENTITY_CENTER);
-
-                        let damage = BULLET_DAMAGE_MAX.lerp_bounded(BULLET_DAMAGE_MIN, distance_normalized);
-
-
-                        match &mut state.entities[*i].dead_or_alive {
-                            DeadOrAlive::Alive { health, .. } => { *health -= damage; }
-                            _ => panic!("Only living entities should collide with bullets!")
-                        }
-
-                        p.velocity = Vector2::zero();
-                    }
-                }
-            }
             ProjectileKind::Fist { owner_index, left_hand: _ } => {
                 let owner = &mut state.entities[owner_index];
                 match owner.dead_or_alive {
@@ -396,15 +416,139 @@ pub fn update(args: &UpdateArgs, state: &mut State) -> SimulationResults {
         }
     }
 
-    for i in 0..state.barricades.len() {
-        if state.barricades[i].health <= 0.0 {
-            state.barricades.remove(i);
-        }
+    let barricade_count = state.barricades.len();
+    state.barricades.retain(|barricade| barricade.health > 0.0);
+    if state.barricades.len() != barricade_count {
+        state.update_barricades();
     }
 
+    update_objectives(args.dt, state, &entity_counts);
+
     SimulationResults { entity_counts, sounds: sounds }
 }
 
+// Carry a bullet along the segment it travels this step. It goes through the bodies in its way
+// while it has the energy to, doing less damage to each, glances off walls it hits at a shallow
+// angle, and is spent at the end of its weapon's range
+fn update_bullet(p: &mut Projectile, segment: &Segment2, entities: &mut Vec<Entity>, buildings: &Vec<Polygon>) {
+    let (weapon, mut energy, distance) = match p.kind {
+        ProjectileKind::Bullet { weapon, energy, distance } => (weapon, energy, distance),
+        _ => panic!("Projectile should be a bullet!")
+    };
+
+    let movement = segment.p2 - segment.p1;
+    let length = movement.length();
+    if length == 0.0 {
+        return;
+    }
+
+    // How far along the segment the bullet runs out of range
+    let spent_time = (weapon.range - distance) / length;
+
+    // The first wall in the way, and which way it faces
+    let mut wall_hit: Option<(Scalar, Vector2)> = None;
+    for building in buildings {
+        if let Some((time, side)) = building.first_intersect(segment.p1, segment.p2) {
+            if wall_hit.map_or(true, |(first_time, _)| time < first_time) {
+                wall_hit = Some((time, building.normals()[side]));
+            }
+        }
+    }
+    if wall_hit.map_or(false, |(time, _)| time > spent_time) {
+        wall_hit = None;
+    }
+    let stop_time = wall_hit.map_or(spent_time.min(1.0), |(time, _)| time);
+
+    // Bodies the bullet goes into before the wall, in the order it reaches them. Bullets that go
+    // through a body are taken out the other side, so one that starts inside a body, further from
+    // the way out than its own size, was fired into it at point blank range
+    let mut hits = vec!();
+    for i in 0..entities.len() {
+        if entities[i].is_dead() {
+            // Dead entities don't collide with bullets
+            continue;
+        }
+
+        let circle = Circle { center: entities[i].position, radius: ENTITY_RADIUS };
+        if let Some(IntersectionTimes(entry, exit)) = segment_circle_intersection_times(segment, &circle) {
+            if (0.0 <= entry || exit * length > BULLET_RADIUS) && entry <= stop_time {
+                hits.push((entry.max(0.0), exit, i));
+            }
+        }
+    }
+    hits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mut end_time: Scalar = 1.0;
+
+    for (entry, exit, i) in hits {
+        let entry_point = segment.p1 + entry * movement;
+        let distance_from_entity_center = if entry > 0.0 {
+            Segment2 { p1: segment.p1, p2: entry_point }
+                .distance_from_ray_to_point_squared(entities[i].position)
+                .sqrt()
+        } else {
+            (entry_point - entities[i].position).length()
+        };
+
+        let distance_normalized
+        = (distance_from_entity_center - BULLET_MAX_DAMAGE_DISTANCE_FROM_ENTITY_CENTER)
+            / (BULLET_MIN_DAMAGE_DISTANCE_FROM_ENTITY_CENTER -
+            BULLET_MAX_DAMAGE_DISTANCE_FROM_ENTITY_CENTER);
+
+        let damage = energy * weapon.damage_at(distance + entry * length) *
+            BULLET_DAMAGE_MAX.lerp_bounded(BULLET_DAMAGE_MIN, distance_normalized);
+
+        match &mut entities[i].dead_or_alive {
+            DeadOrAlive::Alive { health, .. } => { *health -= damage; }
+            _ => panic!("Only living entities should collide with bullets!")
+        }
+
+        // Going through takes energy for the depth of body in the way, which is least for a
+        // glancing hit
+        let depth = (exit - entry) * length;
+        let remaining_energy = if weapon.penetration > 0.0 { energy - depth / weapon.penetration } else { 0.0 };
+        if remaining_energy <= 0.0 {
+            p.position = entry_point;
+            p.velocity = Vector2::zero();
+            p.kind = ProjectileKind::Bullet { weapon, energy: 0.0, distance: distance + entry * length };
+            return;
+        }
+
+        // Slowed down along with the energy it's lost
+        p.velocity *= remaining_energy / energy;
+        energy = remaining_energy;
+
+        end_time = end_time.max(exit);
+    }
+    p.position = segment.p1 + end_time * movement;
+    let mut travelled = end_time * length;
+
+    if let Some((time, normal)) = wall_hit {
+        travelled = time * length;
+
+        let hit_point = segment.p1 + time * movement;
+        let direction = movement / length;
+
+        // Angle between the bullet's path and the wall, for bullets coming at it from outside
+        let incidence = -direction.dot(normal);
+        if incidence > 0.0 && incidence.asin() < RICOCHET_MAX_ANGLE {
+            p.position = hit_point + RICOCHET_OFFSET * normal;
+            p.velocity = RICOCHET_ENERGY_RETAINED * (p.velocity - 2.0 * p.velocity.dot(normal) * normal);
+            energy *= RICOCHET_ENERGY_RETAINED;
+        } else {
+            p.position = hit_point;
+            p.velocity = Vector2::zero();
+        }
+    } else if spent_time < 1.0 {
+        let spent_time = spent_time.max(0.0);
+        travelled = spent_time * length;
+        p.position = segment.p1 + spent_time * movement;
+        p.velocity = Vector2::zero();
+    }
+
+    p.kind = ProjectileKind::Bullet { weapon, energy, distance: distance + travelled };
+}
+
 fn handle_collision(
     args: &UpdateArgs,
     entities: &mut Vec<Entity>,
@@ -431,101 +575,64 @@ fn handle_collision(
         }
     }
 
+    // Entities right on top of each other have no direction to be pushed apart in, so pick one
+    let (delta, delta_length_squared) = if delta_length_squared > 0.0 {
+        (*delta, delta_length_squared)
+    } else {
+        (Vector2 { x: ENTITY_RADIUS, y: 0.0 }, ENTITY_RADIUS * ENTITY_RADIUS)
+    };
+
     // Force entities apart that are overlapping
-    let velocity_change = *delta * (args.dt / delta_length_squared);
+    let velocity_change = delta * (args.dt / delta_length_squared);
     entities[i].velocity -= velocity_change;
     entities[j].velocity += velocity_change;
 }
 
-fn handle_building_collision(
-    args: &UpdateArgs,
-    entity: &mut Entity,
-    building: &Polygon,
-    inside: bool) {
-
-    let mut distance_squared = INFINITY;
-    let mut normal = Vector2::zero();
-    let normals = building.normals();
-
-    // Find the closest edge
-    for i in 0..building.num_sides() {
-        let seg_i = Segment2 {
-            p1: building.get(i),
-            p2: building.get((i + 1) % building.num_sides())
-        };
-        let dist_i = seg_i.distance_from_segment_to_point_squared(entity.position);
-
-        if distance_squared > dist_i {
-            distance_squared = dist_i;
-            normal = normals[i];
-        }
-    }
+// Push the entity out of a building or barricade it overlaps, and stop it moving any further in.
+// Returns the speed it was going into the obstacle
+fn resolve_contact(entity: &mut Entity, contact: &Contact) -> Scalar {
+    entity.position += COLLISION_CORRECTION * (contact.depth - COLLISION_SLOP).max(0.0) * contact.normal;
 
-    let distance = distance_squared.sqrt();
-
-    if inside {
-        // If the entity is inside move them to the nearest edge
-        entity.position += (distance + ENTITY_RADIUS) * normal;
+    let normal_speed = entity.velocity.dot(contact.normal);
+    if normal_speed < 0.0 {
+        entity.velocity -= (1.0 + COLLISION_RESTITUTION) * normal_speed * contact.normal;
+        -normal_speed
     } else {
-        // If the entity is overlapping, force them away from the edge
-        let overlap = ENTITY_RADIUS - distance;
-
-        // Check if the entity is overlapping left/right borders, force them away if yes
-        if building.get(0).x == -24.5 || building.get(0).x == 114.5 {
-            entity.velocity -= args.dt * SPRING_CONSTANT * overlap * normal
-        }
-        else {
-        entity.velocity += args.dt * SPRING_CONSTANT * overlap * normal}
+        0.0
     }
 }
 
-fn handle_barricade_collision(
-    args: &UpdateArgs,
-    entity: &mut Entity,
-    barricade: &mut Barricade,
-    inside: bool) {
-
-    let mut distance_squared = INFINITY;
-    let mut normal = Vector2::zero();
-    let normals = barricade.poly.normals();
-
-    for i in 0..barricade.poly.num_sides() {
-        let seg_i = Segment2 {
-            p1: barricade.poly.get(i),
-            p2: barricade.poly.get((i + 1) % barricade.poly.num_sides())
-        };
-        let dist_i = seg_i.distance_from_segment_to_point_squared(entity.position);
-
-        if distance_squared > dist_i {
-            distance_squared = dist_i;
-            normal = normals[i];
-        }
-    }
-
-    let distance = distance_squared.sqrt();
-
-    if inside {
-        // If the entity is inside move them to the nearest edge
-        entity.position += (distance + ENTITY_RADIUS) * normal;
-    } else {
-        // If the entity is overlapping, force them away from the edge
-        let overlap = ENTITY_RADIUS - distance;
-        let force: Vector2 = args.dt * SPRING_CONSTANT * overlap * normal;
-
-        entity.velocity += force;
-
-        // Only zombies damage barricades by running into them
-        match &entity.dead_or_alive {
-            DeadOrAlive::Alive { zombie_or_human, .. } => match zombie_or_human {
-                ZombieOrHuman::Zombie { .. } => barricade.health -= force.length() * BARRICADE_HEALTH / 25.0,
-                _ => ()
-            },
-            _ => ()
+// Move an entity that's going fast, stopping it where it first runs into a building or barricade
+// rather than letting it end up on the far side
+fn move_swept(entity: &mut Entity, displacement: Vector2, buildings: &Vec<Polygon>, barricades: &Vec<Barricade>) {
+    let start = entity.position;
+    let end = start + displacement;
+
+    let impact = buildings.iter()
+        .chain(barricades.iter().map(|barricade| &barricade.poly))
+        .filter_map(|obstacle| circle_polygon_time_of_impact(start, end, ENTITY_RADIUS, obstacle).map(|time| (time, obstacle)))
+        .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+
+    match impact {
+        None => entity.position = end,
+        Some((time, obstacle)) => {
+            let distance = displacement.length();
+            let time = (time - CONTINUOUS_COLLISION_SKIN / distance).max(0.0);
+            entity.position = start + time * displacement;
+
+            // Slide off what was hit instead of carrying on into it
+            let circle = Circle { center: entity.position, radius: ENTITY_RADIUS + 2.0 * CONTINUOUS_COLLISION_SKIN };
+            if let Some(contact) = circle_polygon_contact(&circle, obstacle) {
+                let normal_speed = entity.velocity.dot(contact.normal);
+                if normal_speed < 0.0 {
+                    entity.velocity -= (1.0 + COLLISION_RESTITUTION) * normal_speed * contact.normal;
+                }
+            }
         }
     }
 }
 
-fn can_see(
+pub fn can_see(
     buildings: &Vec<Polygon>,
     from: Vector2,
     to: Vector2) -> bool {
@@ -539,12 +646,29 @@ fn can_see(
     return true;
 }
 
-// Returns the index of the best target, if such a target exists
-fn cop_find_best_target(sim_state: &mut State, cop_index: usize) -> Option<usize> {
+// Returns the index of the best target, if such a target exists. Only targets in the field of view
+// are considered if there is one, otherwise the cop looks all around
+fn cop_find_best_target(sim_state: &mut State, cop_index: usize, field_of_view: Option<Scalar>) -> Option<usize> {
 
     let entities = &sim_state.entities;
 
     let cop_position = entities[cop_index].position;
+    let sight_radius_squared = sim_state.day_night.sight_radius_squared(COP_SIGHT_RADIUS);
+
+    let weapon = match &entities[cop_index].dead_or_alive {
+        DeadOrAlive::Alive {
+            zombie_or_human: ZombieOrHuman::Human {
+                human: Human::Cop { weapon, .. },
+                .. },
+            ..
+        } => *weapon,
+        _ => panic!("Entity at index should be a cop!")
+    };
+
+    // Portion of hits that go right through someone, which are the ones where the depth of body
+    // in the way is less than the bullet's penetration
+    let pass_through = (weapon.penetration / (2.0 * ENTITY_RADIUS)).min(1.0);
+    let over_penetration_chance = 1.0 - (1.0 - pass_through * pass_through).sqrt();
 
     let mut visible_entity_indices_by_distance_ascending = vec!();
     for i in 0..entities.len() {
@@ -559,6 +683,18 @@ fn cop_find_best_target(sim_state: &mut State, cop_index: usize) -> Option<usize
             continue;
         }
 
+        if (entities[i].position - cop_position).length_squared() > sight_radius_squared {
+            // Don't consider entities too far away to see
+            continue;
+        }
+
+        if let Some(field_of_view) = field_of_view {
+            if !entities[cop_index].is_in_field_of_view(entities[i].position, field_of_view) {
+                // Don't consider entities outside your field of view
+                continue;
+            }
+        }
+
         if !can_see(
             &sim_state.buildings,
             cop_position,
@@ -586,7 +722,14 @@ fn cop_find_best_target(sim_state: &mut State, cop_index: usize) -> Option<usize
             continue;
         }
 
-        let vector_to_target_normal = (entities[*target_index].position - cop_position).normalize();
+        let vector_to_target = entities[*target_index].position - cop_position;
+        let target_distance_squared = vector_to_target.length_squared();
+        if target_distance_squared > weapon.reach() * weapon.reach() {
+            // Don't pick targets out of reach of your weapon
+            continue;
+        }
+
+        let vector_to_target_normal = vector_to_target.normalize();
 
         let mut target_score = 0.0;
 
@@ -599,7 +742,12 @@ fn cop_find_best_target(sim_state: &mut State, cop_index: usize) -> Option<usize
 
             // Bit of an optimization to avoid a square root,
             // equivalent to coverage = cos(angle_of_blocker_from_target) / blocker_distance
-            let blocker_coverage = vector_to_target_normal.dot(vector_to_blocker) / blocker_distance_squared;
+            let mut blocker_coverage = vector_to_target_normal.dot(vector_to_blocker) / blocker_distance_squared;
+
+            // Anyone beyond the target is only in the way of bullets that go through it
+            if blocker_distance_squared > target_distance_squared {
+                blocker_coverage *= over_penetration_chance;
+            }
 
             let blocker_score = match &entities[*blocker_index].dead_or_alive {
 
@@ -648,8 +796,7 @@ fn update_cop(
 
     let entities = &mut sim_state.entities;
     let buildings = &sim_state.buildings;
-    let building_outlines = &sim_state.building_outlines;
-    let barricades = &sim_state.barricades;
+    let navigation = &sim_state.navigation;
 
     enum StateChange {
         // Exit the state you're in
@@ -662,6 +809,8 @@ fn update_cop(
         Enter(CopState),
     }
 
+    let upgrades = sim_state.upgrades;
+
     // Require unsafe to get second mutable reference
     let mut entity = unsafe { &mut *(&mut entities[index] as *mut Entity) };
     match &mut entity {
@@ -669,7 +818,7 @@ fn update_cop(
             position,
             dead_or_alive: DeadOrAlive::Alive {
                 zombie_or_human: ZombieOrHuman::Human {
-                    human: Human::Cop { cop_type, rounds_in_magazine, state_stack },
+                    human: Human::Cop { cop_type, weapon, rounds_in_magazine, reserve_magazines, holstered_weapon, state_stack, .. },
                     ..
                 },
                 ..
@@ -677,34 +826,38 @@ fn update_cop(
             ..
         } => {
             let state_change = match state_stack.last() {
-                Some(CopState::AttackingZombie { target_index, path: _ }) => {
+                Some(CopState::AttackingZombie { target_index, path }) => {
 
                     if let DeadOrAlive::Dead = entities[*target_index].dead_or_alive {
                         // Target is dead, stop attacking
                         StateChange::Exit
                     }
-                    else if *rounds_in_magazine <= 0 {
-                        // Out of ammo, need to reload before we can attack
-                        StateChange::Enter(
-                            CopState::Reloading {
-                                reload_time_remaining: cop_type.reload_time()
-                            }
-                        )
+                    else if weapon.uses_ammo() && *rounds_in_magazine <= 0 {
+                        if *reserve_magazines > 0 {
+                            // Out of ammo, need to reload before we can attack
+                            StateChange::Enter(
+                                CopState::Reloading {
+                                    reload_time_remaining: upgrades.reload_time(weapon)
+                                }
+                            )
+                        } else {
+                            // No magazines left to reload with, give up the attack
+                            StateChange::Exit
+                        }
                     }
-                    else if can_see(
+                    else if (entities[*target_index].position - *position).length_squared() <= weapon.reach() * weapon.reach() &&
+                        can_see(
                         &sim_state.buildings,
                         *position,
                         entities[*target_index].position) {
-                        // Can see the target, take aim
-                        let aim_time_distribution = Exp::new(cop_type.aim_time_mean());
+                        // Can see the target and it's in reach, take aim
                         StateChange::Enter(CopState::Aiming {
-                            aim_time_remaining: aim_time_distribution.sample(&mut sim_state.rng),
+                            aim_time_remaining: sample_aim_time(weapon, &upgrades, &mut sim_state.rng),
                             target_index: *target_index,
                         })
                     }
                     else {
-                        match find_path(entities[index].position, entities[*target_index].position,
-                                        buildings, building_outlines, barricades) {
+                        match navigation.follow_path(entities[index].position, entities[*target_index].position, path, ENTITY_RADIUS) {
                             None => {
                                 // No path to zombie possible, end chase
                                 StateChange::Exit
@@ -715,8 +868,8 @@ fn update_cop(
                                     // No path to zombie possible, end chase
                                         StateChange::Exit,
                                     Some(edge) => {
-                                        let delta = edge.end.pos - entities[index].position;
-                                        entities[index].accelerate_along_vector(delta, args.dt, COP_MOVEMENT_FORCE);
+                                        let next_waypoint = path.edges.get(1).map(|edge| edge.end.pos);
+                                        steer_towards_waypoint(entities, index, edge.end.pos, next_waypoint, COP_MOVEMENT_FORCE, args.dt);
                                         StateChange::Update(CopState::AttackingZombie {
                                             target_index: *target_index,
                                             path: Some(path)
@@ -740,6 +893,12 @@ fn update_cop(
                                 entities[*target_index].position) {
                         StateChange::Exit
                     }
+
+                    // Stop aiming if the target has got out of reach
+                    else if (entities[*target_index].position - entities[index].position).length_squared() >
+                        weapon.reach() * weapon.reach() {
+                        StateChange::Exit
+                    }
                     else {
 
                         let my_pos = entities[index].position;
@@ -753,81 +912,104 @@ fn update_cop(
                                 CopState::Aiming { aim_time_remaining: *aim_time_remaining - args.dt, target_index: *target_index }
                             )
                         } else {
-                            let angular_deviation =
-                                Normal::new(0.0, cop_type.angular_accuracy_std_dev()).sample(&mut sim_state.rng);
-
                             // Finished aiming, take the shot
-                            let delta_normal = delta.rotate_by(angular_deviation);
-
-                            // Spawn outside of the entity - don't want to shoot the entity itself
-                            let spawn_pos = entities[index].position +
-                                BULLET_SPAWN_DISTANCE_MULTIPLIER * ENTITY_RADIUS * delta_normal;
-
-                            // Fire at the target
-                            sim_state.projectiles.push(
-                                Projectile {
-                                    position: spawn_pos,
-                                    velocity: BULLET_SPEED * delta_normal,
-                                    kind: ProjectileKind::Bullet
-                                });
+                            let spread = upgrades.spread(weapon) * sim_state.day_night.accuracy_penalty();
+                            for _ in 0..weapon.pellets {
+                                let angular_deviation = Normal::new(0.0, spread).sample(&mut sim_state.rng);
+                                let delta_normal = delta.rotate_by(angular_deviation);
+
+                                // Spawn outside of the entity - don't want to shoot the entity itself
+                                let spawn_pos = entities[index].position +
+                                    BULLET_SPAWN_DISTANCE_MULTIPLIER * ENTITY_RADIUS * delta_normal;
+
+                                // Fire at the target
+                                sim_state.projectiles.push(
+                                    Projectile {
+                                        position: spawn_pos,
+                                        velocity: weapon.projectile_speed * delta_normal,
+                                        kind: ProjectileKind::Bullet {
+                                            weapon: *weapon,
+                                            energy: 1.0,
+                                            distance: 0.0
+                                        }
+                                    });
+                            }
 
-                            sim_state.projectiles.push(
-                                Projectile {
-                                    position: spawn_pos,
-                                    // Casing ejects from the right of the weapon
-                                    velocity: CASING_SPEED * delta_normal.right(),
-                                    kind: ProjectileKind::Casing
-                                });
+                            if weapon.uses_ammo() {
+                                let delta_normal = delta.normalize();
+                                sim_state.projectiles.push(
+                                    Projectile {
+                                        position: entities[index].position +
+                                            BULLET_SPAWN_DISTANCE_MULTIPLIER * ENTITY_RADIUS * delta_normal,
+                                        // Casing ejects from the right of the weapon
+                                        velocity: CASING_SPEED * delta_normal.right(),
+                                        kind: ProjectileKind::Casing
+                                    });
+
+                                *rounds_in_magazine -= 1;
+                            }
 
-                            *rounds_in_magazine -= 1;
+                            if let Some(sound_type) = weapon.sound {
+                                sounds.push(Sound {
+                                    position: entities[index].position,
+                                    sound_type,
+                                    audible_radius: weapon.noise
+                                });
+                            }
 
-                            sounds.push(Sound {
-                                position: entities[index].position,
-                                sound_type: match cop_type {
-                                    CopType::Normal => SoundType::GunshotHandgun,
-                                    CopType::Soldier => SoundType::GunshotRifle,
-                                }
-                            });
-                            StateChange::Exit
+                            // Firing at will, the cop stays on aim and picks the next target out of
+                            // those in the sights, and only looks all around once there are none
+                            let has_rounds = !weapon.uses_ammo() || *rounds_in_magazine > 0;
+                            let next_target = if state_stack.len() == 1 && has_rounds {
+                                cop_find_best_target(sim_state, index, Some(cop_type.field_of_view()))
+                            } else {
+                                None
+                            };
+                            match next_target {
+                                Some(i) => StateChange::Update(CopState::Aiming {
+                                    aim_time_remaining: sample_aim_time(weapon, &upgrades, &mut sim_state.rng),
+                                    target_index: i,
+                                }),
+                                None => StateChange::Exit
+                            }
                         }
                     }
 
                 }
-                Some(CopState::Moving { waypoint, mode, path: _ }) => {
-                    match mode {
-                        MoveMode::Moving => {
-                            match find_path(entities[index].position, *waypoint,
-                                            buildings, building_outlines, barricades) {
-                                None => {
-                                    StateChange::Exit
-                                },
-                                Some(path) => {
-                                    match path.to_vec().get(1) {
-                                        None => StateChange::Exit,
-                                        Some(&node) => {
-                                            let delta = node - entities[index].position;
-
-                                            if *waypoint == node && delta.length_squared() < COP_MIN_DISTANCE_FROM_WAYPOINT_SQUARED {
-                                                StateChange::Exit
-                                            } else {
-                                                entities[index].accelerate_along_vector(delta, args.dt, COP_MOVEMENT_FORCE);
-                                                StateChange::Update(
-                                                    CopState::Moving { waypoint: *waypoint, mode: MoveMode::Moving, path: Some(path) }
-                                                )
-                                            }
-                                        }
+                Some(CopState::Moving { waypoint, mode, path }) => {
+                    let movement_force = match mode {
+                        MoveMode::Moving => COP_MOVEMENT_FORCE,
+                        MoveMode::Sprinting => COP_MOVEMENT_FORCE * upgrades.sprint_multiplier(),
+                    };
+                    match navigation.follow_path(entities[index].position, *waypoint, path, ENTITY_RADIUS) {
+                        None => {
+                            StateChange::Exit
+                        },
+                        Some(path) => {
+                            match path.edges.first() {
+                                None => StateChange::Exit,
+                                Some(edge) => {
+                                    let node = edge.end.pos;
+                                    let delta = node - entities[index].position;
+
+                                    // Stop once there, or as close as the cops already standing there allow
+                                    if *waypoint == node && (delta.length_squared() < COP_MIN_DISTANCE_FROM_WAYPOINT_SQUARED ||
+                                        is_waypoint_taken(entities, index, node)) {
+                                        StateChange::Exit
+                                    } else {
+                                        let next_waypoint = path.edges.get(1).map(|edge| edge.end.pos);
+                                        steer_towards_waypoint(entities, index, node, next_waypoint, movement_force, args.dt);
+                                        StateChange::Update(
+                                            CopState::Moving { waypoint: *waypoint, mode: *mode, path: Some(path) }
+                                        )
                                     }
                                 }
                             }
                         }
-                        MoveMode::Sprinting => {
-                            // TODO:
-                            StateChange::Exit
-                        }
                     }
                 }
                 Some(CopState::Reloading { reload_time_remaining }) => {
-                    let half_reload_time = 0.5 * cop_type.reload_time();
+                    let half_reload_time = 0.5 * upgrades.reload_time(weapon);
                     let new_reload_time_remaining = reload_time_remaining - args.dt;
 
                     // Play the reload sound when half-done reloading
@@ -835,7 +1017,8 @@ fn update_cop(
                         half_reload_time > new_reload_time_remaining {
                             sounds.push(Sound{
                                 position: entities[index].position,
-                                sound_type: SoundType::Reload
+                                sound_type: SoundType::Reload,
+                                audible_radius: SoundType::Reload.audible_radius()
                             });
                     }
 
@@ -845,25 +1028,67 @@ fn update_cop(
                         })
                     } else {
                         // Finished reloading: replenish rounds and return to the previous state
-                        *rounds_in_magazine = cop_type.magazine_capacity();
+                        *rounds_in_magazine = upgrades.magazine_capacity(weapon);
+                        *reserve_magazines -= 1;
+                        StateChange::Exit
+                    }
+                }
+                Some(CopState::Resupplying { waypoint, path }) => {
+                    let delta = *waypoint - entities[index].position;
+
+                    if delta.length_squared() < AMMO_CRATE_REACH_SQUARED {
+                        // Arrived at the station or crate, restock and return to the previous state
+                        if is_at_police_station(*waypoint, &sim_state.police_stations) {
+                            restock_fully(weapon, reserve_magazines);
+                            restock_fully(&holstered_weapon.weapon, &mut holstered_weapon.reserve_magazines);
+                        } else {
+                            match sim_state.ammo_crates.iter_mut()
+                                .find(|ammo_crate| (ammo_crate.position - *waypoint).length_squared() < AMMO_CRATE_REACH_SQUARED) {
+                                Some(ammo_crate) => {
+                                    ammo_crate.restock(weapon, reserve_magazines);
+                                    ammo_crate.restock(&holstered_weapon.weapon, &mut holstered_weapon.reserve_magazines);
+                                }
+                                None => ()
+                            }
+                        }
                         StateChange::Exit
+                    } else {
+                        match navigation.follow_path(entities[index].position, *waypoint, path, ENTITY_RADIUS) {
+                            None => StateChange::Exit,
+                            Some(path) => {
+                                match path.edges.first() {
+                                    None => StateChange::Exit,
+                                    Some(edge) => {
+                                        let next_waypoint = path.edges.get(1).map(|edge| edge.end.pos);
+                                        steer_towards_waypoint(entities, index, edge.end.pos, next_waypoint, COP_MOVEMENT_FORCE, args.dt);
+                                        StateChange::Update(
+                                            CopState::Resupplying { waypoint: *waypoint, path: Some(path) }
+                                        )
+                                    }
+                                }
+                            }
+                        }
                     }
                 }
                 None => {
                     // Reload if you don't have ammo
-                    if *rounds_in_magazine <= 0 {
-                        StateChange::Enter(CopState::Reloading {
-                            reload_time_remaining: cop_type.reload_time() })
+                    if weapon.uses_ammo() && *rounds_in_magazine <= 0 {
+                        if *reserve_magazines > 0 {
+                            StateChange::Enter(CopState::Reloading {
+                                reload_time_remaining: upgrades.reload_time(weapon) })
+                        } else {
+                            // Nothing to reload with, wait for a resupply order
+                            StateChange::Continue
+                        }
                     }
                     // Look for target if you do have ammo
                     else {
-                        let target_index = cop_find_best_target(sim_state, index);
+                        let target_index = cop_find_best_target(sim_state, index, None);
 
                         match target_index {
                             Some(i) => {
-                                let aim_time_distribution = Exp::new(cop_type.aim_time_mean());
                                 StateChange::Enter(CopState::Aiming {
-                                    aim_time_remaining: aim_time_distribution.sample(&mut sim_state.rng),
+                                    aim_time_remaining: sample_aim_time(weapon, &upgrades, &mut sim_state.rng),
                                     target_index: i,
                                 })
                             },
@@ -890,6 +1115,12 @@ fn update_cop(
     }
 }
 
+// How long a cop takes to aim the weapon, which is never quicker than it can be fired
+fn sample_aim_time(weapon: &Weapon, upgrades: &Upgrades, rng: &mut XorShiftRng) -> Scalar {
+    let aim_time_distribution = Exp::new(upgrades.aim_time_mean(weapon));
+    aim_time_distribution.sample(rng).max(weapon.shot_interval())
+}
+
 fn update_zombie(
     args: &UpdateArgs,
     sim_state: &mut State,
@@ -900,6 +1131,9 @@ fn update_zombie(
 
     let entities = &mut sim_state.entities;
     let buildings = &sim_state.buildings;
+    let field_of_view = sim_state.zombie_field_of_view;
+    let zombie_activity = sim_state.day_night.zombie_activity();
+    let movement_force = ZOMBIE_MOVEMENT_FORCE * zombie_activity;
 
     let my_pos = entities[index].position;
 
@@ -914,7 +1148,8 @@ fn update_zombie(
                 return ZombieState::Fighting { punch_time_remaining: ZOMBIE_PUNCH_TIME, target_index }
             }
 
-            entities[index].accelerate_along_vector(delta, args.dt, ZOMBIE_MOVEMENT_FORCE);
+            let direction = zombie_heading(&sim_state.flow_field, my_pos, target_pos);
+            steer_along_vector(entities, index, direction, movement_force, args.dt);
 
             if entities[target_index].is_human() {
                 if delta.length_squared() < ZOMBIE_SIGHT_RADIUS_SQUARE && can_see(buildings,my_pos,target_pos) {
@@ -934,11 +1169,21 @@ fn update_zombie(
             }
         }
         ZombieState::Moving { waypoint } => {
-            match closest_human(my_pos, entities, buildings) {
+            match closest_human(index, entities, buildings, field_of_view) {
                 // Continue moving
                 None => {
                     let delta = waypoint - my_pos;
-                    entities[index].accelerate_along_vector(delta, args.dt, ZOMBIE_MOVEMENT_FORCE);
+
+                    // Head straight for the waypoint when nothing is in the way. The flow field only
+                    // leads to humans, so it can only lead round the buildings when one is there
+                    let direction = if can_see(buildings, my_pos, waypoint) {
+                        delta
+                    } else if sim_state.flow_field.distance(waypoint) <= ZOMBIE_FLOW_FIELD_TOLERANCE {
+                        zombie_heading(&sim_state.flow_field, my_pos, waypoint)
+                    } else {
+                        delta
+                    };
+                    steer_along_vector(entities, index, direction, movement_force, args.dt);
 
                     if delta.length_squared() < COP_MIN_DISTANCE_FROM_WAYPOINT_SQUARED {
                         ZombieState::Roaming {
@@ -952,14 +1197,14 @@ fn update_zombie(
                 // Start chasing nearest human
                 Some(i) => {
                     let delta = entities[i].position - my_pos;
-                    entities[index].accelerate_along_vector(delta, args.dt, ZOMBIE_MOVEMENT_FORCE);
+                    steer_along_vector(entities, index, delta, movement_force, args.dt);
                     ZombieState::Chasing { target_index: i }
                 }
             }
         }
         ZombieState::Roaming { jerk, acceleration } => {
             // Attempt to acquire a target
-            match closest_human(my_pos, entities, buildings) {
+            match closest_human(index, entities, buildings, field_of_view) {
                 None => {
                     let normal = Normal::new(0.0, 5.0);
                     let delta_jerk = Vector2 {
@@ -977,14 +1222,24 @@ fn update_zombie(
                         y: (acceleration.y + new_jerk.y * args.dt).max(-2.0).min(2.0)
                     };
 
-                    entities[index].look_along_vector(new_acceleration, args.dt);
-                    entities[index].velocity += new_acceleration * args.dt / 5.0;
+                    // Wander randomly, but drift along with any nearby zombies and towards any
+                    // humans close enough to pick up the scent of
+                    let mut steering = horde_steering(entities, buildings, &sim_state.flocking, index);
+                    if sim_state.flow_field.distance(my_pos) < ZOMBIE_SCENT_DISTANCE {
+                        if let Some(direction) = sim_state.flow_field.direction(my_pos) {
+                            steering += direction.normalize() * ZOMBIE_SCENT_PULL;
+                        }
+                    }
+                    let acceleration = new_acceleration * zombie_activity / 5.0 + steering;
+
+                    entities[index].look_along_vector(acceleration, args.dt);
+                    entities[index].velocity += acceleration * args.dt;
 
                     ZombieState::Roaming { jerk: new_jerk, acceleration: new_acceleration }
                 },
                 Some(i) => {
                     let delta = entities[i].position - my_pos;
-                    entities[index].accelerate_along_vector(delta, args.dt, ZOMBIE_MOVEMENT_FORCE);
+                    steer_along_vector(entities, index, delta, movement_force, args.dt);
                     ZombieState::Chasing { target_index: i}
                 }
             }
@@ -1055,15 +1310,181 @@ fn update_zombie(
     }
 }
 
-// Get the index of the closest human in line of sight and sight radius
-fn closest_human(my_pos: Vector2, entities: &Vec<Entity>, buildings: &Vec<Polygon>) -> Option<usize> {
+// Which way a zombie goes for a human at the target. It follows the flow field round the
+// buildings, unless the field leads to some other human, which it must when one is nearer than
+// the target is even in a straight line
+fn zombie_heading(flow_field: &FlowField, position: Vector2, target: Vector2) -> Vector2 {
+    let delta = target - position;
+    if flow_field.distance(position) < delta.length() - ZOMBIE_FLOW_FIELD_TOLERANCE {
+        return delta;
+    }
+    flow_field.direction(position).unwrap_or(delta)
+}
+
+// Boids-style steering that draws roaming zombies into packs that drift around buildings
+fn horde_steering(
+    entities: &Vec<Entity>,
+    buildings: &Vec<Polygon>,
+    flocking: &FlockingWeights,
+    index: usize) -> Vector2 {
+
+    let my_pos = entities[index].position;
+    let my_vel = entities[index].velocity;
+
+    let mut neighbour_count = 0;
+    let mut center = Vector2::zero();
+    let mut heading = Vector2::zero();
+    let mut separation = Vector2::zero();
+
+    for i in 0..entities.len() {
+        if i == index || !entities[i].is_zombie() {
+            continue;
+        }
+
+        let delta = entities[i].position - my_pos;
+        let distance_squared = delta.length_squared();
+
+        if distance_squared > HORDE_NEIGHBOUR_RADIUS_SQUARED {
+            continue;
+        }
+
+        neighbour_count += 1;
+        center += entities[i].position;
+        heading += entities[i].velocity;
+
+        // Push away harder the closer the neighbour is
+        if 0.0 < distance_squared && distance_squared < HORDE_SEPARATION_RADIUS_SQUARED {
+            separation -= delta / distance_squared;
+        }
+    }
+
+    let mut steering = Vector2::zero();
+
+    if neighbour_count > 0 {
+        let count = neighbour_count as Scalar;
+        steering += flocking.cohesion * (center / count - my_pos);
+        steering += flocking.alignment * (heading / count - my_vel);
+        steering += flocking.separation * separation;
+    }
+
+    steering += flocking.obstacle_avoidance * obstacle_avoidance(my_pos, my_vel, buildings);
+
+    if steering.length_squared() > HORDE_MAX_STEERING * HORDE_MAX_STEERING {
+        steering = steering.normalize_to(HORDE_MAX_STEERING);
+    }
+
+    steering
+}
+
+// Steer away from the building ahead of an entity, more strongly the closer it is
+fn obstacle_avoidance(position: Vector2, velocity: Vector2, buildings: &Vec<Polygon>) -> Vector2 {
+    if velocity.length_squared() == 0.0 {
+        return Vector2::zero();
+    }
+
+    let feeler = position + velocity.normalize_to(HORDE_OBSTACLE_LOOKAHEAD);
+
+    let mut closest_distance = HORDE_OBSTACLE_LOOKAHEAD;
+    let mut avoidance = Vector2::zero();
+
+    for building in buildings {
+        for intersect in building.intersects(position, feeler) {
+            let distance = (intersect - position).length();
+
+            if distance < closest_distance {
+                closest_distance = distance;
+
+                // Steer along the normal of the edge that is about to be hit
+                let normals = building.normals();
+                let mut edge_distance_squared = INFINITY;
+                for i in 0..building.num_sides() {
+                    let edge = Segment2 {
+                        p1: building.get(i),
+                        p2: building.get((i + 1) % building.num_sides())
+                    };
+                    let distance_i = edge.distance_from_segment_to_point_squared(intersect);
+                    if distance_i < edge_distance_squared {
+                        edge_distance_squared = distance_i;
+                        avoidance = normals[i];
+                    }
+                }
+            }
+        }
+    }
+
+    (1.0 - closest_distance / HORDE_OBSTACLE_LOOKAHEAD) * avoidance
+}
+
+// Send zombies that are not already engaged toward the loudest sound they can hear
+fn zombies_hear_sounds(sim_state: &mut State, sounds: &Vec<Sound>) {
+    if sounds.is_empty() {
+        return;
+    }
+
+    let buildings = &sim_state.buildings;
+
+    for entity in &mut sim_state.entities {
+        let position = entity.position;
+        match &mut entity.dead_or_alive {
+            DeadOrAlive::Alive { zombie_or_human: ZombieOrHuman::Zombie { state, .. }, .. } => {
+                match state {
+                    ZombieState::Roaming { .. } | ZombieState::Moving { .. } => {
+                        match loudest_audible_sound(position, sounds, buildings) {
+                            Some(source) => *state = ZombieState::Moving { waypoint: source },
+                            None => ()
+                        }
+                    }
+                    // Zombies chasing or fighting a human ignore distractions
+                    _ => ()
+                }
+            }
+            _ => ()
+        }
+    }
+}
+
+// Get the position of the sound that is loudest at the listener, if any can be heard.
+// Each building between the listener and the source attenuates the audible radius.
+fn loudest_audible_sound(listener: Vector2, sounds: &Vec<Sound>, buildings: &Vec<Polygon>) -> Option<Vector2> {
+    let mut max_loudness = 0.0;
+    let mut loudest_source = None;
+
+    for sound in sounds {
+        let distance = (sound.position - listener).length();
+        let mut audible_radius = sound.audible_radius;
+
+        if distance >= audible_radius {
+            continue;
+        }
+
+        for building in buildings {
+            if building.num_intersects(listener, sound.position) > 0 {
+                audible_radius *= SOUND_BUILDING_ATTENUATION;
+            }
+        }
+
+        // Loudness falls off linearly from the source to the edge of the audible radius
+        let loudness = 1.0 - distance / audible_radius;
+        if loudness > max_loudness {
+            max_loudness = loudness;
+            loudest_source = Some(sound.position);
+        }
+    }
+
+    loudest_source
+}
+
+// Get the index of the closest human in line of sight, sight radius and field of view
+fn closest_human(index: usize, entities: &Vec<Entity>, buildings: &Vec<Polygon>, field_of_view: Scalar) -> Option<usize> {
     let mut min_distance_sqr = INFINITY;
     let mut closest_index: Option<usize> = None;
+    let my_pos = entities[index].position;
 
     for i in 0..entities.len() {
         if entities[i].is_human() {
             let delta_squared = (my_pos - entities[i].position).length_squared();
             if delta_squared < ZOMBIE_SIGHT_RADIUS_SQUARE &&
+                entities[index].is_in_field_of_view(entities[i].position, field_of_view) &&
                 can_see(buildings, my_pos, entities[i].position) &&
                 delta_squared < min_distance_sqr {
 
@@ -1086,19 +1507,28 @@ fn simulate_human(args: &UpdateArgs,
 
     let mut min_delta = Vector2::zero();
     let mut min_distance_sqr = INFINITY;
+    let sight_radius_squared = sim_state.day_night.sight_radius_squared(HUMAN_SIGHT_RADIUS);
     let entities = &mut sim_state.entities;
     let buildings = &sim_state.buildings;
     let my_pos = entities[index].position;
 
     match state {
-        HumanState::Running => {
+        HumanState::Running | HumanState::Fleeing { .. } => {
+            // Running away turns your back on the zombie, so it's kept track of without having to
+            // be in view until it's out of range
+            let fleeing_from = match state {
+                HumanState::Fleeing { zombie_index } => Some(zombie_index),
+                _ => None
+            };
+
             let mut zombie_index = 0;
             for i in 0..entities.len() {
                 if entities[i].is_zombie() {
                     // Run from zombies
                     let delta = entities[i].position - my_pos;
                     let distance_sqr = delta.length_squared();
-                    if distance_sqr < HUMAN_SIGHT_RADIUS_SQUARE &&
+                    if distance_sqr < sight_radius_squared &&
+                        (fleeing_from == Some(i) || entities[index].is_in_field_of_view(entities[i].position, CIVILIAN_FIELD_OF_VIEW)) &&
                         can_see(buildings, my_pos, entities[i].position) &&
                         distance_sqr < min_distance_sqr {
 
@@ -1119,7 +1549,8 @@ fn simulate_human(args: &UpdateArgs,
             // else run
             if min_distance_sqr < INFINITY {
                 // Accelerate away from the nearest zombie
-                entities[index].accelerate_along_vector(-min_delta, args.dt, CIVILIAN_MOVEMENT_FORCE);
+                steer_along_vector(entities, index, -min_delta, CIVILIAN_MOVEMENT_FORCE, args.dt);
+                return HumanState::Fleeing { zombie_index }
             }
             return HumanState::Running
         }
@@ -1184,3 +1615,120 @@ fn simulate_human(args: &UpdateArgs,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::ai::flow_field::tests::*;
+    use crate::simulation::day_night::*;
+    use crate::simulation::initial_state::initial_state;
+    use crate::simulation::map::*;
+    use crate::simulation::reinforcements::spawn_cop;
+
+    fn zombie_at(position: Vector2) -> Entity {
+        Entity {
+            position,
+            velocity: Vector2::zero(),
+            facing_angle: 0.0,
+            dead_or_alive: DeadOrAlive::Alive {
+                health: ENTITY_HEALTH_MAX,
+                zombie_or_human: ZombieOrHuman::Zombie {
+                    state: ZombieState::Roaming { jerk: Vector2::zero(), acceleration: Vector2::zero() },
+                    left_hand_status: HandStatus::Normal,
+                    right_hand_status: HandStatus::Normal
+                }
+            }
+        }
+    }
+
+    // Broad daylight on the default map, with nobody about
+    fn empty_street() -> State {
+        let mut state = initial_state(&load_map(DEFAULT_MAP_PATH).unwrap(), 10, 0.1, 0.1, 1);
+        state.entities.clear();
+        state.day_night = DayNightCycle::new(12.0, DAY_NIGHT_DEFAULT_CYCLE_LENGTH);
+        state
+    }
+
+    #[test]
+    fn fleeing_civilians_keep_track_of_the_zombie_behind_them() {
+        let mut state = empty_street();
+        // Facing east, with the zombie following behind
+        state.entities.push(civilian_at(vector2(40.0, 35.0)));
+        state.entities.push(zombie_at(vector2(35.0, 35.0)));
+
+        let args = UpdateArgs { dt: 1.0 / 60.0 };
+        // Still getting over the last punch, so it doesn't throw another
+        let (mut cooldown, mut left, mut right) = (PUNCH_TIME_COOLDOWN, HandStatus::Normal, HandStatus::Normal);
+
+        let unaware = simulate_human(&args, &mut state, 0, &mut cooldown, HumanState::Running, &mut left, &mut right);
+        assert!(unaware == HumanState::Running);
+
+        let fleeing = simulate_human(&args, &mut state, 0, &mut cooldown, HumanState::Fleeing { zombie_index: 1 }, &mut left, &mut right);
+        assert!(fleeing == HumanState::Fleeing { zombie_index: 1 });
+        assert!(state.entities[0].velocity.x > 0.0);
+    }
+
+    #[test]
+    fn idle_cops_look_all_around() {
+        let mut state = empty_street();
+        // Facing north, with the zombie coming up behind
+        spawn_cop(&mut state, CopType::Normal, vector2(35.0, 35.0));
+        state.entities.push(zombie_at(vector2(35.0, 25.0)));
+
+        assert_eq!(cop_find_best_target(&mut state, 0, None), Some(1));
+        assert_eq!(cop_find_best_target(&mut state, 0, Some(CopType::Normal.field_of_view())), None);
+    }
+
+    #[test]
+    fn zombies_go_round_walls_to_their_target_only() {
+        let buildings = split_yard();
+        let target = vector2(10.0, 20.0);
+        let zombie = vector2(30.0, 20.0);
+
+        // The wall is in the way, so the field leads up round the end of it
+        let field = finished_field(&buildings, &vec![civilian_at(target)]);
+        assert!(zombie_heading(&field, zombie, target).y > 0.0);
+
+        // With someone else right by the zombie the field leads to them, so it's not followed
+        let field = finished_field(&buildings, &vec![civilian_at(target), civilian_at(vector2(33.0, 20.0))]);
+        assert_eq!(zombie_heading(&field, zombie, target), target - zombie);
+    }
+
+    #[test]
+    fn zombies_head_for_gunshots_they_can_hear() {
+        let mut state = empty_street();
+        // A wall between the gunshot and the zombies to the north
+        state.buildings = vec![Polygon(vec![
+            vector2(-20.0, 10.0), vector2(20.0, 10.0), vector2(20.0, 12.0), vector2(-20.0, 12.0)
+        ])];
+        let gunshot = vector2(0.0, 0.0);
+        let sounds = vec![Sound {
+            position: gunshot,
+            sound_type: SoundType::GunshotHandgun,
+            audible_radius: SoundType::GunshotHandgun.audible_radius(),
+        }];
+
+        // In the open, behind the wall but close, and behind the wall beyond its muffled radius
+        state.entities.push(zombie_at(vector2(30.0, 0.0)));
+        state.entities.push(zombie_at(vector2(0.0, 15.0)));
+        state.entities.push(zombie_at(vector2(0.0, 30.0)));
+        zombies_hear_sounds(&mut state, &sounds);
+
+        let states: Vec<_> = state.entities.iter()
+            .map(|entity| match &entity.dead_or_alive {
+                DeadOrAlive::Alive { zombie_or_human: ZombieOrHuman::Zombie { state, .. }, .. } => state.clone(),
+                _ => panic!("not a zombie"),
+            })
+            .collect();
+        for heard in &states[..2] {
+            match heard {
+                ZombieState::Moving { waypoint } => assert_eq!(*waypoint, gunshot),
+                _ => panic!("zombie in range didn't head for the gunshot"),
+            }
+        }
+        match states[2] {
+            ZombieState::Roaming { .. } => (),
+            _ => panic!("zombie out of range heard the gunshot"),
+        }
+    }
+}
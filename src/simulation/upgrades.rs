@@ -0,0 +1,150 @@
+use crate::core::scalar::*;
+use crate::simulation::state::*;
+use crate::simulation::barricade::BARRICADE_HEALTH;
+use crate::simulation::weapon::Weapon;
+
+// Sprinting cops move this much faster than walking ones before any upgrades
+pub const COP_SPRINT_MULTIPLIER: Scalar = 1.5;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum UpgradeType {
+    AimTime,
+    Accuracy,
+    Reload,
+    Magazine,
+    Barricade,
+    Sprint,
+}
+
+// In the order they are listed in the upgrade panel
+pub const UPGRADE_TYPES: [UpgradeType; 6] = [
+    UpgradeType::AimTime,
+    UpgradeType::Accuracy,
+    UpgradeType::Reload,
+    UpgradeType::Magazine,
+    UpgradeType::Barricade,
+    UpgradeType::Sprint,
+];
+
+impl UpgradeType {
+    pub fn name(self) -> &'static str {
+        match self {
+            UpgradeType::AimTime => "Aim Time",
+            UpgradeType::Accuracy => "Accuracy",
+            UpgradeType::Reload => "Reload",
+            UpgradeType::Magazine => "Magazine",
+            UpgradeType::Barricade => "Barricades",
+            UpgradeType::Sprint => "Sprint",
+        }
+    }
+    pub fn max_level(self) -> u32 {
+        match self {
+            UpgradeType::Magazine => 2,
+            _ => 3,
+        }
+    }
+    // Cost of buying the next level, each level costs more than the last
+    pub fn cost(self, current_level: u32) -> u32 {
+        let base_cost = match self {
+            UpgradeType::AimTime => 20,
+            UpgradeType::Accuracy => 25,
+            UpgradeType::Reload => 20,
+            UpgradeType::Magazine => 30,
+            UpgradeType::Barricade => 15,
+            UpgradeType::Sprint => 15,
+        };
+        base_cost * (current_level + 1)
+    }
+    // The upgrade and level that must be bought before this one is unlocked
+    pub fn prerequisite(self) -> Option<(UpgradeType, u32)> {
+        match self {
+            UpgradeType::Accuracy => Some((UpgradeType::AimTime, 1)),
+            UpgradeType::Magazine => Some((UpgradeType::Reload, 1)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Upgrades {
+    pub aim_time: u32,
+    pub accuracy: u32,
+    pub reload: u32,
+    pub magazine: u32,
+    pub barricade: u32,
+    pub sprint: u32,
+}
+
+impl Upgrades {
+    pub fn level(&self, upgrade_type: UpgradeType) -> u32 {
+        match upgrade_type {
+            UpgradeType::AimTime => self.aim_time,
+            UpgradeType::Accuracy => self.accuracy,
+            UpgradeType::Reload => self.reload,
+            UpgradeType::Magazine => self.magazine,
+            UpgradeType::Barricade => self.barricade,
+            UpgradeType::Sprint => self.sprint,
+        }
+    }
+
+    fn level_mut(&mut self, upgrade_type: UpgradeType) -> &mut u32 {
+        match upgrade_type {
+            UpgradeType::AimTime => &mut self.aim_time,
+            UpgradeType::Accuracy => &mut self.accuracy,
+            UpgradeType::Reload => &mut self.reload,
+            UpgradeType::Magazine => &mut self.magazine,
+            UpgradeType::Barricade => &mut self.barricade,
+            UpgradeType::Sprint => &mut self.sprint,
+        }
+    }
+
+    pub fn set_level(&mut self, upgrade_type: UpgradeType, level: u32) {
+        *self.level_mut(upgrade_type) = level.min(upgrade_type.max_level());
+    }
+
+    pub fn is_unlocked(&self, upgrade_type: UpgradeType) -> bool {
+        match upgrade_type.prerequisite() {
+            None => true,
+            Some((prerequisite, level)) => self.level(prerequisite) >= level,
+        }
+    }
+
+    pub fn can_buy(&self, upgrade_type: UpgradeType, money: u32) -> bool {
+        let level = self.level(upgrade_type);
+        self.is_unlocked(upgrade_type) &&
+            level < upgrade_type.max_level() &&
+            money >= upgrade_type.cost(level)
+    }
+
+    // Weapon stats after applying upgrades to the base values for the weapon
+    pub fn aim_time_mean(&self, weapon: &Weapon) -> Scalar {
+        weapon.aim_time_mean * (1.0 - 0.15 * self.aim_time as Scalar)
+    }
+    pub fn spread(&self, weapon: &Weapon) -> Scalar {
+        weapon.spread * (1.0 - 0.2 * self.accuracy as Scalar)
+    }
+    pub fn reload_time(&self, weapon: &Weapon) -> Scalar {
+        weapon.reload_time * (1.0 - 0.15 * self.reload as Scalar)
+    }
+    pub fn magazine_capacity(&self, weapon: &Weapon) -> i64 {
+        weapon.magazine_capacity * (2 + self.magazine as i64) / 2
+    }
+    pub fn barricade_health(&self) -> Scalar {
+        BARRICADE_HEALTH * (1.0 + 0.5 * self.barricade as Scalar)
+    }
+    pub fn sprint_multiplier(&self) -> Scalar {
+        COP_SPRINT_MULTIPLIER + 0.25 * self.sprint as Scalar
+    }
+}
+
+pub fn buy_upgrade(state: &mut State, upgrade_type: UpgradeType) -> bool {
+    if !state.upgrades.can_buy(upgrade_type, state.money) {
+        return false;
+    }
+
+    let level = state.upgrades.level_mut(upgrade_type);
+    state.money -= upgrade_type.cost(*level);
+    *level += 1;
+
+    true
+}
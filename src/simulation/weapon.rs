@@ -0,0 +1,216 @@
+use crate::core::scalar::*;
+use crate::simulation::state::*;
+use crate::simulation::update::SoundType;
+
+// How a weapon handles and what its shots do. Weapons are plain data, so cops of any type can
+// carry any of them
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Weapon {
+    pub name: &'static str,
+    // Damage of a shot to a body it hits square on, right by the muzzle and at the end of its
+    // range, falling off evenly in between
+    pub damage_near: Scalar,
+    pub damage_far: Scalar,
+    // Furthest a shot carries before it's spent
+    pub range: Scalar,
+    // Standard deviation of how far off the aim each shot goes, in radians
+    pub spread: Scalar,
+    // Shots that go off together each time it's fired, each with its own spread
+    pub pellets: u32,
+    // Most times a second it can be fired, however quickly it's aimed
+    pub rate_of_fire: Scalar,
+    pub aim_time_mean: Scalar,
+    pub reload_time: Scalar,
+    // Weapons with no magazine, like batons, never run out
+    pub magazine_capacity: i64,
+    pub starting_reserve_magazines: i64,
+    pub max_reserve_magazines: i64,
+    // Distance at which zombies can hear it fired in the open
+    pub noise: Scalar,
+    // What it sounds like, if anything
+    pub sound: Option<SoundType>,
+    pub projectile_speed: Scalar,
+    // Depth of body a shot can go through at full energy
+    pub penetration: Scalar,
+}
+
+impl Weapon {
+    pub fn uses_ammo(&self) -> bool {
+        self.magazine_capacity > 0
+    }
+
+    // Damage done by a square hit from a shot that has gone the distance
+    pub fn damage_at(&self, distance: Scalar) -> Scalar {
+        let fraction = (distance / self.range).min(1.0).max(0.0);
+        self.damage_near + (self.damage_far - self.damage_near) * fraction
+    }
+
+    // Furthest the centre of a target can be from the one firing for shots to get to it
+    pub fn reach(&self) -> Scalar {
+        BULLET_SPAWN_DISTANCE_MULTIPLIER * ENTITY_RADIUS + self.range + ENTITY_RADIUS
+    }
+
+    // Least time between one shot and the next
+    pub fn shot_interval(&self) -> Scalar {
+        1.0 / self.rate_of_fire
+    }
+}
+
+pub const PISTOL: Weapon = Weapon {
+    name: "Pistol",
+    damage_near: 1.0,
+    damage_far: 1.0,
+    range: 100.0,
+    spread: 0.01,
+    pellets: 1,
+    rate_of_fire: 2.0,
+    aim_time_mean: 2.0,
+    reload_time: 4.0,
+    magazine_capacity: 6,
+    starting_reserve_magazines: 4,
+    max_reserve_magazines: 6,
+    noise: 45.0,
+    sound: Some(SoundType::GunshotHandgun),
+    projectile_speed: BULLET_SPEED,
+    // Only goes through a glancing hit
+    penetration: 0.4,
+};
+
+pub const RIFLE: Weapon = Weapon {
+    name: "Rifle",
+    damage_near: 1.0,
+    damage_far: 1.0,
+    range: 150.0,
+    spread: 0.005,
+    pellets: 1,
+    rate_of_fire: 4.0,
+    aim_time_mean: 5.0,
+    reload_time: 4.0,
+    magazine_capacity: 20,
+    starting_reserve_magazines: 3,
+    max_reserve_magazines: 5,
+    noise: 80.0,
+    sound: Some(SoundType::GunshotRifle),
+    projectile_speed: BULLET_SPEED,
+    // Can go through someone and hit whoever is behind them
+    penetration: 2.5,
+};
+
+pub const SHOTGUN: Weapon = Weapon {
+    name: "Shotgun",
+    damage_near: 0.6,
+    damage_far: 0.1,
+    range: 20.0,
+    spread: 0.08,
+    pellets: 8,
+    rate_of_fire: 1.0,
+    aim_time_mean: 2.5,
+    reload_time: 5.0,
+    magazine_capacity: 5,
+    starting_reserve_magazines: 4,
+    max_reserve_magazines: 6,
+    noise: 60.0,
+    sound: Some(SoundType::GunshotRifle),
+    projectile_speed: 0.9 * BULLET_SPEED,
+    penetration: 0.2,
+};
+
+pub const BATON: Weapon = Weapon {
+    name: "Baton",
+    damage_near: 1.0,
+    damage_far: 1.0,
+    // Reaches from where the swing starts to about arm's length past the cop
+    range: 1.5 * ENTITY_RADIUS,
+    spread: 0.05,
+    pellets: 1,
+    rate_of_fire: 1.0,
+    aim_time_mean: 0.5,
+    reload_time: 0.0,
+    magazine_capacity: 0,
+    starting_reserve_magazines: 0,
+    max_reserve_magazines: 0,
+    noise: 0.0,
+    sound: None,
+    projectile_speed: 0.5 * BULLET_SPEED,
+    penetration: 0.0,
+};
+
+// A weapon that's put away, with the rounds left in it and the spare magazines for it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HolsteredWeapon {
+    pub weapon: Weapon,
+    pub rounds_in_magazine: i64,
+    pub reserve_magazines: i64,
+}
+
+impl HolsteredWeapon {
+    // Fully loaded, with the magazines it's handed out with
+    pub fn new(weapon: Weapon) -> HolsteredWeapon {
+        HolsteredWeapon {
+            weapon,
+            rounds_in_magazine: weapon.magazine_capacity,
+            reserve_magazines: weapon.starting_reserve_magazines,
+        }
+    }
+}
+
+// Put away the weapon the cop has in hand and draw the holstered one. Each keeps its own rounds
+// and magazines, and anything the cop was in the middle of doing with the old one is dropped
+pub fn swap_weapons(human: &mut Human) {
+    match human {
+        Human::Cop { weapon, rounds_in_magazine, reserve_magazines, holstered_weapon, state_stack, .. } => {
+            let drawn = *holstered_weapon;
+            *holstered_weapon = HolsteredWeapon {
+                weapon: *weapon,
+                rounds_in_magazine: *rounds_in_magazine,
+                reserve_magazines: *reserve_magazines,
+            };
+            *weapon = drawn.weapon;
+            *rounds_in_magazine = drawn.rounds_in_magazine;
+            *reserve_magazines = drawn.reserve_magazines;
+
+            state_stack.retain(|state| match state {
+                CopState::Aiming { .. } | CopState::Reloading { .. } => false,
+                _ => true
+            });
+        }
+        _ => ()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swapping_keeps_each_weapons_ammo() {
+        let mut soldier = Human::Cop {
+            cop_type: CopType::Soldier,
+            weapon: CopType::Soldier.weapon(),
+            rounds_in_magazine: 3,
+            reserve_magazines: 1,
+            holstered_weapon: HolsteredWeapon::new(CopType::Soldier.holstered_weapon()),
+            state_stack: vec![CopState::Reloading { reload_time_remaining: 1.0 }],
+        };
+
+        swap_weapons(&mut soldier);
+        match &soldier {
+            Human::Cop { weapon, rounds_in_magazine, holstered_weapon, state_stack, .. } => {
+                assert_eq!(*weapon, SHOTGUN);
+                assert_eq!(*rounds_in_magazine, SHOTGUN.magazine_capacity);
+                assert_eq!(holstered_weapon.weapon, RIFLE);
+                assert!(state_stack.is_empty());
+            }
+            _ => unreachable!()
+        }
+
+        swap_weapons(&mut soldier);
+        match &soldier {
+            Human::Cop { weapon, rounds_in_magazine, reserve_magazines, .. } => {
+                assert_eq!(*weapon, RIFLE);
+                assert_eq!((*rounds_in_magazine, *reserve_magazines), (3, 1));
+            }
+            _ => unreachable!()
+        }
+    }
+}